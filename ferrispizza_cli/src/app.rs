@@ -1,23 +1,206 @@
+use std::path::Path;
+use std::time::Duration;
+
+#[cfg(feature = "sqlite")]
+use ferrispizza_lib::storage::{SqliteCustomerStore, SqliteOrderRepository};
+#[cfg(not(feature = "sqlite"))]
+use ferrispizza_lib::storage::FileOrderRepository;
 use ferrispizza_lib::{
-    concurrency::{OrderChannel, SharedOrderState},
-    utils::IdGenerator,
-    billing::BillingEngine,
+    concurrency::{Kitchen, KitchenConfig, OrderQueue, PriorityOrderQueue, SharedOrderState},
+    customer::CustomerRegistry,
+    errors::StorageError,
+    inventory::{Inventory, RecipeBook},
+    order::OrderStatus,
+    patterns::{CircuitBreaker, IdempotencyStore},
+    utils::{FileLogger, IdGenerator},
+    billing::{BillingEngine, Coupon, FlatDiscount, LoyaltyLedger, LoyaltyProgram, PercentageDiscount},
+    menu::Menu,
+    money::Money,
 };
 
+use crate::commands::PaymentGuards;
+
+/// Orders allowed to wait for the kitchen before new payments are refused.
+const KITCHEN_QUEUE_CAPACITY: usize = 16;
+
+/// Pizzas the oven can bake at once.
+const OVEN_CAPACITY: usize = 6;
+
+/// Consecutive payment failures before payments pause, and for how long.
+const PAYMENT_FAILURE_THRESHOLD: u32 = 5;
+const PAYMENT_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Directory (relative to the working directory) holding saved orders.
+const DATA_DIR: &str = "ferrispizza_data";
+
+/// Catalog file read from the working directory, if present.
+const MENU_FILE: &str = "menu.toml";
+
+/// Stock each ingredient starts the day with, and its low-stock alert level
+/// (grams).
+const OPENING_STOCK: &[(&str, u32, u32)] = &[
+    ("dough", 10_000, 2_000),
+    ("tomato_sauce", 3_000, 600),
+    ("mozzarella", 6_000, 1_200),
+    ("vegetables", 3_000, 600),
+    ("cheese_sauce", 2_000, 400),
+    ("olives", 1_000, 200),
+    ("jalapenos", 800, 150),
+];
+
 pub struct FerrisPizzaApp {
-    pub order_channel: OrderChannel,
+    pub order_queue: PriorityOrderQueue,
     pub order_state: SharedOrderState,
     pub id_gen: IdGenerator,
     pub billing: BillingEngine,
+    pub menu: Menu,
+    pub customers: CustomerRegistry,
+    pub payments: PaymentGuards,
+    /// Cooks paid orders in the background. Dropping it with the app stops
+    /// after the orders on the workers; the rest resume on the next start.
+    #[allow(dead_code)]
+    pub kitchen: Kitchen,
 }
 
 impl FerrisPizzaApp {
     pub fn new() -> Self {
+        let order_queue = PriorityOrderQueue::new()
+            .with_capacity(KITCHEN_QUEUE_CAPACITY)
+            .with_oven_capacity(OVEN_CAPACITY);
+        let menu = Self::load_menu();
+        let (order_state, next_id) = Self::open_orders();
+        let loyalty = Self::open_loyalty();
+        let order_state = order_state
+            .with_inventory(Self::inventory(&menu))
+            .with_loyalty(loyalty.clone());
+        let kitchen = Kitchen::start(&order_queue, &order_state, KitchenConfig::default());
+        Self::resume_kitchen(&order_state, &order_queue);
         Self {
-            order_channel: OrderChannel::new(),
-            order_state: SharedOrderState::new(),
-            id_gen: IdGenerator::new(),
-            billing: BillingEngine::new(),
+            order_queue,
+            order_state,
+            id_gen: IdGenerator::starting_at(next_id),
+            billing: Self::billing_engine().with_loyalty(loyalty),
+            menu,
+            customers: Self::open_customers(),
+            payments: PaymentGuards {
+                idempotency: IdempotencyStore::new(),
+                upi_breaker: CircuitBreaker::new(PAYMENT_FAILURE_THRESHOLD, PAYMENT_COOLDOWN),
+                card_breaker: CircuitBreaker::new(PAYMENT_FAILURE_THRESHOLD, PAYMENT_COOLDOWN),
+                log_path: Path::new(DATA_DIR).join("payments.log"),
+                unsent: Default::default(),
+            },
+            kitchen,
+        }
+    }
+
+    /// Customers saved under [`DATA_DIR`] (in `orders.db` with the `sqlite`
+    /// feature, `customers.jsonl` otherwise), or an empty in-memory registry
+    /// if the file cannot be used.
+    fn open_customers() -> CustomerRegistry {
+        #[cfg(feature = "sqlite")]
+        let opened = std::fs::create_dir_all(DATA_DIR)
+            .map_err(StorageError::from)
+            .and_then(|_| SqliteCustomerStore::open(Path::new(DATA_DIR).join("orders.db")))
+            .and_then(CustomerRegistry::with_store);
+        #[cfg(not(feature = "sqlite"))]
+        let opened = std::fs::create_dir_all(DATA_DIR)
+            .map_err(StorageError::from)
+            .and_then(|_| CustomerRegistry::open(Path::new(DATA_DIR).join("customers.jsonl")));
+        opened.unwrap_or_else(|e| {
+            eprintln!(" Could not open customers: {} (customers will not be saved)", e);
+            CustomerRegistry::new()
+        })
+    }
+
+    /// Orders saved in [`DATA_DIR`] (`orders.db` with the `sqlite` feature,
+    /// JSON-lines files otherwise) and the next free order id, or an empty
+    /// in-memory store if the directory cannot be used.
+    fn open_orders() -> (SharedOrderState, u64) {
+        #[cfg(feature = "sqlite")]
+        let opened = std::fs::create_dir_all(DATA_DIR)
+            .map_err(StorageError::from)
+            .and_then(|_| SqliteOrderRepository::open(Path::new(DATA_DIR).join("orders.db")));
+        #[cfg(not(feature = "sqlite"))]
+        let opened = FileOrderRepository::open(DATA_DIR);
+
+        match opened {
+            Ok(repo) => {
+                let next_id = repo.max_id().map_or(1, |id| id + 1);
+                (SharedOrderState::with_repository(repo), next_id)
+            }
+            Err(e) => {
+                eprintln!(" Could not open {}: {} (orders will not be saved)", DATA_DIR, e);
+                (SharedOrderState::new(), 1)
+            }
+        }
+    }
+
+    /// Points saved in `loyalty.jsonl` under [`DATA_DIR`], or an empty
+    /// in-memory ledger if the file cannot be used.
+    fn open_loyalty() -> LoyaltyLedger {
+        let opened = std::fs::create_dir_all(DATA_DIR).map_err(StorageError::from).and_then(|_| {
+            LoyaltyLedger::open(LoyaltyProgram::default(), Path::new(DATA_DIR).join("loyalty.jsonl"))
+        });
+        opened.unwrap_or_else(|e| {
+            eprintln!(" Could not open loyalty points: {} (points will not be saved)", e);
+            LoyaltyLedger::new(LoyaltyProgram::default())
+        })
+    }
+
+    /// Requeue orders that were paid but not yet cooked, or only partly
+    /// cooked, before the last exit. `Ready` orders need nothing more from
+    /// the kitchen and are left for pickup.
+    fn resume_kitchen(state: &SharedOrderState, queue: &PriorityOrderQueue) {
+        let mut paid: Vec<_> = state
+            .list_orders()
+            .into_iter()
+            .filter(|o| matches!(o.status, OrderStatus::Paid | OrderStatus::Preparing))
+            .collect();
+        paid.sort_by_key(|o| o.id.0);
+        for order in paid {
+            let id = order.id.0;
+            if let Err(e) = queue.submit(order) {
+                eprintln!(" Could not requeue order {}: {}", id, e);
+            }
+        }
+    }
+
+    /// Billing engine seeded with the standing coupons.
+    fn billing_engine() -> BillingEngine {
+        let billing = BillingEngine::new();
+        billing.coupons().add(
+            Coupon::new("WELCOME10", PercentageDiscount::new(10).with_cap(Money::inr(100)))
+                .per_customer_limit(1),
+        );
+        billing.coupons().add(
+            Coupon::new("FLAT50", FlatDiscount::new(Money::inr(50)))
+                .min_order_value(Money::inr(300)),
+        );
+        billing
+    }
+
+    /// [`OPENING_STOCK`] for the menu's recipes, with low-stock alerts in
+    /// `inventory.log` under [`DATA_DIR`].
+    fn inventory(menu: &Menu) -> Inventory {
+        let log = Path::new(DATA_DIR).join("inventory.log");
+        OPENING_STOCK.iter().fold(
+            Inventory::new(RecipeBook::from_menu(menu)).with_logger(FileLogger::new(log.to_string_lossy())),
+            |inventory, &(ingredient, on_hand, low_at)| {
+                inventory.with_stock(ingredient, on_hand).with_low_stock_at(ingredient, low_at)
+            },
+        )
+    }
+
+    /// `menu.toml` from the working directory, or the built-in menu.
+    fn load_menu() -> Menu {
+        match Menu::load(MENU_FILE) {
+            Ok(menu) => menu,
+            Err(e) => {
+                if std::path::Path::new(MENU_FILE).exists() {
+                    eprintln!(" Could not load {}: {} (using built-in menu)", MENU_FILE, e);
+                }
+                Menu::default()
+            }
         }
     }
 
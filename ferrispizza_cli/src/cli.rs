@@ -1,5 +1,7 @@
 use crate::{commands, printer::print_welcome, input::read_choice};
 use crate::app::FerrisPizzaApp;
+use ferrispizza_lib::menu::MenuSection;
+use ferrispizza_lib::pizza::NutritionFilter;
 
 pub fn run_cli(app: &FerrisPizzaApp) -> Result<(), String> {
     print_welcome();
@@ -10,15 +12,41 @@ pub fn run_cli(app: &FerrisPizzaApp) -> Result<(), String> {
         println!("2) Place Order");
         println!("3) View Order History");
         println!("4) Pay for Order");
-        println!("5) Exit");
+        println!("5) Update Order Status");
+        println!("6) Kitchen Display");
+        println!("7) Register Customer");
+        println!("8) Loyalty Points");
+        println!("9) Refund Order");
+        println!("10) Exit");
 
         let choice = read_choice()?;
         match choice.as_str() {
-            "1" => commands::show_menu(),
+            "1" => {
+                println!("Filter (e.g. vegan no-dairy, blank for everything):");
+                println!("Diets: vegetarian vegan jain | Allergens: no-gluten no-dairy no-nuts");
+                let line = read_choice()?;
+                match NutritionFilter::parse(&line) {
+                    Some(filter) => commands::show_menu(&app.menu, &filter),
+                    None => println!(" Unknown filter: {}", line.trim()),
+                }
+            }
 
             "2" => {
-                println!("Enter pizzas (space separated):");
-                println!("Options: margherita farmhouse thin_margherita cheese_burst_farmhouse");
+                println!("Enter pizzas (space separated): base[:size][+topping[:left|:right]...][@crust] [xN]");
+                println!("Example: farmhouse:large+cheese+olives@thin x2 margherita --express --customer=9876543210");
+                println!("Sizes: small medium (default) large family");
+                let options: Vec<&str> = app
+                    .menu
+                    .available(MenuSection::Base)
+                    .map(|b| b.id.as_str())
+                    .chain(app.menu.presets.iter().map(|p| p.id.as_str()))
+                    .collect();
+                println!("Pizzas: {}", options.join(" "));
+                let ids = |section| {
+                    app.menu.available(section).map(|i| i.id.as_str()).collect::<Vec<_>>().join(" ")
+                };
+                println!("Toppings: {}", ids(MenuSection::Topping));
+                println!("Crusts: {}", ids(MenuSection::Crust));
 
                 let line = read_choice()?;
                 if line.trim().is_empty() {
@@ -31,28 +59,59 @@ pub fn run_cli(app: &FerrisPizzaApp) -> Result<(), String> {
                 let token_ref: Vec<&str> = tokens.iter().map(|s| s.as_str()).collect();
 
                 match commands::Command::parse(&token_ref) {
-                    Some(commands::Command::PlaceOrder { items }) => {
-                        if let Err(e) = commands::place_order(&app.order_state, items) {
-                            eprintln!("Failed: {:?}", e);
+                    Some(commands::Command::PlaceOrder { items, priority, customer }) => {
+                        if let Err(e) = commands::place_order(
+                            &app.order_state,
+                            &app.menu,
+                            &app.id_gen,
+                            items,
+                            priority,
+                            &app.customers,
+                            customer.as_deref(),
+                        ) {
+                            eprintln!(" Failed: {}", e);
                         }
                     }
-                    _ => println!("nvalid pizza names"),
+                    _ => println!(" Invalid order. Use base[:size][+topping[:left|:right]...][@crust] [xN]"),
                 }
             }
 
-            "3" => commands::show_history(&app.order_state),
+            "3" => commands::show_history(&app.order_state, &app.customers),
 
             "4" => {
-                println!("Enter: <order_id> <upi|card>");
-                println!("Example: pay 1 upi");
+                println!("Enter: pay <order_id> <upi|card> [coupon] [--points=N] [--key=K]");
+                println!("Example: pay 1 upi WELCOME10 --points=20 --key=till-1");
+                println!("Or split: split <order_id> <upi|card>[:amount|*shares]... [coupon] [--points=N] [--key=K]");
+                println!("Example: split 1 upi:100 card card");
 
                 let line = read_choice()?;
                 let tokens: Vec<&str> = line.split_whitespace().collect();
 
                 match commands::Command::parse(&tokens) {
-                    Some(commands::Command::Pay { order_id, method }) => {
-                        if let Err(e) = commands::pay_order(&app.order_state, order_id, method) {
-                            eprintln!(" Payment failed: {:?}", e);
+                    Some(commands::Command::Pay { order_id, method, coupon, points, key }) => {
+                        if let Err(e) = commands::pay_order(
+                            &app.order_state,
+                            &app.billing,
+                            &app.payments,
+                            &app.order_queue,
+                            order_id,
+                            method,
+                            commands::PaymentOptions { coupon, points, key },
+                        ) {
+                            eprintln!(" Payment failed: {}", e);
+                        }
+                    }
+                    Some(commands::Command::SplitPay { order_id, legs, coupon, points, key }) => {
+                        if let Err(e) = commands::split_order(
+                            &app.order_state,
+                            &app.billing,
+                            &app.payments,
+                            &app.order_queue,
+                            order_id,
+                            legs,
+                            commands::PaymentOptions { coupon, points, key },
+                        ) {
+                            eprintln!(" Payment failed: {}", e);
                         }
                     }
                     _ => println!(" Invalid payment input"),
@@ -60,6 +119,75 @@ pub fn run_cli(app: &FerrisPizzaApp) -> Result<(), String> {
             }
 
             "5" => {
+                println!("Enter: <prepare|ready|complete|cancel> <order_id>");
+                println!("Example: prepare 1");
+
+                let line = read_choice()?;
+                let tokens: Vec<&str> = line.split_whitespace().collect();
+
+                match commands::Command::parse(&tokens) {
+                    Some(commands::Command::UpdateStatus { order_id, status }) => {
+                        if let Err(e) = commands::update_status(&app.order_state, order_id, status) {
+                            eprintln!(" Failed: {}", e);
+                        }
+                    }
+                    _ => println!(" Invalid status input"),
+                }
+            }
+
+            "6" => commands::show_kitchen(&app.order_queue, &app.order_state),
+
+            "7" => {
+                println!("Enter: register <phone> <name> [--upi|--card]");
+                println!("Example: register 9876543210 Asha Rao --upi");
+
+                let line = read_choice()?;
+                let tokens: Vec<&str> = line.split_whitespace().collect();
+
+                match commands::Command::parse(&tokens) {
+                    Some(commands::Command::RegisterCustomer { phone, name, payment }) => {
+                        if let Err(e) = commands::register_customer(&app.customers, &phone, &name, payment) {
+                            eprintln!(" Failed: {}", e);
+                        }
+                    }
+                    _ => println!(" Invalid registration input"),
+                }
+            }
+
+            "8" => {
+                println!("Enter: points <phone|customer_id>");
+
+                let line = read_choice()?;
+                let tokens: Vec<&str> = line.split_whitespace().collect();
+
+                match (commands::Command::parse(&tokens), app.billing.loyalty()) {
+                    (Some(commands::Command::Points { customer }), Some(ledger)) => {
+                        if let Err(e) = commands::show_points(&app.customers, ledger, &customer) {
+                            eprintln!(" Failed: {}", e);
+                        }
+                    }
+                    _ => println!(" Invalid points input"),
+                }
+            }
+
+            "9" => {
+                println!("Enter: refund <order_id> [amount | --lines=N,M]");
+                println!("Example: refund 1 --lines=2 (no amount refunds everything left, to the methods that paid)");
+
+                let line = read_choice()?;
+                let tokens: Vec<&str> = line.split_whitespace().collect();
+
+                match commands::Command::parse(&tokens) {
+                    Some(commands::Command::Refund { order_id, method, amount, lines }) => {
+                        if let Err(e) = commands::refund_order(&app.order_state, &app.billing, order_id, method, amount, lines) {
+                            eprintln!(" Refund failed: {}", e);
+                        }
+                    }
+                    _ => println!(" Invalid refund input"),
+                }
+            }
+
+            "10" => {
                 println!(" Goodbye!");
                 return Ok(());
             }
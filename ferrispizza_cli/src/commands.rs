@@ -8,11 +8,15 @@
 //! CLI runner module.
 //!
 //! # Example
-//! ```
-//! use pizza_store::cli::command::{Command, PizzaSelection};
+//! The CLI is a binary crate, so its examples are not run as doctests.
+//! ```ignore
+//! use crate::commands::{Command, PizzaSelection};
+//! use ferrispizza_lib::order::OrderPriority;
 //!
 //! let cmd = Command::PlaceOrder {
-//!     items: vec![PizzaSelection::Margherita],
+//!     items: vec![PizzaSelection::new("margherita")],
+//!     priority: OrderPriority::Normal,
+//!     customer: None,
 //! };
 //! ```
 
@@ -21,21 +25,162 @@
 mod menu;
 mod order;
 mod history;
+mod kitchen;
 mod pay;
+mod status;
+mod customer;
+mod loyalty;
+mod refund;
+mod split;
 
 pub use menu::show_menu;
 pub use order::place_order;
 pub use history::show_history;
-pub use pay::pay_order;
+pub use kitchen::show_kitchen;
+pub use pay::{pay_order, PaymentGuards, PaymentOptions};
+pub use status::update_status;
+pub use customer::register_customer;
+pub use loyalty::show_points;
+pub use refund::refund_order;
+pub use split::split_order;
+
+use ferrispizza_lib::money::{Currency, Money};
+use ferrispizza_lib::order::OrderPriority;
+use ferrispizza_lib::patterns::Allocation;
+use ferrispizza_lib::pizza::{Placement, Size};
+
 
+/// Most copies of one pizza accepted in a single order line.
+pub const MAX_QUANTITY: u32 = 20;
+
+/// A pizza the user asked for, written as
+/// `base[:size][+topping[:left|:right]...][@crust] [xN]`.
+///
+/// `base` may be a plain base or a preset. Ids are checked against the
+/// [`Menu`](ferrispizza_lib::menu::Menu) when the order is placed, so new
+/// menu items need no parser changes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PizzaSelection {
+    pub base: String,
+    pub size: Size,
+    pub crust: Option<String>,
+    pub toppings: Vec<ToppingSelection>,
+    pub quantity: u32,
+}
 
-/// Represents pizzas user can select through CLI.
+/// A topping id and the part of the pizza it goes on.
 #[derive(Debug, Clone, PartialEq)]
-pub enum PizzaSelection {
-    Margherita,
-    Farmhouse,
-    ThinMargherita,
-    CheeseBurstFarmhouse,
+pub struct ToppingSelection {
+    pub id: String,
+    pub placement: Placement,
+}
+
+impl ToppingSelection {
+    /// Parse `id`, `id:left` or `id:right` (or `:l` / `:r`).
+    fn parse(token: &str) -> Option<Self> {
+        let (id, placement) = match token.split_once(':') {
+            Some((id, side)) => (id, Placement::parse(side)?),
+            None => (token, Placement::Whole),
+        };
+        is_id(id).then(|| Self { id: id.to_string(), placement })
+    }
+}
+
+impl From<&str> for ToppingSelection {
+    /// A topping over the whole pizza.
+    fn from(id: &str) -> Self {
+        Self { id: id.to_string(), placement: Placement::Whole }
+    }
+}
+
+impl PizzaSelection {
+    /// A single plain medium pizza.
+    pub fn new(base: impl Into<String>) -> Self {
+        Self {
+            base: base.into(),
+            size: Size::default(),
+            crust: None,
+            toppings: Vec::new(),
+            quantity: 1,
+        }
+    }
+
+    /// Parse one `base:size+topping+topping@crust` token (quantity 1).
+    ///
+    /// The size is a name or its first letter and defaults to medium. A
+    /// topping followed by `:left` or `:right` covers that half only.
+    ///
+    /// # Example
+    /// ```ignore
+    /// use crate::commands::PizzaSelection;
+    /// use ferrispizza_lib::pizza::Size;
+    ///
+    /// let p = PizzaSelection::parse("farmhouse:l+cheese+olives@thin").unwrap();
+    /// assert_eq!(p.base, "farmhouse");
+    /// assert_eq!(p.size, Size::Large);
+    /// let toppings: Vec<&str> = p.toppings.iter().map(|t| t.id.as_str()).collect();
+    /// assert_eq!(toppings, ["cheese", "olives"]);
+    /// assert_eq!(p.crust.as_deref(), Some("thin"));
+    /// ```
+    pub fn parse(token: &str) -> Option<Self> {
+        let token = token.to_ascii_lowercase();
+        let (pizza, crust) = match token.split_once('@') {
+            Some((pizza, crust)) if is_id(crust) => (pizza, Some(crust.to_string())),
+            Some(_) => return None,
+            None => (token.as_str(), None),
+        };
+
+        let mut parts = pizza.split('+');
+        let first = parts.next()?;
+        let (base, size) = match first.split_once(':') {
+            Some((base, size)) => (base, Size::parse(size)?),
+            None => (first, Size::default()),
+        };
+        if !is_id(base) {
+            return None;
+        }
+        let toppings = parts.map(ToppingSelection::parse).collect::<Option<Vec<_>>>()?;
+
+        Some(Self { size, crust, toppings, ..Self::new(base) })
+    }
+}
+
+/// Menu ids are non-empty and contain no grammar characters.
+fn is_id(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Recognise an `xN` quantity token. `Some(None)` means it is a quantity
+/// but out of range.
+fn parse_quantity(token: &str) -> Option<Option<u32>> {
+    let digits = token.strip_prefix(['x', 'X'])?;
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let n = digits.parse().ok().filter(|n| (1..=MAX_QUANTITY).contains(n));
+    Some(n)
+}
+
+/// Recognise a split payment leg: `upi` or `card`, alone for one share,
+/// with `*N` for N shares or with `:amount`. Anything else, such as a
+/// `CARD10` coupon, is not a leg. `Some(None)` means it is a leg but
+/// malformed.
+fn parse_leg(token: &str) -> Option<Option<(PaymentMethodCommand, Allocation)>> {
+    let (keyword, rest) = token.split_at(token.find(['*', ':']).unwrap_or(token.len()));
+    let method = match keyword {
+        "upi" => PaymentMethodCommand::Upi,
+        "card" => PaymentMethodCommand::Card,
+        _ => return None,
+    };
+    let allocation = if rest.is_empty() {
+        Some(Allocation::Share(1))
+    } else if let Some(n) = rest.strip_prefix('*') {
+        n.parse().ok().filter(|&n| n > 0).map(Allocation::Share)
+    } else {
+        let amount = rest.strip_prefix(':')?;
+        Money::parse(amount, Currency::Inr).ok().map(Allocation::Amount)
+    };
+    Some(allocation.map(|a| (method, a)))
 }
 
 /// Commands supported by CLI.
@@ -44,25 +189,79 @@ pub enum Command {
     /// Show menu
     Menu,
 
-    /// Place an order with selected pizzas
+    /// Place an order with selected pizzas, optionally for a registered
+    /// customer given by phone or id
     PlaceOrder {
         items: Vec<PizzaSelection>,
+        priority: OrderPriority,
+        customer: Option<String>,
+    },
+
+    /// Register a customer, optionally saving how they like to pay
+    RegisterCustomer {
+        phone: String,
+        name: String,
+        payment: Option<PaymentMethodCommand>,
     },
 
-    /// Select payment method and pay for an order
+    /// Select payment method and pay for an order, optionally with a coupon,
+    /// loyalty points and an idempotency key
     Pay {
         order_id: u64,
         method: PaymentMethodCommand,
+        coupon: Option<String>,
+        points: u32,
+        key: Option<String>,
+    },
+
+    /// Split an order's payment across methods, each paying a fixed amount
+    /// or a share of what the amounts leave, optionally with an idempotency
+    /// key
+    SplitPay {
+        order_id: u64,
+        legs: Vec<(PaymentMethodCommand, Allocation)>,
+        coupon: Option<String>,
+        points: u32,
+        key: Option<String>,
+    },
+
+    /// Show a customer's loyalty points, given by phone or id
+    Points { customer: String },
+
+    /// Refund an amount, some order lines (positions from 0), or with
+    /// neither everything left on the payment. Money goes back to the
+    /// methods that paid; `method` is only needed for payments saved
+    /// before those were recorded
+    Refund {
+        order_id: u64,
+        method: Option<PaymentMethodCommand>,
+        amount: Option<Money>,
+        lines: Vec<usize>,
+    },
+
+    /// Move an order to the next lifecycle stage
+    UpdateStatus {
+        order_id: u64,
+        status: StatusCommand,
     },
 
     /// Exit application
     Exit,
 }
 
+/// CLI-level representation of order lifecycle stages staff can set
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatusCommand {
+    Preparing,
+    Ready,
+    Completed,
+    Cancelled,
+}
+
 /// CLI-level representation of payment methods
 #[derive(Debug, Clone, PartialEq)]
 pub enum PaymentMethodCommand {
-    UPI,
+    Upi,
     Card,
 }
 
@@ -76,43 +275,167 @@ impl Command {
     /// A parsed command or `None` if input is invalid.
     ///
     /// # Example
-    /// ```
-    /// use pizza_store::cli::command::{Command, PizzaSelection};
+    /// ```ignore
+    /// use crate::commands::{Command, PizzaSelection};
+    /// use ferrispizza_lib::order::OrderPriority;
     ///
     /// let cmd = Command::parse(&["order", "margherita"]);
     /// assert_eq!(
     ///     cmd,
-    ///     Some(Command::PlaceOrder { items: vec![PizzaSelection::Margherita] })
+    ///     Some(Command::PlaceOrder {
+    ///         items: vec![PizzaSelection::new("margherita")],
+    ///         priority: OrderPriority::Normal,
+    ///         customer: None,
+    ///     })
     /// );
     /// ```
     pub fn parse(tokens: &[&str]) -> Option<Self> {
-        match tokens.get(0)? {
-            &"menu" => Some(Command::Menu),
-            &"exit" => Some(Command::Exit),
+        match *tokens.first()? {
+            "menu" => Some(Command::Menu),
+            "exit" => Some(Command::Exit),
 
-            &"order" => {
-                let mut items = vec![];
+            "order" => {
+                let mut items: Vec<PizzaSelection> = vec![];
+                let mut priority = OrderPriority::Normal;
+                let mut customer = None;
                 for t in &tokens[1..] {
-                    let p = match *t {
-                        "margherita" => PizzaSelection::Margherita,
-                        "farmhouse" => PizzaSelection::Farmhouse,
-                        "thin_margherita" => PizzaSelection::ThinMargherita,
-                        "cheese_burst_farmhouse" => PizzaSelection::CheeseBurstFarmhouse,
-                        _ => return None,
-                    };
-                    items.push(p);
+                    if let Some(key) = t.strip_prefix("--customer=") {
+                        if key.is_empty() {
+                            return None;
+                        }
+                        customer = Some(key.to_string());
+                    } else if let Some(flag) = t.strip_prefix("--") {
+                        priority = match flag {
+                            "express" => OrderPriority::Express,
+                            "vip" => OrderPriority::Vip,
+                            _ => return None,
+                        };
+                    } else if let Some(quantity) = parse_quantity(t) {
+                        // Applies to the pizza just before it, once
+                        let last = items.last_mut().filter(|p| p.quantity == 1)?;
+                        last.quantity = quantity?;
+                    } else {
+                        items.push(PizzaSelection::parse(t)?);
+                    }
+                }
+                if items.is_empty() {
+                    return None;
                 }
-                Some(Command::PlaceOrder { items })
+                Some(Command::PlaceOrder { items, priority, customer })
             }
 
-            &"pay" => {
+            "register" => {
+                let phone = tokens.get(1)?.to_string();
+                let mut payment = None;
+                let mut name = Vec::new();
+                for t in &tokens[2..] {
+                    match *t {
+                        "--upi" => payment = Some(PaymentMethodCommand::Upi),
+                        "--card" => payment = Some(PaymentMethodCommand::Card),
+                        _ if t.starts_with("--") => return None,
+                        _ => name.push(*t),
+                    }
+                }
+                if name.is_empty() {
+                    return None;
+                }
+                Some(Command::RegisterCustomer { phone, name: name.join(" "), payment })
+            }
+
+            "pay" => {
                 let id = tokens.get(1)?.parse().ok()?;
                 let method = match *tokens.get(2)? {
-                    "upi" => PaymentMethodCommand::UPI,
+                    "upi" => PaymentMethodCommand::Upi,
                     "card" => PaymentMethodCommand::Card,
                     _ => return None,
                 };
-                Some(Command::Pay { order_id: id, method })
+                let mut coupon = None;
+                let mut points = 0;
+                let mut key = None;
+                for t in &tokens[3..] {
+                    if let Some(n) = t.strip_prefix("--points=") {
+                        points = n.parse().ok()?;
+                    } else if let Some(k) = t.strip_prefix("--key=") {
+                        if k.is_empty() {
+                            return None;
+                        }
+                        key = Some(k.to_string());
+                    } else if t.starts_with("--") || coupon.is_some() {
+                        return None;
+                    } else {
+                        coupon = Some(t.to_string());
+                    }
+                }
+                Some(Command::Pay { order_id: id, method, coupon, points, key })
+            }
+
+            "split" => {
+                let id = tokens.get(1)?.parse().ok()?;
+                let mut legs = Vec::new();
+                let mut coupon = None;
+                let mut points = 0;
+                let mut key = None;
+                for t in &tokens[2..] {
+                    if let Some(n) = t.strip_prefix("--points=") {
+                        points = n.parse().ok()?;
+                    } else if let Some(k) = t.strip_prefix("--key=") {
+                        if k.is_empty() {
+                            return None;
+                        }
+                        key = Some(k.to_string());
+                    } else if let Some(leg) = parse_leg(t) {
+                        legs.push(leg?);
+                    } else if t.starts_with("--") || coupon.is_some() {
+                        return None;
+                    } else {
+                        coupon = Some(t.to_string());
+                    }
+                }
+                if legs.len() < 2 {
+                    return None;
+                }
+                Some(Command::SplitPay { order_id: id, legs, coupon, points, key })
+            }
+
+            "points" => Some(Command::Points { customer: tokens.get(1)?.to_string() }),
+
+            "refund" => {
+                let id = tokens.get(1)?.parse().ok()?;
+                let method = match tokens.get(2).copied() {
+                    Some("upi") => Some(PaymentMethodCommand::Upi),
+                    Some("card") => Some(PaymentMethodCommand::Card),
+                    _ => None,
+                };
+                let mut amount = None;
+                let mut lines = Vec::new();
+                let rest = if method.is_some() { 3 } else { 2 };
+                for t in &tokens[rest..] {
+                    if let Some(list) = t.strip_prefix("--lines=") {
+                        // Typed from 1, like the kitchen ticket
+                        for n in list.split(',') {
+                            lines.push(n.parse::<usize>().ok()?.checked_sub(1)?);
+                        }
+                    } else if amount.is_none() {
+                        amount = Some(Money::parse(t, Currency::Inr).ok()?);
+                    } else {
+                        return None;
+                    }
+                }
+                if amount.is_some() && !lines.is_empty() {
+                    return None;
+                }
+                Some(Command::Refund { order_id: id, method, amount, lines })
+            }
+
+            "prepare" | "ready" | "complete" | "cancel" => {
+                let status = match tokens[0] {
+                    "prepare" => StatusCommand::Preparing,
+                    "ready" => StatusCommand::Ready,
+                    "complete" => StatusCommand::Completed,
+                    _ => StatusCommand::Cancelled,
+                };
+                let id = tokens.get(1)?.parse().ok()?;
+                Some(Command::UpdateStatus { order_id: id, status })
             }
 
             _ => None,
@@ -139,19 +462,143 @@ mod tests {
         let parsed = Command::parse(&["order", "margherita"]);
         assert_eq!(
             parsed,
-            Some(Command::PlaceOrder { items: vec![PizzaSelection::Margherita] })
+            Some(Command::PlaceOrder {
+                items: vec![PizzaSelection::new("margherita")],
+                priority: OrderPriority::Normal,
+                customer: None,
+            })
         );
     }
 
+    #[test]
+    fn test_parse_priority_flags() {
+        let parsed = Command::parse(&["order", "--vip", "margherita"]);
+        assert_eq!(
+            parsed,
+            Some(Command::PlaceOrder {
+                items: vec![PizzaSelection::new("margherita")],
+                priority: OrderPriority::Vip,
+                customer: None,
+            })
+        );
+        assert_eq!(Command::parse(&["order", "margherita", "--urgent"]), None);
+    }
+
     #[test]
     fn test_parse_multi_pizza_order() {
         let parsed = Command::parse(&["order", "margherita", "farmhouse"]);
         assert_eq!(
             parsed,
             Some(Command::PlaceOrder {
-                items: vec![PizzaSelection::Margherita, PizzaSelection::Farmhouse]
+                items: vec![
+                    PizzaSelection::new("margherita"),
+                    PizzaSelection::new("farmhouse"),
+                ],
+                priority: OrderPriority::Normal,
+                customer: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_order_accepts_any_menu_id() {
+        let parsed = Command::parse(&["order", "Paneer_Tikka"]);
+        assert_eq!(
+            parsed,
+            Some(Command::PlaceOrder {
+                items: vec![PizzaSelection::new("paneer_tikka")],
+                priority: OrderPriority::Normal,
+                customer: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_custom_pizza_with_quantity() {
+        let parsed = Command::parse(&["order", "farmhouse+cheese+olives@thin", "x2", "margherita"]);
+        assert_eq!(
+            parsed,
+            Some(Command::PlaceOrder {
+                items: vec![
+                    PizzaSelection {
+                        base: "farmhouse".into(),
+                        size: Size::Medium,
+                        crust: Some("thin".into()),
+                        toppings: vec!["cheese".into(), "olives".into()],
+                        quantity: 2,
+                    },
+                    PizzaSelection::new("margherita"),
+                ],
+                priority: OrderPriority::Normal,
+                customer: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_sizes() {
+        let large = PizzaSelection::parse("Margherita:L+cheese").unwrap();
+        assert_eq!((large.base.as_str(), large.size), ("margherita", Size::Large));
+        assert_eq!(PizzaSelection::parse("farmhouse:family@thin").unwrap().size, Size::Family);
+        assert_eq!(PizzaSelection::parse("farmhouse:huge"), None);
+        assert_eq!(PizzaSelection::parse("farmhouse:"), None);
+        assert_eq!(PizzaSelection::parse(":l"), None);
+    }
+
+    #[test]
+    fn test_parse_half_toppings() {
+        let p = PizzaSelection::parse("margherita:l+cheese+jalapenos:left+olives:R@thin").unwrap();
+        assert_eq!(
+            p.toppings,
+            vec![
+                ToppingSelection::from("cheese"),
+                ToppingSelection { id: "jalapenos".into(), placement: Placement::Left },
+                ToppingSelection { id: "olives".into(), placement: Placement::Right },
+            ]
+        );
+        assert_eq!(PizzaSelection::parse("margherita+olives:top"), None);
+        assert_eq!(PizzaSelection::parse("margherita+:left"), None);
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed_orders() {
+        assert_eq!(Command::parse(&["order"]), None);
+        assert_eq!(Command::parse(&["order", "x2"]), None);
+        assert_eq!(Command::parse(&["order", "margherita", "x0"]), None);
+        assert_eq!(Command::parse(&["order", "margherita", "x2", "x3"]), None);
+        assert_eq!(Command::parse(&["order", "margherita", "x99"]), None);
+        assert_eq!(Command::parse(&["order", "margherita+"]), None);
+        assert_eq!(Command::parse(&["order", "+cheese"]), None);
+        assert_eq!(Command::parse(&["order", "margherita@"]), None);
+        assert_eq!(Command::parse(&["order", "margherita@thin@thin"]), None);
+    }
+
+    #[test]
+    fn test_parse_order_for_customer() {
+        let parsed = Command::parse(&["order", "margherita", "--customer=9876543210", "--express"]);
+        assert_eq!(
+            parsed,
+            Some(Command::PlaceOrder {
+                items: vec![PizzaSelection::new("margherita")],
+                priority: OrderPriority::Express,
+                customer: Some("9876543210".into()),
             })
         );
+        assert_eq!(Command::parse(&["order", "margherita", "--customer="]), None);
+    }
+
+    #[test]
+    fn test_parse_register_customer() {
+        assert_eq!(
+            Command::parse(&["register", "9876543210", "Asha", "Rao", "--upi"]),
+            Some(Command::RegisterCustomer {
+                phone: "9876543210".into(),
+                name: "Asha Rao".into(),
+                payment: Some(PaymentMethodCommand::Upi),
+            })
+        );
+        assert_eq!(Command::parse(&["register", "9876543210"]), None);
+        assert_eq!(Command::parse(&["register", "9876543210", "Asha", "--cash"]), None);
     }
 
     #[test]
@@ -159,8 +606,124 @@ mod tests {
         let parsed = Command::parse(&["pay", "1", "upi"]);
         assert_eq!(
             parsed,
-            Some(Command::Pay { order_id: 1, method: PaymentMethodCommand::UPI })
+            Some(Command::Pay { order_id: 1, method: PaymentMethodCommand::Upi, coupon: None, points: 0, key: None })
+        );
+    }
+
+    #[test]
+    fn test_parse_payment_with_coupon() {
+        let parsed = Command::parse(&["pay", "2", "card", "WELCOME10"]);
+        assert_eq!(
+            parsed,
+            Some(Command::Pay {
+                order_id: 2,
+                method: PaymentMethodCommand::Card,
+                coupon: Some("WELCOME10".into()),
+                points: 0,
+                key: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_payment_with_points() {
+        assert_eq!(
+            Command::parse(&["pay", "2", "upi", "--points=40", "FLAT50"]),
+            Some(Command::Pay {
+                order_id: 2,
+                method: PaymentMethodCommand::Upi,
+                coupon: Some("FLAT50".into()),
+                points: 40,
+                key: None,
+            })
+        );
+        assert_eq!(Command::parse(&["pay", "2", "upi", "--points=lots"]), None);
+        assert_eq!(Command::parse(&["pay", "2", "upi", "FLAT50", "WELCOME10"]), None);
+        assert_eq!(Command::parse(&["points", "9876543210"]), Some(Command::Points { customer: "9876543210".into() }));
+    }
+
+    #[test]
+    fn test_parse_payment_with_key() {
+        assert_eq!(
+            Command::parse(&["pay", "4", "card", "--key=till-2"]),
+            Some(Command::Pay {
+                order_id: 4,
+                method: PaymentMethodCommand::Card,
+                coupon: None,
+                points: 0,
+                key: Some("till-2".into()),
+            })
+        );
+        assert_eq!(Command::parse(&["pay", "4", "card", "--key="]), None);
+    }
+
+    #[test]
+    fn test_parse_split_payment() {
+        assert_eq!(
+            Command::parse(&["split", "5", "upi:60", "card*2", "card", "FLAT50", "--key=till-1"]),
+            Some(Command::SplitPay {
+                order_id: 5,
+                legs: vec![
+                    (PaymentMethodCommand::Upi, Allocation::Amount(Money::from_minor(6_000, Currency::Inr))),
+                    (PaymentMethodCommand::Card, Allocation::Share(2)),
+                    (PaymentMethodCommand::Card, Allocation::Share(1)),
+                ],
+                coupon: Some("FLAT50".into()),
+                points: 0,
+                key: Some("till-1".into()),
+            })
+        );
+        assert_eq!(Command::parse(&["split", "5", "upi"]), None);
+        assert_eq!(Command::parse(&["split", "5", "upi", "card*0"]), None);
+        assert_eq!(Command::parse(&["split", "5", "upi", "card:lots"]), None);
+    }
+
+    #[test]
+    fn test_parse_split_coupons_that_look_like_legs() {
+        for code in ["CARD10", "card10", "upiday", "cardholder*2"] {
+            let Some(Command::SplitPay { legs, coupon, .. }) = Command::parse(&["split", "5", "upi", "card", code])
+            else {
+                panic!("{} was not taken as a coupon", code);
+            };
+            assert_eq!(legs.len(), 2);
+            assert_eq!(coupon.as_deref(), Some(code));
+        }
+    }
+
+    #[test]
+    fn test_parse_refunds() {
+        assert_eq!(
+            Command::parse(&["refund", "3", "card", "--lines=1,3"]),
+            Some(Command::Refund { order_id: 3, method: Some(PaymentMethodCommand::Card), amount: None, lines: vec![0, 2] })
+        );
+        assert_eq!(
+            Command::parse(&["refund", "3", "40.50"]),
+            Some(Command::Refund {
+                order_id: 3,
+                method: None,
+                amount: Some(Money::from_minor(4_050, Currency::Inr)),
+                lines: vec![],
+            })
+        );
+        assert_eq!(
+            Command::parse(&["refund", "3"]),
+            Some(Command::Refund { order_id: 3, method: None, amount: None, lines: vec![] })
+        );
+        assert_eq!(Command::parse(&["refund", "3", "upi", "40", "--lines=1"]), None);
+        assert_eq!(Command::parse(&["refund", "3", "upi", "--lines=0"]), None);
+    }
+
+    #[test]
+    fn test_parse_status_updates() {
+        assert_eq!(
+            Command::parse(&["complete", "3"]),
+            Some(Command::UpdateStatus { order_id: 3, status: StatusCommand::Completed })
+        );
+        assert_eq!(
+            Command::parse(&["prepare", "4"]),
+            Some(Command::UpdateStatus { order_id: 4, status: StatusCommand::Preparing })
         );
+        assert_eq!(Command::parse(&["cancel", "x"]), None);
     }
 
     #[test]
@@ -0,0 +1,23 @@
+//! Register a customer via CLI
+
+use crate::commands::PaymentMethodCommand;
+use ferrispizza_lib::customer::{CustomerRegistry, PaymentMethod};
+use ferrispizza_lib::errors::OrderError;
+
+pub fn register_customer(
+    registry: &CustomerRegistry,
+    phone: &str,
+    name: &str,
+    payment: Option<PaymentMethodCommand>,
+) -> Result<(), OrderError> {
+    let mut customer = registry.register(name, phone)?;
+    if let Some(method) = payment {
+        customer = customer.with_preferred_payment(match method {
+            PaymentMethodCommand::Upi => PaymentMethod::Upi,
+            PaymentMethodCommand::Card => PaymentMethod::Card,
+        });
+        registry.save(customer.clone())?;
+    }
+    println!(" Registered {} ({}) as customer {}.", customer.name, customer.phone, customer.id.0);
+    Ok(())
+}
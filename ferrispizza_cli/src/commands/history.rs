@@ -1,15 +1,39 @@
 //! Show order history
 
 use ferrispizza_lib::concurrency::SharedOrderState;
+use ferrispizza_lib::customer::CustomerRegistry;
+use ferrispizza_lib::order::OrderStatus;
 
-pub fn show_history(state: &SharedOrderState) {
+pub fn show_history(state: &SharedOrderState, customers: &CustomerRegistry) {
     println!(" Order History:");
 
-    for order in state.list_orders() {
+    let mut orders = state.list_orders();
+    orders.sort_by_key(|o| o.id.0);
+
+    for order in orders {
+        let paid = match &order.receipt {
+            Some(receipt) => format!(" | paid {}", receipt.total_amount),
+            None => String::new(),
+        };
+        let progress = match order.status {
+            OrderStatus::Preparing => format!(" ({}/{} cooked)", order.pizzas_ready, order.pizzas.len()),
+            _ => String::new(),
+        };
+        let who = match order.customer {
+            Some(id) => match customers.get(id) {
+                Some(c) => format!(" | {} ({})", c.name, c.phone),
+                None => format!(" | customer {}", id.0),
+            },
+            None => String::new(),
+        };
         println!(
-            " - Order #{} | {} pizzas",
+            " - Order #{}{} | {} pizzas | {:?}{}{}",
             order.id.0,
-            order.pizzas.len()
+            who,
+            order.pizzas.len(),
+            order.status,
+            progress,
+            paid
         );
     }
 }
@@ -0,0 +1,43 @@
+//! Show the kitchen display: oven load, low stock and queued orders in
+//! cooking order, each with a ticket per pizza
+
+use ferrispizza_lib::concurrency::{PriorityOrderQueue, SharedOrderState};
+
+pub fn show_kitchen(queue: &PriorityOrderQueue, state: &SharedOrderState) {
+    match queue.oven_capacity() {
+        Some(cap) => println!(" Oven: {}/{} pizzas", queue.in_oven(), cap),
+        None => println!(" Oven: {} pizzas", queue.in_oven()),
+    }
+
+    if let Some(inventory) = state.inventory() {
+        for (ingredient, level) in inventory.low_stock() {
+            println!(" Low stock: {} ({} available, {} reserved)", ingredient, level.available(), level.reserved);
+        }
+    }
+
+    let waiting = queue.snapshot();
+    if waiting.is_empty() {
+        println!(" No orders waiting.");
+        return;
+    }
+
+    println!(" Up next:");
+    for (pos, row) in waiting.iter().enumerate() {
+        println!(
+            " {}. Order #{} | {} pizzas | {:?} | waited {}s",
+            pos + 1,
+            row.order_id,
+            row.pizzas,
+            row.priority,
+            row.waited.as_secs()
+        );
+
+        let Some(order) = state.get_order(row.order_id) else { continue };
+        for (n, pizza) in order.pizzas.iter().enumerate() {
+            for (i, line) in pizza.spec().ticket_lines().iter().enumerate() {
+                let label = if i == 0 { format!("#{}", n + 1) } else { String::new() };
+                println!("    {:<4}{}", label, line);
+            }
+        }
+    }
+}
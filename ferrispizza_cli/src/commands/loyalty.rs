@@ -0,0 +1,37 @@
+//! Show a customer's loyalty points via CLI
+
+use std::time::SystemTime;
+
+use ferrispizza_lib::billing::{EntryKind, LoyaltyLedger};
+use ferrispizza_lib::customer::CustomerRegistry;
+use ferrispizza_lib::errors::OrderError;
+
+pub fn show_points(
+    customers: &CustomerRegistry,
+    ledger: &LoyaltyLedger,
+    key: &str,
+) -> Result<(), OrderError> {
+    let customer = customers
+        .lookup(key)
+        .ok_or_else(|| OrderError::UnknownCustomer(key.to_string()))?;
+    let tier = ledger.tier(customer.id).map_or_else(|| "no tier".to_string(), |t| t.name);
+
+    println!(
+        " {} ({}): {} points | {}",
+        customer.name,
+        customer.phone,
+        ledger.balance(customer.id, SystemTime::now()),
+        tier
+    );
+    for entry in ledger.entries(customer.id) {
+        let what = match entry.kind {
+            EntryKind::Earned { spend, .. } => format!("+{} earned on {}", entry.points, spend),
+            EntryKind::Spent => format!("-{} spent", entry.points),
+            EntryKind::EarnReversed => format!("-{} refunded", entry.points),
+            EntryKind::EarnReduced { refunded } => format!("-{} for {} refunded", entry.points, refunded),
+            EntryKind::SpendReversed => format!("+{} refunded", entry.points),
+        };
+        println!("  - Order #{}: {}", entry.order_id, what);
+    }
+    Ok(())
+}
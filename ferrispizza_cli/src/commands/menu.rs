@@ -1,9 +1,76 @@
-//! Show pizza menu
+//! Show pizza menu with a price column per size and nutrition labels
+
+use ferrispizza_lib::menu::{Menu, MenuItem, MenuSection};
+use ferrispizza_lib::pizza::{Nutrition, NutritionFilter, Pizza, PizzaSpec, Size};
+
+pub fn show_menu(menu: &Menu, filter: &NutritionFilter) {
+    print!(" {:<26}", "");
+    for size in Size::ALL {
+        print!(" {:>10}", size.to_string());
+    }
+    println!();
 
-pub fn show_menu() {
     println!(" Available Pizzas:");
-    println!(" - margherita");
-    println!(" - farmhouse");
-    println!(" - thin_margherita");
-    println!(" - cheese_burst_farmhouse");
+    print_items(menu, MenuSection::Base, filter);
+    for preset in menu.matching_presets(filter) {
+        let specs: Result<Vec<PizzaSpec>, _> = Size::ALL
+            .iter()
+            .map(|&size| menu.custom_spec(&preset.id, size, None, &[]))
+            .collect();
+        match specs {
+            Ok(specs) => {
+                let prices = specs.iter().map(|s| s.cost().map_or_else(|_| "n/a".to_string(), |c| c.to_string())).collect();
+                println!(" - {:<24} {}  ({})", preset.id, columns(prices), contents(&specs[0]));
+                print_label(specs[1].nutrition().as_ref());
+            }
+            Err(_) => println!(" - {:<24} {:>10}", preset.id, "sold out"),
+        }
+    }
+
+    println!(" Crusts:");
+    print_items(menu, MenuSection::Crust, filter);
+    println!(" Toppings:");
+    print_items(menu, MenuSection::Topping, filter);
+}
+
+/// Items on sale that pass the filter; sold-out items only without a filter.
+fn print_items(menu: &Menu, section: MenuSection, filter: &NutritionFilter) {
+    let items = match section {
+        MenuSection::Base => &menu.bases,
+        MenuSection::Crust => &menu.crusts,
+        MenuSection::Topping => &menu.toppings,
+    };
+    let matching: Vec<&MenuItem> = menu.matching(section, filter).collect();
+    for item in items {
+        if matching.contains(&item) {
+            let prices = Size::ALL.iter().map(|&size| item.price(size).to_string()).collect();
+            println!(" - {:<24} {}  ({})", item.id, columns(prices), item.name);
+            print_label(item.nutrition.as_ref());
+        } else if !item.available && filter.is_empty() {
+            println!(" - {:<24} {:>10}", item.id, "sold out");
+        }
+    }
+}
+
+/// Medium-size label under an item.
+fn print_label(facts: Option<&Nutrition>) {
+    match facts {
+        Some(facts) => println!("   {:<24} {}", "", facts.label()),
+        None => println!("   {:<24} no nutrition information", ""),
+    }
+}
+
+fn columns(prices: Vec<String>) -> String {
+    prices.iter().map(|p| format!("{:>10}", p)).collect::<Vec<_>>().join(" ")
+}
+
+/// What goes on a preset, without the size: "Margherita + Cheese, Thin Crust".
+fn contents(spec: &PizzaSpec) -> String {
+    let mut parts = vec![spec.base.name.as_str()];
+    parts.extend(spec.topping_names());
+    let mut text = parts.join(" + ");
+    if let Some(crust) = &spec.crust {
+        text = format!("{}, {}", text, crust.name);
+    }
+    text
 }
@@ -1,29 +1,52 @@
 //! Place a pizza order via CLI
 
 use crate::commands::PizzaSelection;
-use ferrispizza_lib::errors::{BillingError, OrderError};
-use ferrispizza_lib::order::order;
+use ferrispizza_lib::customer::CustomerRegistry;
+use ferrispizza_lib::errors::OrderError;
+use ferrispizza_lib::menu::Menu;
+use ferrispizza_lib::order::{order, OrderPriority};
 use ferrispizza_lib::pizza::*;
 use ferrispizza_lib::concurrency::SharedOrderState;
+use ferrispizza_lib::utils::IdSource;
 
-pub fn place_order(state: &SharedOrderState, items: Vec<PizzaSelection>) -> Result<(),OrderError> {
+pub fn place_order(
+    state: &SharedOrderState,
+    menu: &Menu,
+    ids: &dyn IdSource,
+    items: Vec<PizzaSelection>,
+    priority: OrderPriority,
+    customers: &CustomerRegistry,
+    customer: Option<&str>,
+) -> Result<(), OrderError> {
+    let customer = match customer {
+        Some(key) => Some(
+            customers
+                .lookup(key)
+                .ok_or_else(|| OrderError::UnknownCustomer(key.to_string()))?,
+        ),
+        None => None,
+    };
     let mut pizza: Vec<Box<dyn Pizza>>  = Vec::new();
 
     for item in items {
-        match item {
-            PizzaSelection::Margherita => pizza.push(Box::new(Margherita::new())),
-            PizzaSelection::Farmhouse => pizza.push(Box::new(Farmhouse::new())),
-            PizzaSelection::ThinMargherita => {
-                pizza.push(Box::new(ThinCrust::new(Margherita::new())))
-            },
-            PizzaSelection::CheeseBurstFarmhouse => {
-                pizza.push(Box::new(CheeseBurst::new(Farmhouse::new())))
-            }
+        let mut spec = menu.custom_spec(&item.base, item.size, item.crust.as_deref(), &[])?;
+        for topping in &item.toppings {
+            spec.toppings.push(menu.topping_part(&topping.id, item.size, topping.placement)?);
         }
+        menu.validate(&spec)?;
+        for _ in 0..item.quantity {
+            pizza.push(spec.build());
+        }
+    }
+    let mut order1 = order::with_id_source(pizza, ids)?.with_priority(priority);
+    if let Some(customer) = &customer {
+        order1 = order1.for_customer(customer)?;
+    }
+    let id = order1.id.0;
+    state.add_order(order1)?;
+    match customer {
+        Some(c) => println!(" Order placed successfully for {}! Order ID: {}", c.name, id),
+        None => println!(" Order placed successfully! Order ID: {}", id),
     }
-    let order1 = order::new(pizza);
-    let y = &order1;
-    state.add_order(order1.clone());
-    println!(" Order placed successfully! Order ID: {}", y.id.0);
     Ok(())
 }
@@ -1,22 +1,231 @@
 //! Process order payment via CLI
 
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
 use crate::commands::PaymentMethodCommand;
-use ferrispizza_lib::concurrency::SharedOrderState;
-use ferrispizza_lib::billing::BillingEngine;
-use ferrispizza_lib::errors::BillingError;
-use ferrispizza_lib::patterns::{PaymentAdapter, UpiPayment, CardPayment};
-
-pub fn pay_order(state: &SharedOrderState, order_id: u64, method: PaymentMethodCommand) -> Result<(),BillingError> {
-    let mut order = state.get_order(order_id).clone().unwrap();
-    let billing = BillingEngine::new();
-    let adapter: Box<dyn PaymentAdapter> = match method {
-        PaymentMethodCommand::UPI => Box::new(UpiPayment::with_success_rate("tester@upi", 1.0)),
-        PaymentMethodCommand::Card => Box::new(CardPayment::new("4111222233334444", 123)),
-    };
+use ferrispizza_lib::billing::{BillingEngine, CouponClaim, PaymentReceipt};
+use ferrispizza_lib::concurrency::{OrderQueue, SharedOrderState};
+use ferrispizza_lib::errors::{BillingError, OrderError};
+use ferrispizza_lib::order::{order, OrderStatus};
+use ferrispizza_lib::patterns::{
+    CardPayment, CircuitBreaker, IdempotencyStore, IdempotentPayment, PaymentAdapter, RetryingPayment, UpiPayment,
+};
+use ferrispizza_lib::utils::FileLogger;
+
+/// How long to wait for room in the kitchen queue after charging.
+const KITCHEN_SEND_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Prefix of the customer key used for coupon limits on orders without a
+/// customer. Each walk-in order counts as its own customer, so one walk-in
+/// using a coupon does not use it up for every other walk-in.
+const WALK_IN: &str = "walk-in";
+
+/// Idempotency key for payments made without `--key`.
+pub(super) const DEFAULT_KEY: &str = "counter";
+
+/// Safeguards shared by every payment made at the counter.
+pub struct PaymentGuards {
+    /// Payments already made this session, so a retried `pay` never charges
+    /// twice.
+    pub idempotency: IdempotencyStore,
+    /// Pauses UPI payments after repeated UPI failures.
+    pub upi_breaker: CircuitBreaker,
+    /// Pauses card payments after repeated card failures.
+    pub card_breaker: CircuitBreaker,
+    /// Where every payment attempt is logged.
+    pub log_path: PathBuf,
+    /// Paid orders not yet handed to the kitchen.
+    pub unsent: Mutex<HashSet<u64>>,
+}
+
+impl PaymentGuards {
+    /// The provider for `method`, retried with backoff and guarded by that
+    /// provider's breaker.
+    pub(super) fn retrying(
+        &self,
+        method: PaymentMethodCommand,
+        billing: &BillingEngine,
+    ) -> RetryingPayment<Box<dyn PaymentAdapter>> {
+        let breaker = match method {
+            PaymentMethodCommand::Upi => &self.upi_breaker,
+            PaymentMethodCommand::Card => &self.card_breaker,
+        };
+        RetryingPayment::new(adapter_for(method, billing))
+            .with_logger(FileLogger::new(self.log_path.to_string_lossy()))
+            .with_breaker(breaker.clone())
+    }
+}
+
+/// What the customer typed with a payment besides the order and method.
+pub struct PaymentOptions {
+    pub coupon: Option<String>,
+    pub points: u32,
+    /// Idempotency key; retries with the same key never charge twice.
+    pub key: Option<String>,
+}
+
+pub fn pay_order(
+    state: &SharedOrderState,
+    billing: &BillingEngine,
+    guards: &PaymentGuards,
+    kitchen: &dyn OrderQueue,
+    order_id: u64,
+    method: PaymentMethodCommand,
+    options: PaymentOptions,
+) -> Result<(), BillingError> {
+    let PaymentOptions { coupon, points, key } = options;
+    let order = with_options(state, order_id, coupon, points)?;
 
+    // A retry of a payment that went through gets its receipt back
+    let key = key.unwrap_or_else(|| DEFAULT_KEY.to_string());
+    let adapter = IdempotentPayment::new(
+        guards.retrying(method, billing),
+        guards.idempotency.clone(),
+        key.as_str(),
+    );
+    if let Some(receipt) = adapter.replay(&order)? {
+        println!("Order {} was already paid with key '{}'; no new charge.", order_id, key);
+        return complete(state, billing, guards, kitchen, receipt);
+    }
+
+    ensure_payable(&order, kitchen)?;
+    save_options(state, &order)?;
     let receipt = adapter.pay(&order)?;
-    println!("Payment successful for Order {}!", order_id);
-    println!("Total paid: {}", receipt.total_amount);
-    order.mark_paid();
+    complete(state, billing, guards, kitchen, receipt)
+}
+
+/// The order as it will be billed: the coupon and points typed with this
+/// attempt replace any earlier ones.
+pub(super) fn with_options(
+    state: &SharedOrderState,
+    order_id: u64,
+    coupon: Option<String>,
+    points: u32,
+) -> Result<order, BillingError> {
+    let mut order = state
+        .get_order(order_id)
+        .ok_or(OrderError::NotFound(order_id))?;
+    order.pay_with_points(points);
+    let customer = order
+        .customer
+        .map_or_else(|| format!("{}-{}", WALK_IN, order_id), |id| format!("customer-{}", id.0));
+    order.coupon = coupon.map(|code| CouponClaim::new(code, customer));
+    Ok(order)
+}
+
+/// Refuse before charging rather than after.
+pub(super) fn ensure_payable(order: &order, kitchen: &dyn OrderQueue) -> Result<(), BillingError> {
+    if !order.status.can_transition_to(OrderStatus::Paid) {
+        return Err(OrderError::InvalidTransition {
+            from: order.status,
+            to: OrderStatus::Paid,
+        }
+        .into());
+    }
+
+    // Don't take money for an order the kitchen cannot queue
+    let waiting = kitchen.depth();
+    if kitchen.capacity().is_some_and(|cap| waiting >= cap) {
+        return Err(OrderError::QueueError(format!(
+            "kitchen is busy with {} orders, please try again shortly",
+            waiting
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+/// Keep the coupon and points being paid with on the stored order.
+pub(super) fn save_options(state: &SharedOrderState, order: &order) -> Result<(), BillingError> {
+    state.update_order(order.id.0, |o| {
+        o.pay_with_points(order.redeem_points);
+        o.coupon = order.coupon.clone();
+    })?;
+    Ok(())
+}
+
+/// Record a successful payment and send the order to the kitchen. Also
+/// finishes a replayed payment whose first attempt stopped before either.
+pub(super) fn complete(
+    state: &SharedOrderState,
+    billing: &BillingEngine,
+    guards: &PaymentGuards,
+    kitchen: &dyn OrderQueue,
+    receipt: PaymentReceipt,
+) -> Result<(), BillingError> {
+    let order_id = receipt.order_id;
+    let stored = state.get_order(order_id).ok_or(OrderError::NotFound(order_id))?;
+    if stored.receipt.is_none() {
+        state.update_order(order_id, |o| o.record_payment(receipt.clone()))??;
+        println!("Payment successful for Order {}!", order_id);
+        guards.unsent.lock().unwrap().insert(order_id);
+    }
+    print_receipt(&receipt, billing);
+    if !guards.unsent.lock().unwrap().contains(&order_id) {
+        return Ok(());
+    }
+
+    // Hand the paid order to the kitchen. If the queue stays full the
+    // payment still stands and the order waits for a replay or restart.
+    let paid = state.get_order(order_id).ok_or(OrderError::NotFound(order_id))?;
+    let waiting = kitchen.depth();
+    match kitchen.submit_timeout(paid, KITCHEN_SEND_TIMEOUT) {
+        Ok(()) => {
+            guards.unsent.lock().unwrap().remove(&order_id);
+            println!("Order {} sent to the kitchen ({} waiting).", order_id, waiting);
+        }
+        Err(e) => println!(
+            "Order {} is paid but not sent to the kitchen yet ({}); pay again with the same key to send it, or it is sent on the next start.",
+            order_id, e
+        ),
+    }
     Ok(())
 }
+
+pub(super) fn print_receipt(receipt: &PaymentReceipt, billing: &BillingEngine) {
+    println!("Subtotal: {}", receipt.subtotal);
+    for discount in &receipt.discounts {
+        println!("  {}: -{}", discount.name, discount.amount);
+    }
+    for line in &receipt.tax_lines {
+        println!(
+            "  {} @ {}.{:02}%: {}",
+            line.name,
+            line.rate_bps / 100,
+            line.rate_bps % 100,
+            line.amount
+        );
+    }
+    match receipt.points {
+        Some(tender) => {
+            println!("Total: {}", receipt.total_amount);
+            println!("  {} loyalty points: -{}", tender.points, tender.value);
+            let paid = receipt.amount_due().map_or_else(|e| e.to_string(), |due| due.to_string());
+            println!("Total paid: {}", paid);
+        }
+        None => println!("Total paid: {}", receipt.total_amount),
+    }
+    for tender in &receipt.tenders {
+        println!("  by {}: {}", tender.provider, tender.amount);
+    }
+    if let (Some(ledger), Some(customer)) = (billing.loyalty(), receipt.customer) {
+        println!("Loyalty balance: {} points", ledger.balance(customer, SystemTime::now()));
+    }
+}
+
+/// The simulated provider for `method`, billing with `billing`. Refunds
+/// call it directly: one that seems to fail may still have gone through,
+/// so it is never retried.
+pub(super) fn adapter_for(method: PaymentMethodCommand, billing: &BillingEngine) -> Box<dyn PaymentAdapter> {
+    match method {
+        PaymentMethodCommand::Upi => Box::new(
+            UpiPayment::with_success_rate("tester@upi", 1.0).with_billing(billing.clone()),
+        ),
+        PaymentMethodCommand::Card => Box::new(
+            CardPayment::new("4111222233334444", 123).with_billing(billing.clone()),
+        ),
+    }
+}
@@ -0,0 +1,188 @@
+//! Refund a paid order via CLI
+
+use crate::commands::PaymentMethodCommand;
+use ferrispizza_lib::billing::{BillingEngine, RefundReceipt};
+use ferrispizza_lib::concurrency::SharedOrderState;
+use ferrispizza_lib::errors::{BillingError, OrderError};
+use ferrispizza_lib::money::Money;
+use ferrispizza_lib::order::{order, OrderStatus};
+use ferrispizza_lib::patterns::PaymentAdapter;
+
+use super::pay::adapter_for;
+
+/// Refund `order_id` to the methods that paid it, each getting back no
+/// more than it paid. `method` is only used for payments saved before
+/// those methods were recorded.
+pub fn refund_order(
+    state: &SharedOrderState,
+    billing: &BillingEngine,
+    order_id: u64,
+    method: Option<PaymentMethodCommand>,
+    amount: Option<Money>,
+    lines: Vec<usize>,
+) -> Result<(), BillingError> {
+    let order = state.get_order(order_id).ok_or(OrderError::NotFound(order_id))?;
+    let receipt = order.receipt.as_ref().ok_or(BillingError::NotPaid(order_id))?;
+    let refundable = receipt.refundable()?;
+    let amount = match amount {
+        Some(amount) => amount,
+        None if !lines.is_empty() => billing.line_refund(&order, &lines)?,
+        None => refundable,
+    };
+
+    // Refuse before returning money the order cannot record
+    let to = if amount == refundable {
+        OrderStatus::Refunded
+    } else {
+        OrderStatus::PartiallyRefunded
+    };
+    if !order.status.can_transition_to(to) {
+        return Err(OrderError::InvalidTransition { from: order.status, to }.into());
+    }
+    let refund = billing.quote_refund(receipt, amount)?.with_lines(lines);
+
+    let parts = if receipt.tenders.is_empty() {
+        vec![(adapter_for(method.ok_or(BillingError::InvalidPaymentMethod)?, billing), amount)]
+    } else {
+        receipt
+            .refund_tenders(amount)?
+            .into_iter()
+            .map(|tender| Ok((adapter_for(method_for(&tender.provider)?, billing), tender.amount)))
+            .collect::<Result<Vec<_>, BillingError>>()?
+    };
+    let status = refund_parts(state, billing, &order, refund, parts)?;
+
+    let remaining = state.get_order(order_id).and_then(|o| o.receipt).and_then(|r| r.refundable().ok());
+    if let Some(remaining) = remaining {
+        println!("{} left to refund on Order {}.", remaining, order_id);
+    }
+    println!("Order {} is now {:?}.", order_id, status);
+    Ok(())
+}
+
+/// Return `refund` through each adapter in turn, then record it. If one
+/// fails, what already went back is recorded and that adapter's error
+/// returned.
+fn refund_parts(
+    state: &SharedOrderState,
+    billing: &BillingEngine,
+    order: &order,
+    refund: RefundReceipt,
+    parts: Vec<(Box<dyn PaymentAdapter>, Money)>,
+) -> Result<OrderStatus, BillingError> {
+    let order_id = order.id.0;
+    let receipt = order.receipt.as_ref().ok_or(BillingError::NotPaid(order_id))?;
+    // A failure part-way leaves a partial refund to record
+    if parts.len() > 1 && !order.status.can_transition_to(OrderStatus::PartiallyRefunded) {
+        return Err(BillingError::SplitRefundNotAllowed { order_id, status: order.status });
+    }
+
+    let mut returned = Money::zero(refund.amount.currency());
+    for (adapter, part) in parts {
+        if let Err(e) = adapter.refund(receipt, part) {
+            if !returned.is_zero() {
+                let partial = billing.quote_refund(receipt, returned)?.with_lines(refund.lines.clone());
+                match state.record_refund(order_id, partial) {
+                    Ok(_) => eprintln!(" Only {} of {} was refunded.", returned, refund.amount),
+                    Err(record) => eprintln!(" {} was refunded but could not be recorded: {}", returned, record),
+                }
+            }
+            return Err(e);
+        }
+        println!("Refunded {} by {} for Order {}.", part, adapter.name(), order_id);
+        returned = returned.checked_add(part)?;
+    }
+    state.record_refund(order_id, refund)
+}
+
+/// The method a recorded tender was paid with.
+fn method_for(provider: &str) -> Result<PaymentMethodCommand, BillingError> {
+    match provider {
+        "upi" => Ok(PaymentMethodCommand::Upi),
+        "card" => Ok(PaymentMethodCommand::Card),
+        _ => Err(BillingError::InvalidPaymentMethod),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ferrispizza_lib::billing::{PaymentReceipt, Tender};
+    use ferrispizza_lib::patterns::UpiPayment;
+    use ferrispizza_lib::pizza::Margherita;
+
+    /// A provider whose refunds never go through.
+    struct Refuses;
+
+    impl PaymentAdapter for Refuses {
+        fn name(&self) -> &str {
+            "upi"
+        }
+
+        fn pay(&self, _: &order) -> Result<PaymentReceipt, BillingError> {
+            unreachable!()
+        }
+
+        fn charge(&self, _: &PaymentReceipt, _: Money) -> Result<(), BillingError> {
+            unreachable!()
+        }
+
+        fn refund(&self, _: &PaymentReceipt, _: Money) -> Result<RefundReceipt, BillingError> {
+            Err(BillingError::PaymentFailed("gateway timeout".into()))
+        }
+    }
+
+    /// A ₹126 order paid ₹100 by UPI and ₹26 by card, moved through `statuses`.
+    fn split_order(state: &SharedOrderState, billing: &BillingEngine, statuses: &[OrderStatus]) -> order {
+        let order = order::new(vec![Box::new(Margherita::new())]);
+        let id = order.id.0;
+        state.add_order(order.clone()).unwrap();
+        let mut receipt = billing.generate_receipt(&order).unwrap();
+        receipt.tenders = vec![
+            Tender { provider: "upi".to_string(), amount: Money::inr(100) },
+            Tender { provider: "card".to_string(), amount: Money::inr(26) },
+        ];
+        state.update_order(id, |o| o.record_payment(receipt)).unwrap().unwrap();
+        for &status in statuses {
+            state.transition_status(id, status).unwrap();
+        }
+        state.get_order(id).unwrap()
+    }
+
+    fn upi() -> Box<dyn PaymentAdapter> {
+        Box::new(UpiPayment::with_success_rate("asha@upi", 1.0).with_delay(0))
+    }
+
+    #[test]
+    fn failed_tender_keeps_a_record_of_what_went_back() {
+        let (state, billing) = (SharedOrderState::new(), BillingEngine::new());
+        let completed = [OrderStatus::Preparing, OrderStatus::Ready, OrderStatus::Completed];
+        let order = split_order(&state, &billing, &completed);
+        let receipt = order.receipt.as_ref().unwrap();
+        let refund = billing.quote_refund(receipt, Money::inr(126)).unwrap().with_lines(vec![0]);
+
+        // The card's ₹26 goes back, then UPI fails
+        let parts: Vec<(Box<dyn PaymentAdapter>, Money)> =
+            vec![(upi(), Money::inr(26)), (Box::new(Refuses), Money::inr(100))];
+        let result = refund_parts(&state, &billing, &order, refund, parts);
+        assert!(matches!(result, Err(BillingError::PaymentFailed(_))));
+
+        let stored = state.get_order(order.id.0).unwrap();
+        assert_eq!(stored.status, OrderStatus::PartiallyRefunded);
+        let receipt = stored.receipt.unwrap();
+        assert_eq!(receipt.refundable().unwrap(), Money::inr(100));
+        assert_eq!(receipt.refunds[0].lines, vec![0]);
+    }
+
+    #[test]
+    fn split_refunds_wait_until_a_partial_refund_can_be_recorded() {
+        let (state, billing) = (SharedOrderState::new(), BillingEngine::new());
+        let order = split_order(&state, &billing, &[]);
+        let refund = billing.quote_refund(order.receipt.as_ref().unwrap(), Money::inr(126)).unwrap();
+
+        let parts: Vec<(Box<dyn PaymentAdapter>, Money)> = vec![(upi(), Money::inr(26)), (upi(), Money::inr(100))];
+        let result = refund_parts(&state, &billing, &order, refund, parts);
+        assert!(matches!(result, Err(BillingError::SplitRefundNotAllowed { status: OrderStatus::Paid, .. })));
+        assert!(state.get_order(order.id.0).unwrap().receipt.unwrap().refunds.is_empty());
+    }
+}
@@ -0,0 +1,45 @@
+//! Split one order's payment across several methods via CLI
+
+use crate::commands::PaymentMethodCommand;
+use ferrispizza_lib::billing::BillingEngine;
+use ferrispizza_lib::concurrency::{OrderQueue, SharedOrderState};
+use ferrispizza_lib::errors::BillingError;
+use ferrispizza_lib::patterns::{Allocation, SplitTender};
+
+use super::pay::{
+    complete, ensure_payable, save_options, with_options, PaymentGuards, PaymentOptions, DEFAULT_KEY,
+};
+
+/// Pay `order_id` with one leg per `(method, allocation)`. Like a single
+/// payment, a retry with the same key never charges again.
+pub fn split_order(
+    state: &SharedOrderState,
+    billing: &BillingEngine,
+    guards: &PaymentGuards,
+    kitchen: &dyn OrderQueue,
+    order_id: u64,
+    legs: Vec<(PaymentMethodCommand, Allocation)>,
+    options: PaymentOptions,
+) -> Result<(), BillingError> {
+    let PaymentOptions { coupon, points, key } = options;
+    let order = with_options(state, order_id, coupon, points)?;
+    let split = legs.into_iter().fold(SplitTender::new(billing.clone()), |split, (method, allocation)| {
+        split.with_leg(guards.retrying(method, billing), allocation)
+    });
+
+    // A retry of a payment that went through gets its receipt back
+    let key = key.unwrap_or_else(|| DEFAULT_KEY.to_string());
+    let method = split.to_string();
+    if let Some(receipt) = guards.idempotency.replay(&order, &key, &method)? {
+        println!("Order {} was already paid with key '{}'; no new charge.", order_id, key);
+        return complete(state, billing, guards, kitchen, receipt);
+    }
+
+    ensure_payable(&order, kitchen)?;
+    // Check the legs add up before touching the stored order
+    split.allocate(billing.generate_receipt(&order)?.amount_due()?)?;
+
+    save_options(state, &order)?;
+    let receipt = guards.idempotency.pay_once(&order, &key, &method, || split.pay(&order))?;
+    complete(state, billing, guards, kitchen, receipt)
+}
@@ -0,0 +1,23 @@
+//! Move an order through its lifecycle via CLI
+
+use crate::commands::StatusCommand;
+use ferrispizza_lib::concurrency::SharedOrderState;
+use ferrispizza_lib::errors::OrderError;
+use ferrispizza_lib::order::OrderStatus;
+
+pub fn update_status(
+    state: &SharedOrderState,
+    order_id: u64,
+    status: StatusCommand,
+) -> Result<(), OrderError> {
+    let target = match status {
+        StatusCommand::Preparing => OrderStatus::Preparing,
+        StatusCommand::Ready => OrderStatus::Ready,
+        StatusCommand::Completed => OrderStatus::Completed,
+        StatusCommand::Cancelled => OrderStatus::Cancelled,
+    };
+
+    state.transition_status(order_id, target)?;
+    println!(" Order {} is now {:?}.", order_id, target);
+    Ok(())
+}
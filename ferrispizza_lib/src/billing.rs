@@ -2,8 +2,25 @@
 //!
 //! This module acts as the public entry point for billing components.
 //! Internally, it exposes the [`BillingEngine`] which is responsible for
-//! calculating total pizza cost and generating payment receipts.
+//! calculating total pizza cost and generating payment receipts, the
+//! [`Discount`] rules and [`Coupon`]s it applies, and the [`TaxPolicy`]
+//! implementations it charges. Customers can also pay with points from a
+//! [`LoyaltyLedger`], and payments can be refunded in full or in part with
+//! a [`RefundReceipt`].
 mod calculator;
+mod coupon;
+mod discount;
+mod loyalty;
+mod refund;
+mod tax;
 
 pub use calculator::BillingEngine;
-pub use calculator::PaymentReceipt;
+pub use calculator::{PaymentReceipt, PointsTender, Tender};
+pub use coupon::{Coupon, CouponBook, CouponClaim};
+pub use loyalty::{EntryKind, LedgerEntry, LoyaltyLedger, LoyaltyProgram, LoyaltyTier};
+pub use refund::RefundReceipt;
+pub use discount::{
+    BuyNGetMFree, Discount, DiscountContext, DiscountLine, DiscountStage, FlatDiscount,
+    FreeTopping, PercentageDiscount,
+};
+pub use tax::{BillItem, GstPolicy, NoTax, SupplyType, TaxCategory, TaxLine, TaxPolicy};
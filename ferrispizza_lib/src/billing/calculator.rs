@@ -1,87 +1,512 @@
 //! Billing calculator — computes total bill & generates receipts.
 //!
 //! The [`BillingEngine`] iterates through items in an [`order`] and sums
-//! their cost using their `Pizza` trait API. It also generates a typed
-//! [`PaymentReceipt`] carrying billing metadata.
+//! their cost using their `Pizza` trait API, takes off any [`Discount`]s
+//! (automatic promotions plus the order's coupon), then applies its
+//! [`TaxPolicy`] to what is left. It also generates a typed
+//! [`PaymentReceipt`] carrying billing metadata, itemised discounts and
+//! tax lines. Loyalty points offered on the order are a tender: they pay
+//! part of the total and do not change the tax.
 //!
 //! # Example
 //! ```
 //! use ferrispizza_lib::billing::{BillingEngine};
 //! use ferrispizza_lib::pizza::Margherita;
 //! use ferrispizza_lib::order::order;
+//! use ferrispizza_lib::money::Money;
 //!
 //! let engine = BillingEngine::new();
 //! let order = order::new(vec![Box::new(Margherita::new())]);
 //!
-//! let total = engine.calculate_total(&order);
-//! assert_eq!(total, 120.0);
+//! // ₹120 + 5% GST
+//! let total = engine.calculate_total(&order).unwrap();
+//! assert_eq!(total, Money::inr(126));
 //! ```
 
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use crate::customer::CustomerId;
+use crate::errors::{BillingError, MoneyError};
+use crate::money::Money;
 use crate::order::order;
+use crate::money::Rounding;
+use super::coupon::{CouponBook, CouponClaim};
+use super::discount::{Discount, DiscountContext, DiscountLine};
+use super::loyalty::LoyaltyLedger;
+use super::refund::RefundReceipt;
+use super::tax::{BillItem, GstPolicy, TaxLine, TaxPolicy};
 
 /// Receipt generated after successful payment processing.
 ///
-/// Contains order id, total billed amount, and generated timestamp.
-#[derive(Debug, Clone, PartialEq)]
+/// Contains order id, pre-discount subtotal, itemised discounts and tax
+/// lines, the grand total billed, and generated timestamp.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PaymentReceipt {
     pub order_id: u64,
-    pub total_amount: f32,
+    pub subtotal: Money,
+    /// Discounts in the order they were applied.
+    pub discounts: Vec<DiscountLine>,
+    pub tax_lines: Vec<TaxLine>,
+    /// Grand total: subtotal minus discounts plus all tax lines.
+    pub total_amount: Money,
     pub timestamp: u128,
+    /// Coupon applied to this bill, if any.
+    pub coupon: Option<CouponClaim>,
+    /// Registered customer billed, if any.
+    #[serde(default)]
+    pub customer: Option<CustomerId>,
+    /// Loyalty points put towards the total, if any.
+    #[serde(default)]
+    pub points: Option<PointsTender>,
+    /// Money returned since, oldest first.
+    #[serde(default)]
+    pub refunds: Vec<RefundReceipt>,
+    /// What each payment method paid, in the order they were charged.
+    /// Empty on receipts saved before payment methods were recorded.
+    #[serde(default)]
+    pub tenders: Vec<Tender>,
 }
 
+/// Loyalty points used to pay part of a bill.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PointsTender {
+    pub points: u32,
+    /// What the points paid for.
+    pub value: Money,
+}
+
+/// One payment method's part of a bill.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tender {
+    /// Provider name, as given by [`PaymentAdapter::name`](crate::patterns::PaymentAdapter::name).
+    pub provider: String,
+    pub amount: Money,
+}
+
+impl PaymentReceipt {
+    // A receipt read back from storage may mix currencies or hold amounts
+    // that overflow, so none of these sums can assume it adds up.
+
+    /// Sum of all discount lines.
+    pub fn total_discount(&self) -> Result<Money, MoneyError> {
+        Money::checked_sum(self.discounts.iter().map(|d| d.amount), self.subtotal.currency())
+    }
+
+    /// Sum of all tax lines.
+    pub fn total_tax(&self) -> Result<Money, MoneyError> {
+        Money::checked_sum(self.tax_lines.iter().map(|l| l.amount), self.subtotal.currency())
+    }
+
+    /// What is left to pay after loyalty points.
+    pub fn amount_due(&self) -> Result<Money, MoneyError> {
+        match self.points {
+            Some(tender) => self.total_amount.checked_sub(tender.value),
+            None => Ok(self.total_amount),
+        }
+    }
+
+    /// Sum of all refunds so far.
+    pub fn total_refunded(&self) -> Result<Money, MoneyError> {
+        Money::checked_sum(self.refunds.iter().map(|r| r.amount), self.subtotal.currency())
+    }
+
+    /// What can still be refunded: the money paid, less earlier refunds.
+    /// Points are not refunded as money.
+    pub fn refundable(&self) -> Result<Money, MoneyError> {
+        self.amount_due()?.checked_sub(self.total_refunded()?)
+    }
+
+    /// How a refund of `amount` goes back to the methods that paid: the
+    /// latest tender first, none beyond what is left of it. Earlier refunds
+    /// are taken to have gone back the same way.
+    ///
+    /// Fails with [`BillingError::OverRefund`] if more is asked back than
+    /// is left on the payment.
+    pub fn refund_tenders(&self, amount: Money) -> Result<Vec<Tender>, BillingError> {
+        let refundable = self.refundable()?;
+        if amount > refundable {
+            return Err(BillingError::OverRefund { requested: amount, refundable });
+        }
+        let mut refunded = self.total_refunded()?;
+        let mut amount = amount;
+        let mut parts = Vec::new();
+        for tender in self.tenders.iter().rev() {
+            let returned = if refunded > tender.amount { tender.amount } else { refunded };
+            refunded = refunded.checked_sub(returned)?;
+            let left = tender.amount.checked_sub(returned)?;
+            let part = if amount > left { left } else { amount };
+            if !part.is_zero() {
+                amount = amount.checked_sub(part)?;
+                parts.push(Tender { provider: tender.provider.clone(), amount: part });
+            }
+        }
+        Ok(parts)
+    }
+}
+
+/// How long after payment refunds are accepted by default.
+pub const DEFAULT_REFUND_WINDOW: Duration = Duration::from_secs(7 * 86_400);
+
 /// Billing engine responsible for calculating total order cost
 /// and producing [`PaymentReceipt`] objects.
 ///
 /// # Responsibility
 /// * Sum cost of all pizzas in an order
+/// * Apply automatic discounts and the order's coupon, in stage order
+/// * Apply the configured [`TaxPolicy`] (intra-state GST by default)
 /// * Create timestamped receipt for finalized bill
-pub struct BillingEngine;
+#[derive(Clone)]
+pub struct BillingEngine {
+    tax_policy: Arc<dyn TaxPolicy>,
+    discounts: Vec<Arc<dyn Discount>>,
+    coupons: CouponBook,
+    loyalty: Option<LoyaltyLedger>,
+    refund_window: Duration,
+}
 
 impl BillingEngine {
-    /// Creates a new billing engine instance.
+    /// Creates a new billing engine charging intra-state GST.
     pub fn new() -> Self {
-        BillingEngine
+        Self::with_tax_policy(GstPolicy::default())
+    }
+
+    /// Creates a billing engine with a custom tax policy.
+    pub fn with_tax_policy(policy: impl TaxPolicy + 'static) -> Self {
+        Self {
+            tax_policy: Arc::new(policy),
+            discounts: Vec::new(),
+            coupons: CouponBook::new(),
+            loyalty: None,
+            refund_window: DEFAULT_REFUND_WINDOW,
+        }
+    }
+
+    /// Apply `discount` to every order billed by this engine.
+    pub fn with_discount(mut self, discount: impl Discount + 'static) -> Self {
+        self.discounts.push(Arc::new(discount));
+        self
+    }
+
+    /// Validate order coupons against `coupons`.
+    pub fn with_coupon_book(mut self, coupons: CouponBook) -> Self {
+        self.coupons = coupons;
+        self
+    }
+
+    /// Coupons known to this engine.
+    pub fn coupons(&self) -> &CouponBook {
+        &self.coupons
+    }
+
+    /// Accept points from, and award points to, `ledger`.
+    pub fn with_loyalty(mut self, ledger: LoyaltyLedger) -> Self {
+        self.loyalty = Some(ledger);
+        self
+    }
+
+    pub fn loyalty(&self) -> Option<&LoyaltyLedger> {
+        self.loyalty.as_ref()
+    }
+
+    /// Accept refunds for `window` after payment.
+    pub fn with_refund_window(mut self, window: Duration) -> Self {
+        self.refund_window = window;
+        self
+    }
+
+    /// Billable items of an order, one per pizza.
+    pub fn bill_items(&self, order: &order) -> Result<Vec<BillItem>, MoneyError> {
+        order
+            .pizzas
+            .iter()
+            .map(|p| Ok(BillItem::new(p.tax_category(), p.cost()?)))
+            .collect()
     }
 
-    /// Calculate the total cost of the given order.
+    /// Calculate the pre-tax subtotal of the given order.
     ///
     /// Iterates over all pizzas and adds their cost.
-    pub fn calculate_total(&self, order: &order) -> f32 {
-        order.pizzas.iter().map(|p| p.cost()).sum()
+    pub fn calculate_subtotal(&self, order: &order) -> Result<Money, BillingError> {
+        Ok(order.total_cost()?)
+    }
+
+    /// Discounts for the given order, in the order they are applied.
+    ///
+    /// Rules run by [`DiscountStage`](super::DiscountStage); within a stage,
+    /// automatic discounts come before the coupon. Fails if the order's
+    /// coupon is not currently usable.
+    pub fn calculate_discounts(&self, order: &order) -> Result<Vec<DiscountLine>, BillingError> {
+        let subtotal = self.calculate_subtotal(order)?;
+
+        let mut rules: Vec<(String, Arc<dyn Discount>)> = self
+            .discounts
+            .iter()
+            .map(|d| (d.name(), Arc::clone(d)))
+            .collect();
+        if let Some(claim) = &order.coupon {
+            let coupon = self.coupons.validate(
+                &claim.code,
+                &claim.customer,
+                subtotal,
+                SystemTime::now(),
+            )?;
+            let discount = coupon.discount();
+            rules.push((format!("{} ({})", discount.name(), coupon.code), discount));
+        }
+        rules.sort_by_key(|(_, d)| d.stage());
+
+        let mut remaining = subtotal;
+        let mut lines = Vec::new();
+        for (name, rule) in rules {
+            let ctx = DiscountContext { order, remaining };
+            let mut amount = rule.amount(&ctx)?;
+            if amount > remaining {
+                amount = remaining;
+            }
+            if amount.is_zero() || amount.is_negative() {
+                continue;
+            }
+
+            remaining = remaining.checked_sub(amount)?;
+            lines.push(DiscountLine { name, amount });
+        }
+        Ok(lines)
+    }
+
+    /// Itemised tax lines for the given order, before any discount.
+    pub fn calculate_tax(&self, order: &order) -> Result<Vec<TaxLine>, BillingError> {
+        self.tax_policy.tax_lines(&self.bill_items(order)?)
+    }
+
+    /// Spread `discount` over `items` in proportion to their amounts so
+    /// tax is charged on what the customer actually pays.
+    fn discounted_items(items: Vec<BillItem>, discount: Money) -> Result<Vec<BillItem>, BillingError> {
+        let subtotal = Money::checked_sum(items.iter().map(|i| i.amount), discount.currency())?;
+        if discount.is_zero() || subtotal.is_zero() {
+            return Ok(items);
+        }
+
+        let mut left = discount;
+        let last = items.len() - 1;
+        let mut adjusted = Vec::with_capacity(items.len());
+        for (idx, item) in items.into_iter().enumerate() {
+            let share = if idx == last {
+                left
+            } else {
+                discount.apply_rate(
+                    item.amount.minor_units(),
+                    subtotal.minor_units(),
+                    Rounding::Down,
+                )?
+            };
+            left = left.checked_sub(share)?;
+            adjusted.push(BillItem::new(item.category, item.amount.checked_sub(share)?));
+        }
+        Ok(adjusted)
+    }
+
+    /// Calculate the grand total (after discounts, plus tax) of the given order.
+    pub fn calculate_total(&self, order: &order) -> Result<Money, BillingError> {
+        Ok(self.generate_receipt(order)?.total_amount)
     }
 
     /// Generate a receipt for the given order.
     ///
-    /// Includes order id, computed total, and current timestamp.
-    pub fn generate_receipt(&self, order: &order) -> PaymentReceipt {
-        PaymentReceipt {
+    /// Includes order id, subtotal, discounts, tax lines, grand total, and
+    /// current timestamp. This only quotes the bill; coupon usage is
+    /// counted by [`BillingEngine::redeem_coupon`] once payment succeeds.
+    pub fn generate_receipt(&self, order: &order) -> Result<PaymentReceipt, BillingError> {
+        let subtotal = self.calculate_subtotal(order)?;
+        let discounts = self.calculate_discounts(order)?;
+        let total_discount =
+            Money::checked_sum(discounts.iter().map(|d| d.amount), subtotal.currency())?;
+
+        let items = Self::discounted_items(self.bill_items(order)?, total_discount)?;
+        let tax_lines = self.tax_policy.tax_lines(&items)?;
+        let total_tax = Money::checked_sum(tax_lines.iter().map(|l| l.amount), subtotal.currency())?;
+        let total_amount = subtotal.checked_sub(total_discount)?.checked_add(total_tax)?;
+        let points = self.points_tender(order, total_amount)?;
+
+        Ok(PaymentReceipt {
             order_id: order.id.0,
-            total_amount: self.calculate_total(order),
-            timestamp: SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_millis(),
+            subtotal,
+            discounts,
+            tax_lines,
+            total_amount,
+            timestamp: now_millis()?,
+            coupon: order.coupon.clone(),
+            customer: order.customer,
+            points,
+            refunds: Vec::new(),
+            tenders: Vec::new(),
+        })
+    }
+
+    /// The order's points as a tender against `total`.
+    ///
+    /// Fails if the customer has fewer points than offered or points are
+    /// worth nothing. Never uses more points than the bill needs.
+    fn points_tender(&self, order: &order, total: Money) -> Result<Option<PointsTender>, BillingError> {
+        if order.redeem_points == 0 {
+            return Ok(None);
+        }
+        let (Some(ledger), Some(customer)) = (&self.loyalty, order.customer) else {
+            return Err(BillingError::NoLoyaltyAccount);
+        };
+        let available = ledger.balance(customer, SystemTime::now());
+        if order.redeem_points > available {
+            return Err(BillingError::InsufficientPoints { requested: order.redeem_points, available });
+        }
+
+        let each = ledger.program().point_value.minor_units();
+        if each <= 0 {
+            return Err(BillingError::PointsNotRedeemable);
+        }
+        let needed = total.minor_units().max(0) / each;
+        let points = order.redeem_points.min(u32::try_from(needed).unwrap_or(u32::MAX));
+        if points == 0 {
+            return Ok(None);
+        }
+        let value = Money::from_minor(each, total.currency()).checked_mul(i64::from(points))?;
+        Ok(Some(PointsTender { points, value }))
+    }
+
+    /// Count the receipt's coupon against its usage limits.
+    ///
+    /// Call once the payment for `receipt` has gone through.
+    pub fn redeem_coupon(&self, receipt: &PaymentReceipt) -> Result<(), BillingError> {
+        match &receipt.coupon {
+            Some(claim) => self.coupons.redeem(claim, receipt.subtotal, SystemTime::now()),
+            None => Ok(()),
+        }
+    }
+
+    /// Quote a refund of `amount` against `receipt`.
+    ///
+    /// Fails with [`BillingError::RefundWindowClosed`] once the refund
+    /// window has passed and [`BillingError::OverRefund`] if more is asked
+    /// back than is left. Like [`generate_receipt`](Self::generate_receipt)
+    /// this moves no money; payment adapters do.
+    pub fn quote_refund(&self, receipt: &PaymentReceipt, amount: Money) -> Result<RefundReceipt, BillingError> {
+        if amount.is_zero() || amount.is_negative() {
+            return Err(BillingError::InvalidRefundAmount(amount));
+        }
+        let now = now_millis()?;
+        if now.saturating_sub(receipt.timestamp) > self.refund_window.as_millis() {
+            return Err(BillingError::RefundWindowClosed {
+                order_id: receipt.order_id,
+                days: self.refund_window.as_secs() / 86_400,
+            });
         }
+        let refundable = receipt.refundable()?;
+        if amount > refundable {
+            return Err(BillingError::OverRefund { requested: amount, refundable });
+        }
+
+        Ok(RefundReceipt {
+            order_id: receipt.order_id,
+            amount,
+            lines: Vec::new(),
+            remaining: refundable.checked_sub(amount)?,
+            timestamp: now,
+        })
+    }
+
+    /// What the customer paid for each pizza on `order`, after discounts,
+    /// tax and points: the payment spread over the pizzas by price.
+    pub fn line_amounts(&self, order: &order) -> Result<Vec<Money>, BillingError> {
+        let receipt = order.receipt.as_ref().ok_or(BillingError::NotPaid(order.id.0))?;
+        let paid = receipt.amount_due()?;
+        let subtotal = receipt.subtotal.minor_units();
+        let last = order.pizzas.len().saturating_sub(1);
+
+        let mut left = paid;
+        let mut amounts = Vec::with_capacity(order.pizzas.len());
+        for (idx, pizza) in order.pizzas.iter().enumerate() {
+            let share = if idx == last || subtotal == 0 {
+                left
+            } else {
+                paid.apply_rate(pizza.cost()?.minor_units(), subtotal, Rounding::Down)?
+            };
+            left = left.checked_sub(share)?;
+            amounts.push(share);
+        }
+        Ok(amounts)
+    }
+
+    /// Refund due for the pizzas at `lines` (positions from 0).
+    ///
+    /// Fails for a line the order does not have or one already refunded.
+    pub fn line_refund(&self, order: &order, lines: &[usize]) -> Result<Money, BillingError> {
+        let amounts = self.line_amounts(order)?;
+        let receipt = order.receipt.as_ref().ok_or(BillingError::NotPaid(order.id.0))?;
+        let mut total = Money::zero(receipt.subtotal.currency());
+        for (n, &line) in lines.iter().enumerate() {
+            let amount = *amounts.get(line).ok_or(BillingError::UnknownLine(line))?;
+            if lines[..n].contains(&line) || receipt.refunds.iter().any(|r| r.lines.contains(&line)) {
+                return Err(BillingError::LineAlreadyRefunded(line));
+            }
+            total = total.checked_add(amount)?;
+        }
+        Ok(total)
     }
+
+    /// Book everything a successful payment for `receipt` uses up or earns:
+    /// the coupon, the points spent and the points earned on
+    /// [`amount_due`](PaymentReceipt::amount_due).
+    ///
+    /// All or nothing: if the points cannot be booked the coupon is
+    /// released again, so a failed settle leaves nothing to undo.
+    pub fn settle(&self, receipt: &PaymentReceipt) -> Result<(), BillingError> {
+        self.redeem_coupon(receipt)?;
+        if let (Some(ledger), Some(customer)) = (&self.loyalty, receipt.customer) {
+            let points = receipt.points.map_or(0, |tender| tender.points);
+            if let Err(e) = ledger.settle(customer, receipt.order_id, points, receipt.amount_due()?, SystemTime::now()) {
+                if let Some(claim) = &receipt.coupon {
+                    self.coupons.release(claim);
+                }
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for BillingEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Milliseconds since the Unix epoch, for receipt timestamps.
+fn now_millis() -> Result<u128, BillingError> {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_millis())
+        .map_err(|e| BillingError::InvoiceError(format!("system clock is before 1970: {}", e)))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::pizza::{Margherita, Cheese};
+    use crate::billing::{
+        BuyNGetMFree, Coupon, CouponClaim, FlatDiscount, GstPolicy, NoTax, PercentageDiscount,
+    };
+    use crate::money::Currency;
+    use crate::pizza::{Margherita, Cheese, Farmhouse};
 
     #[test]
     fn test_calculate_total() {
-        let engine = BillingEngine::new();
+        let engine = BillingEngine::with_tax_policy(NoTax);
 
         let pizza1 = Box::new(Margherita::new());
         let pizza2 = Box::new(Cheese::new(Box::new(Margherita::new())));
 
         let order = order::new(vec![pizza1, pizza2]);
-        let total = engine.calculate_total(&order);
+        let total = engine.calculate_total(&order).unwrap();
 
-        assert_eq!(total, 250.0); // 120 + 130
+        assert_eq!(total, Money::inr(250)); // 120 + 130
     }
 
     #[test]
@@ -90,10 +515,216 @@ mod tests {
         let pizza = Box::new(Margherita::new());
         let order = order::new(vec![pizza]);
 
-        let receipt = engine.generate_receipt(&order);
+        let receipt = engine.generate_receipt(&order).unwrap();
 
         assert_eq!(receipt.order_id, order.id.0);
-        assert_eq!(receipt.total_amount, 120.0);
+        assert_eq!(receipt.subtotal, Money::inr(120));
+        assert_eq!(receipt.total_tax().unwrap(), Money::inr(6));
+        assert_eq!(receipt.total_amount, Money::inr(126));
         assert!(receipt.timestamp > 0);
     }
+
+    #[test]
+    fn receipt_itemises_gst() {
+        let engine = BillingEngine::new();
+        let order = order::new(vec![
+            Box::new(Margherita::new()),
+            Box::new(Cheese::new(Box::new(Margherita::new()))),
+        ]);
+
+        let receipt = engine.generate_receipt(&order).unwrap();
+
+        let names: Vec<_> = receipt.tax_lines.iter().map(|l| l.name.as_str()).collect();
+        assert_eq!(names, vec!["CGST", "SGST"]);
+        // 2.5% of ₹250 = ₹6.25 each
+        assert_eq!(receipt.tax_lines[0].amount, Money::from_minor(625, Currency::Inr));
+        assert_eq!(receipt.total_amount, Money::from_minor(26_250, Currency::Inr));
+    }
+
+    #[test]
+    fn inter_state_receipt_uses_igst() {
+        let engine = BillingEngine::with_tax_policy(GstPolicy::inter_state());
+        let order = order::new(vec![Box::new(Margherita::new())]);
+
+        let receipt = engine.generate_receipt(&order).unwrap();
+
+        assert_eq!(receipt.tax_lines.len(), 1);
+        assert_eq!(receipt.tax_lines[0].name, "IGST");
+        assert_eq!(receipt.total_amount, Money::inr(126));
+    }
+
+    #[test]
+    fn discounts_apply_in_stage_order_before_tax() {
+        // Flat registered first but runs after percentage
+        let engine = BillingEngine::new()
+            .with_discount(FlatDiscount::new(Money::inr(20)))
+            .with_discount(PercentageDiscount::new(10));
+        let order = order::new(vec![
+            Box::new(Margherita::new()),
+            Box::new(Cheese::new(Box::new(Margherita::new()))),
+        ]);
+
+        let receipt = engine.generate_receipt(&order).unwrap();
+
+        // 250 - 10% = 225 - 20 = 205; CGST and SGST are ₹5.125 → ₹5.13 each
+        let amounts: Vec<_> = receipt.discounts.iter().map(|d| d.amount).collect();
+        assert_eq!(amounts, vec![Money::inr(25), Money::inr(20)]);
+        assert_eq!(receipt.total_discount().unwrap(), Money::inr(45));
+        assert_eq!(receipt.total_tax().unwrap(), Money::from_minor(1026, Currency::Inr));
+        assert_eq!(receipt.total_amount, Money::from_minor(21_526, Currency::Inr));
+    }
+
+    #[test]
+    fn discount_never_exceeds_bill() {
+        let engine = BillingEngine::with_tax_policy(NoTax)
+            .with_discount(BuyNGetMFree::new(0, 1))
+            .with_discount(FlatDiscount::new(Money::inr(50)));
+        let order = order::new(vec![Box::new(Margherita::new())]);
+
+        let receipt = engine.generate_receipt(&order).unwrap();
+        assert_eq!(receipt.discounts.len(), 1);
+        assert!(receipt.total_amount.is_zero());
+    }
+
+    #[test]
+    fn coupon_is_validated_itemised_and_redeemed() {
+        let engine = BillingEngine::with_tax_policy(NoTax);
+        engine.coupons().add(
+            Coupon::new("FLAT50", FlatDiscount::new(Money::inr(50))).per_customer_limit(1),
+        );
+        let mut order = order::new(vec![Box::new(Farmhouse::new())]);
+        order.apply_coupon(CouponClaim::new("flat50", "asha"));
+
+        let receipt = engine.generate_receipt(&order).unwrap();
+        assert_eq!(receipt.discounts[0].name, "₹50.00 off (FLAT50)");
+        assert_eq!(receipt.total_amount, Money::inr(100));
+
+        engine.redeem_coupon(&receipt).unwrap();
+        assert!(matches!(
+            engine.generate_receipt(&order),
+            Err(BillingError::CouponUsageExceeded { .. })
+        ));
+    }
+
+    #[test]
+    fn unknown_coupon_is_rejected() {
+        let engine = BillingEngine::new();
+        let mut order = order::new(vec![Box::new(Margherita::new())]);
+        order.apply_coupon(CouponClaim::new("BOGUS", "asha"));
+
+        assert!(matches!(
+            engine.generate_receipt(&order),
+            Err(BillingError::UnknownCoupon(code)) if code == "BOGUS"
+        ));
+    }
+
+    #[test]
+    fn line_refunds_split_the_payment_by_pizza() {
+        let engine = BillingEngine::new();
+        let mut order = order::new(vec![
+            Box::new(Margherita::new()),
+            Box::new(Cheese::new(Box::new(Margherita::new()))),
+        ]);
+        let receipt = engine.generate_receipt(&order).unwrap();
+        order.record_payment(receipt).unwrap();
+
+        // ₹262.50 paid for ₹120 + ₹130 of pizza
+        let lines = engine.line_amounts(&order).unwrap();
+        assert_eq!(lines, vec![Money::inr(126), Money::from_minor(13_650, Currency::Inr)]);
+
+        let refund = engine.quote_refund(order.receipt.as_ref().unwrap(), lines[1]).unwrap().with_lines(vec![1]);
+        order.receipt.as_mut().unwrap().refunds.push(refund);
+        assert!(matches!(engine.line_refund(&order, &[1]), Err(BillingError::LineAlreadyRefunded(1))));
+        assert!(matches!(engine.line_refund(&order, &[2]), Err(BillingError::UnknownLine(2))));
+        assert_eq!(engine.line_refund(&order, &[0]).unwrap(), Money::inr(126));
+    }
+
+    #[test]
+    fn refunds_go_back_to_the_latest_tender_first() {
+        let engine = BillingEngine::with_tax_policy(NoTax);
+        let mut receipt = engine.generate_receipt(&order::new(vec![Box::new(Margherita::new())])).unwrap();
+        receipt.tenders = vec![
+            Tender { provider: "upi".to_string(), amount: Money::inr(100) },
+            Tender { provider: "card".to_string(), amount: Money::inr(20) },
+        ];
+        receipt.refunds.push(engine.quote_refund(&receipt, Money::inr(15)).unwrap());
+
+        // ₹5 is left on the card, the rest of ₹30 comes off the UPI payment
+        let parts = receipt.refund_tenders(Money::inr(30)).unwrap();
+        assert_eq!(
+            parts,
+            vec![
+                Tender { provider: "card".to_string(), amount: Money::inr(5) },
+                Tender { provider: "upi".to_string(), amount: Money::inr(25) },
+            ]
+        );
+        assert!(matches!(receipt.refund_tenders(Money::inr(106)), Err(BillingError::OverRefund { .. })));
+
+        // A stored refund in another currency is an error, not a panic
+        let mut stray = engine.quote_refund(&receipt, Money::inr(1)).unwrap();
+        stray.amount = Money::from_minor(100, Currency::Usd);
+        receipt.refunds.push(stray);
+        assert!(receipt.refundable().is_err());
+        assert!(receipt.refund_tenders(Money::inr(1)).is_err());
+    }
+
+    #[test]
+    fn points_pay_part_of_the_bill_without_changing_tax() {
+        use crate::billing::{LoyaltyLedger, LoyaltyProgram};
+        use crate::customer::Customer;
+
+        let ledger = LoyaltyLedger::new(LoyaltyProgram::default());
+        let engine = BillingEngine::new().with_loyalty(ledger.clone());
+        let asha = Customer::new(CustomerId(1), "Asha", "9876543210").unwrap();
+        ledger.earn(asha.id, 900, Money::inr(20_000), SystemTime::now()).unwrap();
+
+        let mut order = order::new(vec![Box::new(Margherita::new())]).for_customer(&asha).unwrap();
+        order.pay_with_points(500);
+        assert!(matches!(
+            engine.generate_receipt(&order),
+            Err(BillingError::InsufficientPoints { requested: 500, available: 200 })
+        ));
+
+        // ₹126 bill: only 126 of the 200 points are needed
+        order.pay_with_points(200);
+        let receipt = engine.generate_receipt(&order).unwrap();
+        assert_eq!(receipt.total_tax().unwrap(), Money::inr(6));
+        assert_eq!(receipt.points, Some(PointsTender { points: 126, value: Money::inr(126) }));
+        assert!(receipt.amount_due().unwrap().is_zero());
+
+        engine.settle(&receipt).unwrap();
+        assert_eq!(ledger.balance(asha.id, SystemTime::now()), 74);
+
+        let mut walk_in = order::new(vec![Box::new(Margherita::new())]);
+        walk_in.pay_with_points(10);
+        assert!(matches!(engine.generate_receipt(&walk_in), Err(BillingError::NoLoyaltyAccount)));
+
+        let worthless = LoyaltyLedger::new(LoyaltyProgram::default().with_point_value(Money::inr(0)));
+        worthless.earn(asha.id, 900, Money::inr(20_000), SystemTime::now()).unwrap();
+        let engine = BillingEngine::new().with_loyalty(worthless);
+        assert!(matches!(engine.generate_receipt(&order), Err(BillingError::PointsNotRedeemable)));
+    }
+
+    #[test]
+    fn failed_settle_books_nothing() {
+        use crate::billing::{LoyaltyLedger, LoyaltyProgram};
+        use crate::customer::Customer;
+
+        let ledger = LoyaltyLedger::new(LoyaltyProgram::default());
+        let engine = BillingEngine::new().with_loyalty(ledger.clone());
+        engine.coupons().add(Coupon::new("FLAT50", FlatDiscount::new(Money::inr(50))).per_customer_limit(1));
+        let asha = Customer::new(CustomerId(1), "Asha", "9876543210").unwrap();
+        ledger.earn(asha.id, 900, Money::inr(5_000), SystemTime::now()).unwrap();
+
+        let mut order = order::new(vec![Box::new(Farmhouse::new())]).for_customer(&asha).unwrap();
+        order.apply_coupon(CouponClaim::new("FLAT50", "asha"));
+        order.pay_with_points(50);
+        let receipt = engine.generate_receipt(&order).unwrap();
+
+        // The points went elsewhere between quote and settle
+        ledger.spend(asha.id, 901, 50, SystemTime::now()).unwrap();
+        assert!(matches!(engine.settle(&receipt), Err(BillingError::InsufficientPoints { .. })));
+        assert_eq!(engine.coupons().usage("FLAT50", "asha"), 0);
+        assert_eq!(ledger.entries(asha.id).len(), 2);
+    }
 }
@@ -0,0 +1,296 @@
+//! Coupons — code-activated discounts with eligibility rules.
+//!
+//! A [`Coupon`] wraps any [`Discount`] with a validity window, a minimum
+//! order value and a per-customer usage limit. Coupons live in a shared
+//! [`CouponBook`], which validates claims and records redemptions.
+//!
+//! # Example
+//! ```
+//! use ferrispizza_lib::billing::{Coupon, CouponBook, PercentageDiscount};
+//! use ferrispizza_lib::money::Money;
+//! use std::time::SystemTime;
+//!
+//! let book = CouponBook::new();
+//! book.add(
+//!     Coupon::new("WEEKEND20", PercentageDiscount::new(20))
+//!         .min_order_value(Money::inr(300))
+//!         .per_customer_limit(1),
+//! );
+//!
+//! assert!(book.validate("WEEKEND20", "asha", Money::inr(350), SystemTime::now()).is_ok());
+//! assert!(book.validate("WEEKEND20", "asha", Money::inr(200), SystemTime::now()).is_err());
+//! ```
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::errors::BillingError;
+use crate::money::Money;
+use super::discount::Discount;
+
+/// A customer's request to use a coupon on an order.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CouponClaim {
+    pub code: String,
+    /// Key used for per-customer usage limits.
+    pub customer: String,
+}
+
+impl CouponClaim {
+    pub fn new(code: impl Into<String>, customer: impl Into<String>) -> Self {
+        Self {
+            code: normalize(&code.into()),
+            customer: customer.into(),
+        }
+    }
+}
+
+/// A discount unlocked by a code.
+#[derive(Clone)]
+pub struct Coupon {
+    pub code: String,
+    discount: Arc<dyn Discount>,
+    pub valid_from: Option<SystemTime>,
+    pub valid_until: Option<SystemTime>,
+    pub min_order_value: Option<Money>,
+    pub per_customer_limit: Option<u32>,
+}
+
+impl Coupon {
+    /// Coupon without restrictions. Codes are case-insensitive.
+    pub fn new(code: impl Into<String>, discount: impl Discount + 'static) -> Self {
+        Self {
+            code: normalize(&code.into()),
+            discount: Arc::new(discount),
+            valid_from: None,
+            valid_until: None,
+            min_order_value: None,
+            per_customer_limit: None,
+        }
+    }
+
+    /// Only valid in `[from, until)`.
+    pub fn valid_between(mut self, from: SystemTime, until: SystemTime) -> Self {
+        self.valid_from = Some(from);
+        self.valid_until = Some(until);
+        self
+    }
+
+    /// Require a pre-discount subtotal of at least `amount`.
+    pub fn min_order_value(mut self, amount: Money) -> Self {
+        self.min_order_value = Some(amount);
+        self
+    }
+
+    /// Allow each customer to redeem the coupon at most `limit` times.
+    pub fn per_customer_limit(mut self, limit: u32) -> Self {
+        self.per_customer_limit = Some(limit);
+        self
+    }
+
+    pub fn discount(&self) -> Arc<dyn Discount> {
+        Arc::clone(&self.discount)
+    }
+}
+
+fn normalize(code: &str) -> String {
+    code.trim().to_ascii_uppercase()
+}
+
+#[derive(Default)]
+struct Inner {
+    coupons: HashMap<String, Coupon>,
+    /// (code, customer) → times redeemed
+    usage: HashMap<(String, String), u32>,
+}
+
+/// Thread-safe registry of coupons and their redemptions.
+///
+/// Clones share the same underlying book.
+#[derive(Clone, Default)]
+pub struct CouponBook {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl CouponBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register (or replace) a coupon.
+    pub fn add(&self, coupon: Coupon) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.coupons.insert(coupon.code.clone(), coupon);
+    }
+
+    /// Times `customer` has redeemed `code`.
+    pub fn usage(&self, code: &str, customer: &str) -> u32 {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .usage
+            .get(&(normalize(code), customer.to_string()))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Check that `customer` may use `code` on a bill of `subtotal` at `now`.
+    pub fn validate(
+        &self,
+        code: &str,
+        customer: &str,
+        subtotal: Money,
+        now: SystemTime,
+    ) -> Result<Coupon, BillingError> {
+        let inner = self.inner.lock().unwrap();
+        Self::check(&inner, &normalize(code), customer, subtotal, now)
+    }
+
+    /// Validate and count one redemption atomically.
+    pub fn redeem(
+        &self,
+        claim: &CouponClaim,
+        subtotal: Money,
+        now: SystemTime,
+    ) -> Result<(), BillingError> {
+        let mut inner = self.inner.lock().unwrap();
+        Self::check(&inner, &claim.code, &claim.customer, subtotal, now)?;
+        *inner
+            .usage
+            .entry((claim.code.clone(), claim.customer.clone()))
+            .or_insert(0) += 1;
+        Ok(())
+    }
+
+    /// Take back one redemption of `claim`, e.g. when the payment it was
+    /// counted for did not go through.
+    pub fn release(&self, claim: &CouponClaim) {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(used) = inner.usage.get_mut(&(claim.code.clone(), claim.customer.clone())) {
+            *used = used.saturating_sub(1);
+        }
+    }
+
+    fn check(
+        inner: &Inner,
+        code: &str,
+        customer: &str,
+        subtotal: Money,
+        now: SystemTime,
+    ) -> Result<Coupon, BillingError> {
+        let coupon = inner
+            .coupons
+            .get(code)
+            .ok_or_else(|| BillingError::UnknownCoupon(code.to_string()))?;
+
+        if coupon.valid_from.is_some_and(|from| now < from) {
+            return Err(BillingError::CouponNotYetValid(code.to_string()));
+        }
+        if coupon.valid_until.is_some_and(|until| now >= until) {
+            return Err(BillingError::CouponExpired(code.to_string()));
+        }
+        if let Some(minimum) = coupon.min_order_value
+            && !matches!(subtotal.partial_cmp(&minimum), Some(Ordering::Greater | Ordering::Equal))
+        {
+            return Err(BillingError::MinimumOrderNotMet {
+                code: code.to_string(),
+                minimum,
+            });
+        }
+        if let Some(limit) = coupon.per_customer_limit {
+            let used = inner
+                .usage
+                .get(&(code.to_string(), customer.to_string()))
+                .copied()
+                .unwrap_or(0);
+            if used >= limit {
+                return Err(BillingError::CouponUsageExceeded {
+                    code: code.to_string(),
+                    customer: customer.to_string(),
+                });
+            }
+        }
+
+        Ok(coupon.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::billing::FlatDiscount;
+    use std::time::Duration;
+
+    fn book() -> CouponBook {
+        let book = CouponBook::new();
+        book.add(
+            Coupon::new("flat50", FlatDiscount::new(Money::inr(50)))
+                .min_order_value(Money::inr(300))
+                .per_customer_limit(2),
+        );
+        book
+    }
+
+    #[test]
+    fn codes_are_case_insensitive() {
+        let now = SystemTime::now();
+        assert!(book().validate("FLAT50", "asha", Money::inr(300), now).is_ok());
+        assert!(book().validate(" Flat50 ", "asha", Money::inr(300), now).is_ok());
+    }
+
+    #[test]
+    fn unknown_and_minimum_rejections() {
+        let now = SystemTime::now();
+        let book = book();
+        assert!(matches!(
+            book.validate("NOPE", "asha", Money::inr(500), now),
+            Err(BillingError::UnknownCoupon(_))
+        ));
+        assert!(matches!(
+            book.validate("FLAT50", "asha", Money::inr(299), now),
+            Err(BillingError::MinimumOrderNotMet { .. })
+        ));
+    }
+
+    #[test]
+    fn validity_window_is_enforced() {
+        let now = SystemTime::now();
+        let day = Duration::from_secs(86_400);
+        let book = CouponBook::new();
+        book.add(
+            Coupon::new("WEEKLY", FlatDiscount::new(Money::inr(20)))
+                .valid_between(now, now + 7 * day),
+        );
+
+        assert!(matches!(
+            book.validate("WEEKLY", "asha", Money::inr(100), now - day),
+            Err(BillingError::CouponNotYetValid(_))
+        ));
+        assert!(book.validate("WEEKLY", "asha", Money::inr(100), now + day).is_ok());
+        assert!(matches!(
+            book.validate("WEEKLY", "asha", Money::inr(100), now + 7 * day),
+            Err(BillingError::CouponExpired(_))
+        ));
+    }
+
+    #[test]
+    fn per_customer_limit_counts_redemptions() {
+        let now = SystemTime::now();
+        let book = book();
+        let claim = CouponClaim::new("flat50", "asha");
+
+        book.redeem(&claim, Money::inr(400), now).unwrap();
+        book.redeem(&claim, Money::inr(400), now).unwrap();
+        assert!(matches!(
+            book.redeem(&claim, Money::inr(400), now),
+            Err(BillingError::CouponUsageExceeded { .. })
+        ));
+
+        // Another customer is unaffected
+        assert!(book.redeem(&CouponClaim::new("flat50", "ravi"), Money::inr(400), now).is_ok());
+        assert_eq!(book.usage("FLAT50", "asha"), 2);
+    }
+}
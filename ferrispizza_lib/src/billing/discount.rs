@@ -0,0 +1,247 @@
+//! Discount rules — promotions applied to an order before tax.
+//!
+//! Every rule implements [`Discount`]. The
+//! [`BillingEngine`](super::BillingEngine) sorts active rules by
+//! [`DiscountStage`] (item-level rules first, then percentage, then flat
+//! amounts) and applies them one after another, each seeing only what is
+//! left of the bill after the previous ones. Rules in the same stage keep
+//! the order in which they were registered.
+
+use serde::{Deserialize, Serialize};
+
+use crate::errors::BillingError;
+use crate::money::{Money, Rounding};
+use crate::order::order;
+
+/// Where a rule runs in the discount pipeline. Lower stages run first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DiscountStage {
+    /// Rules that make specific items free.
+    Item,
+    /// Percentage off what is left.
+    Percentage,
+    /// Fixed amount off what is left.
+    Flat,
+}
+
+/// Inputs available to a discount rule.
+pub struct DiscountContext<'a> {
+    pub order: &'a order,
+    /// Amount still payable after earlier rules.
+    pub remaining: Money,
+}
+
+/// A discount applied to a receipt.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DiscountLine {
+    pub name: String,
+    pub amount: Money,
+}
+
+/// A promotion rule.
+pub trait Discount: Send + Sync {
+    /// Label printed on the receipt.
+    fn name(&self) -> String;
+
+    fn stage(&self) -> DiscountStage;
+
+    /// Amount to take off. The engine never lets the total discount exceed
+    /// [`DiscountContext::remaining`].
+    fn amount(&self, ctx: &DiscountContext<'_>) -> Result<Money, BillingError>;
+}
+
+/// Percentage off the remaining bill, optionally capped.
+pub struct PercentageDiscount {
+    pub bps: u32,
+    pub cap: Option<Money>,
+}
+
+impl PercentageDiscount {
+    /// `percent` whole percent off (e.g. `10` → 10%).
+    pub fn new(percent: u32) -> Self {
+        Self {
+            bps: percent * 100,
+            cap: None,
+        }
+    }
+
+    /// Never take off more than `cap`.
+    pub fn with_cap(mut self, cap: Money) -> Self {
+        self.cap = Some(cap);
+        self
+    }
+}
+
+impl Discount for PercentageDiscount {
+    fn name(&self) -> String {
+        format!("{}.{:02}% off", self.bps / 100, self.bps % 100)
+    }
+
+    fn stage(&self) -> DiscountStage {
+        DiscountStage::Percentage
+    }
+
+    fn amount(&self, ctx: &DiscountContext<'_>) -> Result<Money, BillingError> {
+        // Round in the customer's disfavour by at most one paisa
+        let off = ctx.remaining.percent_bps(self.bps, Rounding::Down)?;
+        Ok(match self.cap {
+            Some(cap) if off > cap => cap,
+            _ => off,
+        })
+    }
+}
+
+/// Fixed amount off the bill.
+pub struct FlatDiscount {
+    pub amount: Money,
+}
+
+impl FlatDiscount {
+    pub fn new(amount: Money) -> Self {
+        Self { amount }
+    }
+}
+
+impl Discount for FlatDiscount {
+    fn name(&self) -> String {
+        format!("{} off", self.amount)
+    }
+
+    fn stage(&self) -> DiscountStage {
+        DiscountStage::Flat
+    }
+
+    fn amount(&self, _ctx: &DiscountContext<'_>) -> Result<Money, BillingError> {
+        Ok(self.amount)
+    }
+}
+
+/// Buy `buy` pizzas, get `free` more free.
+///
+/// Pizzas are ranked by price, most expensive first, and grouped in runs of
+/// `buy + free`; the cheapest `free` pizzas of each full run are free.
+pub struct BuyNGetMFree {
+    pub buy: usize,
+    pub free: usize,
+}
+
+impl BuyNGetMFree {
+    pub fn new(buy: usize, free: usize) -> Self {
+        Self { buy, free }
+    }
+}
+
+impl Discount for BuyNGetMFree {
+    fn name(&self) -> String {
+        format!("Buy {} get {} free", self.buy, self.free)
+    }
+
+    fn stage(&self) -> DiscountStage {
+        DiscountStage::Item
+    }
+
+    fn amount(&self, ctx: &DiscountContext<'_>) -> Result<Money, BillingError> {
+        let currency = ctx.remaining.currency();
+        let group = self.buy + self.free;
+        if self.free == 0 || group == 0 {
+            return Ok(Money::zero(currency));
+        }
+
+        let mut prices = ctx.order.pizzas.iter().map(|p| p.cost()).collect::<Result<Vec<_>, _>>()?;
+        prices.sort_by_key(|p| std::cmp::Reverse(p.minor_units()));
+
+        let free = prices
+            .chunks_exact(group)
+            .flat_map(|chunk| chunk[self.buy..].iter().copied());
+        Ok(Money::checked_sum(free, currency)?)
+    }
+}
+
+/// Makes the cheapest `per_pizza` toppings on every pizza free.
+pub struct FreeTopping {
+    pub per_pizza: usize,
+}
+
+impl FreeTopping {
+    pub fn new(per_pizza: usize) -> Self {
+        Self { per_pizza }
+    }
+}
+
+impl Discount for FreeTopping {
+    fn name(&self) -> String {
+        match self.per_pizza {
+            1 => "Free topping".to_string(),
+            n => format!("{} free toppings", n),
+        }
+    }
+
+    fn stage(&self) -> DiscountStage {
+        DiscountStage::Item
+    }
+
+    fn amount(&self, ctx: &DiscountContext<'_>) -> Result<Money, BillingError> {
+        let mut total = Money::zero(ctx.remaining.currency());
+        for pizza in &ctx.order.pizzas {
+            let mut prices: Vec<Money> =
+                pizza.topping_charges().into_iter().map(|(_, price)| price).collect();
+            prices.sort_by_key(|p| p.minor_units());
+
+            for price in prices.into_iter().take(self.per_pizza) {
+                total = total.checked_add(price)?;
+            }
+        }
+        Ok(total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pizza::{Cheese, Farmhouse, Jalapenos, Margherita, Olives, Pizza};
+
+    fn ctx(order: &order) -> DiscountContext<'_> {
+        DiscountContext {
+            order,
+            remaining: order.total_cost().unwrap(),
+        }
+    }
+
+    #[test]
+    fn percentage_discount_rounds_down_and_caps() {
+        let order = order::new(vec![Box::new(Margherita::new()) as Box<dyn Pizza>]);
+        assert_eq!(PercentageDiscount::new(15).amount(&ctx(&order)).unwrap(), Money::inr(18));
+
+        let capped = PercentageDiscount::new(50).with_cap(Money::inr(25));
+        assert_eq!(capped.amount(&ctx(&order)).unwrap(), Money::inr(25));
+    }
+
+    #[test]
+    fn buy_two_get_one_frees_cheapest_of_each_group() {
+        let order = order::new(vec![
+            Box::new(Farmhouse::new()) as Box<dyn Pizza>,
+            Box::new(Margherita::new()),
+            Box::new(Farmhouse::new()),
+            Box::new(Margherita::new()),
+        ]);
+
+        // Ranked 150, 150, 120 | 120 → only the first full group counts
+        let off = BuyNGetMFree::new(2, 1).amount(&ctx(&order)).unwrap();
+        assert_eq!(off, Money::inr(120));
+    }
+
+    #[test]
+    fn free_topping_takes_cheapest_per_pizza() {
+        let loaded = Olives::new(Box::new(Jalapenos::new(Box::new(Cheese::new(Box::new(
+            Margherita::new(),
+        ))))));
+        let order = order::new(vec![
+            Box::new(loaded) as Box<dyn Pizza>,
+            Box::new(Margherita::new()),
+        ]);
+
+        // Cheese (10) is the cheapest of cheese/jalapenos/olives
+        assert_eq!(FreeTopping::new(1).amount(&ctx(&order)).unwrap(), Money::inr(10));
+        assert_eq!(FreeTopping::new(2).amount(&ctx(&order)).unwrap(), Money::inr(22));
+    }
+}
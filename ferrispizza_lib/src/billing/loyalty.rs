@@ -0,0 +1,551 @@
+//! Loyalty points — earned on paid bills, spent as a tender.
+//!
+//! A [`LoyaltyProgram`] sets the earning tiers, what one point is worth
+//! and how long points last. The [`LoyaltyLedger`] records every earn,
+//! spend and reversal per customer. Balances only count unexpired points,
+//! and points are spent oldest first.
+//!
+//! # Example
+//! ```
+//! use ferrispizza_lib::billing::{LoyaltyLedger, LoyaltyProgram};
+//! use ferrispizza_lib::customer::CustomerId;
+//! use ferrispizza_lib::money::Money;
+//! use std::time::SystemTime;
+//!
+//! let ledger = LoyaltyLedger::new(LoyaltyProgram::default());
+//! let asha = CustomerId(1);
+//! let now = SystemTime::now();
+//!
+//! // Bronze earns 1 point per ₹100
+//! assert_eq!(ledger.earn(asha, 7, Money::inr(450), now).unwrap(), 4);
+//! ledger.spend(asha, 8, 3, now).unwrap();
+//! assert_eq!(ledger.balance(asha, now), 1);
+//!
+//! // Order 8 was refunded: its points come back
+//! ledger.reverse(8, now).unwrap();
+//! assert_eq!(ledger.balance(asha, now), 4);
+//! ```
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+use crate::customer::CustomerId;
+use crate::errors::{BillingError, OrderError, StorageError};
+use crate::money::Money;
+
+/// Earning rate for customers who have spent at least `min_spend`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LoyaltyTier {
+    pub name: String,
+    /// Lifetime spend needed to reach the tier.
+    pub min_spend: Money,
+    /// Points earned per 100 major units (₹100) paid.
+    pub points_per_100: u32,
+}
+
+/// Rules for earning and spending points.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LoyaltyProgram {
+    /// Sorted by `min_spend`.
+    tiers: Vec<LoyaltyTier>,
+    /// What one point pays for.
+    pub point_value: Money,
+    /// How long points last after they are earned.
+    pub expiry: Duration,
+}
+
+impl LoyaltyProgram {
+    /// Program without tiers (nothing is earned), where a point is worth ₹1
+    /// and lasts a year.
+    pub fn new() -> Self {
+        Self {
+            tiers: Vec::new(),
+            point_value: Money::inr(1),
+            expiry: Duration::from_secs(365 * 86_400),
+        }
+    }
+
+    pub fn with_tier(mut self, name: impl Into<String>, min_spend: Money, points_per_100: u32) -> Self {
+        self.tiers.push(LoyaltyTier { name: name.into(), min_spend, points_per_100 });
+        self.tiers.sort_by_key(|t| t.min_spend.minor_units());
+        self
+    }
+
+    pub fn with_point_value(mut self, value: Money) -> Self {
+        self.point_value = value;
+        self
+    }
+
+    pub fn with_expiry(mut self, expiry: Duration) -> Self {
+        self.expiry = expiry;
+        self
+    }
+
+    pub fn tiers(&self) -> &[LoyaltyTier] {
+        &self.tiers
+    }
+
+    /// Highest tier reached with `lifetime_spend`.
+    pub fn tier_for(&self, lifetime_spend: Money) -> Option<&LoyaltyTier> {
+        self.tiers.iter().rev().find(|t| {
+            matches!(lifetime_spend.partial_cmp(&t.min_spend), Some(Ordering::Greater | Ordering::Equal))
+        })
+    }
+
+    /// Points for paying `amount`, at the tier reached before this payment.
+    pub fn points_for(&self, lifetime_spend: Money, amount: Money) -> u32 {
+        let Some(tier) = self.tier_for(lifetime_spend) else {
+            return 0;
+        };
+        let hundreds = amount.minor_units().max(0) / (100 * amount.currency().minor_per_major());
+        u32::try_from(hundreds.saturating_mul(i64::from(tier.points_per_100))).unwrap_or(u32::MAX)
+    }
+}
+
+impl Default for LoyaltyProgram {
+    /// Bronze from the first order, Silver from ₹2,000 and Gold from ₹5,000.
+    fn default() -> Self {
+        Self::new()
+            .with_tier("Bronze", Money::inr(0), 1)
+            .with_tier("Silver", Money::inr(2_000), 2)
+            .with_tier("Gold", Money::inr(5_000), 3)
+    }
+}
+
+/// What a ledger entry did.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryKind {
+    /// Points earned by paying `spend` for the order.
+    Earned { spend: Money, expires_at: SystemTime },
+    /// Points used to pay for the order.
+    Spent,
+    /// Points earned by the order taken back after a refund.
+    EarnReversed,
+    /// Points earned by the order taken back after `refunded` of it was
+    /// refunded, leaving the rest of the order paid.
+    EarnReduced { refunded: Money },
+    /// Points spent on the order handed back after a refund.
+    SpendReversed,
+}
+
+/// One line of a customer's points statement.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub customer: CustomerId,
+    pub order_id: u64,
+    pub kind: EntryKind,
+    pub points: u32,
+    pub at: SystemTime,
+}
+
+/// Points earned by one order.
+struct Lot {
+    customer: CustomerId,
+    order_id: u64,
+    spend: Money,
+    left: u32,
+    expires_at: SystemTime,
+    reversed: bool,
+}
+
+#[derive(Default)]
+struct State {
+    entries: Vec<LedgerEntry>,
+    /// In the order they were earned, so spending takes the oldest first.
+    lots: Vec<Lot>,
+    /// Order id → (lot index, points) taken to pay for it.
+    spends: HashMap<u64, Vec<(usize, u32)>>,
+    /// Append-only file holding every entry.
+    file: Option<File>,
+}
+
+/// Thread-safe points ledger. Clones share the same ledger.
+#[derive(Clone)]
+pub struct LoyaltyLedger {
+    program: Arc<LoyaltyProgram>,
+    state: Arc<Mutex<State>>,
+}
+
+impl LoyaltyLedger {
+    /// Ledger that lives only as long as the process.
+    pub fn new(program: LoyaltyProgram) -> Self {
+        Self {
+            program: Arc::new(program),
+            state: Arc::new(Mutex::new(State::default())),
+        }
+    }
+
+    /// Open (or create) the ledger file at `path` and replay its entries.
+    pub fn open(program: LoyaltyProgram, path: impl AsRef<Path>) -> Result<Self, StorageError> {
+        let path = path.as_ref();
+        let ledger = Self::new(program);
+        {
+            let mut state = ledger.lock();
+            if path.exists() {
+                for (n, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+                    let line = line?;
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    let entry: LedgerEntry = serde_json::from_str(&line).map_err(|e| StorageError::Corrupt {
+                        file: path.display().to_string(),
+                        line: n + 1,
+                        reason: e.to_string(),
+                    })?;
+                    apply(&mut state, entry);
+                }
+            }
+            state.file = Some(OpenOptions::new().create(true).append(true).open(path)?);
+        }
+        Ok(ledger)
+    }
+
+    pub fn program(&self) -> &LoyaltyProgram {
+        &self.program
+    }
+
+    /// Award points for `order_id`, paid `spend` by `customer`.
+    ///
+    /// Returns the points earned; an order only earns once, so a repeat
+    /// call returns 0.
+    pub fn earn(&self, customer: CustomerId, order_id: u64, spend: Money, now: SystemTime) -> Result<u32, StorageError> {
+        let mut state = self.lock();
+        if state.lots.iter().any(|lot| lot.order_id == order_id) {
+            return Ok(0);
+        }
+        let points = self.program.points_for(lifetime_spend(&state, customer, spend), spend);
+        let kind = EntryKind::Earned { spend, expires_at: now + self.program.expiry };
+        record(&mut state, LedgerEntry { customer, order_id, kind, points, at: now })?;
+        Ok(points)
+    }
+
+    /// Pay for `order_id` with `points`, oldest points first.
+    ///
+    /// Fails with [`BillingError::InsufficientPoints`] if the customer has
+    /// fewer unexpired points. Spending again for the same order does nothing.
+    pub fn spend(&self, customer: CustomerId, order_id: u64, points: u32, now: SystemTime) -> Result<(), BillingError> {
+        let mut state = self.lock();
+        if points == 0 || state.spends.contains_key(&order_id) {
+            return Ok(());
+        }
+        let available = balance(&state, customer, now);
+        if points > available {
+            return Err(BillingError::InsufficientPoints { requested: points, available });
+        }
+        let entry = LedgerEntry { customer, order_id, kind: EntryKind::Spent, points, at: now };
+        record(&mut state, entry).map_err(OrderError::from)?;
+        Ok(())
+    }
+
+    /// Spend `points` on `order_id` and award points for `spend`, as one
+    /// write: either both are booked or neither is.
+    ///
+    /// Returns the points earned. Like [`spend`](Self::spend) and
+    /// [`earn`](Self::earn), repeating it for the same order does nothing.
+    pub fn settle(
+        &self,
+        customer: CustomerId,
+        order_id: u64,
+        points: u32,
+        spend: Money,
+        now: SystemTime,
+    ) -> Result<u32, BillingError> {
+        let mut state = self.lock();
+        let mut entries = Vec::new();
+        if points > 0 && !state.spends.contains_key(&order_id) {
+            let available = balance(&state, customer, now);
+            if points > available {
+                return Err(BillingError::InsufficientPoints { requested: points, available });
+            }
+            entries.push(LedgerEntry { customer, order_id, kind: EntryKind::Spent, points, at: now });
+        }
+        let mut earned = 0;
+        if !state.lots.iter().any(|lot| lot.order_id == order_id) {
+            earned = self.program.points_for(lifetime_spend(&state, customer, spend), spend);
+            let kind = EntryKind::Earned { spend, expires_at: now + self.program.expiry };
+            entries.push(LedgerEntry { customer, order_id, kind, points: earned, at: now });
+        }
+        record_all(&mut state, entries).map_err(OrderError::from)?;
+        Ok(earned)
+    }
+
+    /// Undo everything `order_id` did to the ledger after a refund: spent
+    /// points are handed back and earned points taken away.
+    pub fn reverse(&self, order_id: u64, now: SystemTime) -> Result<(), StorageError> {
+        let mut state = self.lock();
+        if let Some(taken) = state.spends.get(&order_id)
+            && let Some(&(first, _)) = taken.first()
+        {
+            let points = taken.iter().map(|&(_, p)| p).sum();
+            let customer = state.lots[first].customer;
+            let entry = LedgerEntry { customer, order_id, kind: EntryKind::SpendReversed, points, at: now };
+            record(&mut state, entry)?;
+        }
+        if let Some(lot) = state.lots.iter().find(|lot| lot.order_id == order_id && !lot.reversed) {
+            let earned = earned_on(&state, order_id).map_or(0, |(points, _)| points);
+            let entry = LedgerEntry {
+                customer: lot.customer,
+                order_id,
+                kind: EntryKind::EarnReversed,
+                points: earned.saturating_sub(reduced_on(&state, order_id)),
+                at: now,
+            };
+            record(&mut state, entry)?;
+        }
+        Ok(())
+    }
+
+    /// Take back the share of `order_id`'s earned points that paid for
+    /// `refunded`, after a partial refund. Points spent on the order stay
+    /// spent until it is refunded in full.
+    pub fn reverse_part(&self, order_id: u64, refunded: Money, now: SystemTime) -> Result<(), StorageError> {
+        let mut state = self.lock();
+        let Some(lot) = state.lots.iter().find(|lot| lot.order_id == order_id && !lot.reversed) else {
+            return Ok(());
+        };
+        let customer = lot.customer;
+        let still_paid = (lot.spend.minor_units() - refunded.minor_units()).max(0);
+        let Some((earned, paid)) = earned_on(&state, order_id) else {
+            return Ok(());
+        };
+        let kept = match paid.minor_units() {
+            0 => 0,
+            paid => i64::from(earned) * still_paid / paid,
+        };
+        let points = (earned - u32::try_from(kept).unwrap_or(earned)).saturating_sub(reduced_on(&state, order_id));
+        let entry = LedgerEntry { customer, order_id, kind: EntryKind::EarnReduced { refunded }, points, at: now };
+        record(&mut state, entry)
+    }
+
+    /// Unexpired points `customer` can spend at `now`.
+    pub fn balance(&self, customer: CustomerId, now: SystemTime) -> u32 {
+        balance(&self.lock(), customer, now)
+    }
+
+    /// The customer's current tier, from what they have paid so far.
+    pub fn tier(&self, customer: CustomerId) -> Option<LoyaltyTier> {
+        let state = self.lock();
+        let spent = lifetime_spend(&state, customer, self.program.point_value);
+        self.program.tier_for(spent).cloned()
+    }
+
+    /// Every entry for `customer`, oldest first.
+    pub fn entries(&self, customer: CustomerId) -> Vec<LedgerEntry> {
+        self.lock().entries.iter().filter(|e| e.customer == customer).cloned().collect()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().expect("loyalty ledger lock poisoned")
+    }
+}
+
+/// Total the customer paid on orders that were not refunded, in the
+/// currency of `like`.
+fn lifetime_spend(state: &State, customer: CustomerId, like: Money) -> Money {
+    let minor = state
+        .lots
+        .iter()
+        .filter(|lot| lot.customer == customer && !lot.reversed)
+        .map(|lot| lot.spend.minor_units())
+        .sum();
+    Money::from_minor(minor, like.currency())
+}
+
+/// Points `order_id` earned and the spend that earned them.
+fn earned_on(state: &State, order_id: u64) -> Option<(u32, Money)> {
+    state.entries.iter().find_map(|e| match e.kind {
+        EntryKind::Earned { spend, .. } if e.order_id == order_id => Some((e.points, spend)),
+        _ => None,
+    })
+}
+
+/// Points already taken back from `order_id` by partial refunds.
+fn reduced_on(state: &State, order_id: u64) -> u32 {
+    state
+        .entries
+        .iter()
+        .filter(|e| e.order_id == order_id && matches!(e.kind, EntryKind::EarnReduced { .. }))
+        .map(|e| e.points)
+        .sum()
+}
+
+fn balance(state: &State, customer: CustomerId, now: SystemTime) -> u32 {
+    state
+        .lots
+        .iter()
+        .filter(|lot| lot.customer == customer && lot.expires_at > now)
+        .map(|lot| lot.left)
+        .sum()
+}
+
+/// Write `entry` to the file, then apply it.
+fn record(state: &mut State, entry: LedgerEntry) -> Result<(), StorageError> {
+    record_all(state, vec![entry])
+}
+
+/// Write `entries` to the file in one go, then apply them; on failure none
+/// is applied.
+fn record_all(state: &mut State, entries: Vec<LedgerEntry>) -> Result<(), StorageError> {
+    if let Some(file) = &mut state.file {
+        let mut lines = String::new();
+        for entry in &entries {
+            lines += &serde_json::to_string(entry).map_err(|e| StorageError::Serialize(e.to_string()))?;
+            lines.push('\n');
+        }
+        file.write_all(lines.as_bytes())?;
+        file.sync_data()?;
+    }
+    for entry in entries {
+        apply(state, entry);
+    }
+    Ok(())
+}
+
+/// Update balances for `entry`. Replaying the saved entries through this
+/// rebuilds the ledger exactly.
+fn apply(state: &mut State, entry: LedgerEntry) {
+    match entry.kind {
+        EntryKind::Earned { spend, expires_at } => state.lots.push(Lot {
+            customer: entry.customer,
+            order_id: entry.order_id,
+            spend,
+            left: entry.points,
+            expires_at,
+            reversed: false,
+        }),
+        EntryKind::Spent => {
+            let taken = take(state, entry.customer, entry.points, entry.at);
+            state.spends.insert(entry.order_id, taken);
+        }
+        EntryKind::SpendReversed => {
+            for (idx, points) in state.spends.remove(&entry.order_id).unwrap_or_default() {
+                state.lots[idx].left += points;
+            }
+        }
+        EntryKind::EarnReversed => {
+            let Some(idx) = state.lots.iter().position(|lot| lot.order_id == entry.order_id) else {
+                return;
+            };
+            let lot = &mut state.lots[idx];
+            // Points from this order that were already spent come out of
+            // the customer's other points, as far as they go.
+            let already_spent = entry.points.saturating_sub(lot.left);
+            lot.left = 0;
+            lot.reversed = true;
+            take(state, entry.customer, already_spent, entry.at);
+        }
+        EntryKind::EarnReduced { refunded } => {
+            let Some(lot) = state.lots.iter_mut().find(|lot| lot.order_id == entry.order_id) else {
+                return;
+            };
+            lot.spend = Money::from_minor((lot.spend.minor_units() - refunded.minor_units()).max(0), lot.spend.currency());
+            let from_lot = lot.left.min(entry.points);
+            lot.left -= from_lot;
+            // As for a full reversal, points already spent come out of the
+            // customer's other points
+            take(state, entry.customer, entry.points - from_lot, entry.at);
+        }
+    }
+    state.entries.push(entry);
+}
+
+/// Remove up to `points` from the customer's unexpired lots, oldest first.
+fn take(state: &mut State, customer: CustomerId, mut points: u32, now: SystemTime) -> Vec<(usize, u32)> {
+    let mut taken = Vec::new();
+    for (idx, lot) in state.lots.iter_mut().enumerate() {
+        if points == 0 {
+            break;
+        }
+        if lot.customer != customer || lot.expires_at <= now || lot.left == 0 {
+            continue;
+        }
+        let used = lot.left.min(points);
+        lot.left -= used;
+        points -= used;
+        taken.push((idx, used));
+    }
+    taken
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::money::Currency;
+
+    const DAY: Duration = Duration::from_secs(86_400);
+
+    #[test]
+    fn tiers_raise_the_earning_rate_and_points_expire() {
+        let ledger = LoyaltyLedger::new(LoyaltyProgram::default().with_expiry(30 * DAY));
+        let asha = CustomerId(1);
+        let start = SystemTime::now();
+
+        assert_eq!(ledger.earn(asha, 1, Money::inr(2_500), start).unwrap(), 25);
+        assert_eq!(ledger.tier(asha).unwrap().name, "Silver");
+        assert_eq!(ledger.earn(asha, 2, Money::inr(300), start + 10 * DAY).unwrap(), 6);
+        assert_eq!(ledger.earn(asha, 2, Money::inr(300), start + 10 * DAY).unwrap(), 0);
+
+        assert_eq!(ledger.balance(asha, start + 20 * DAY), 31);
+        assert_eq!(ledger.balance(asha, start + 35 * DAY), 6);
+        assert!(matches!(
+            ledger.spend(asha, 3, 10, start + 35 * DAY),
+            Err(BillingError::InsufficientPoints { requested: 10, available: 6 })
+        ));
+    }
+
+    #[test]
+    fn refunds_reverse_cleanly_and_survive_reopening() {
+        let path = std::env::temp_dir().join(format!("ferrispizza-loyalty-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let asha = CustomerId(1);
+        let now = SystemTime::now();
+        {
+            let ledger = LoyaltyLedger::open(LoyaltyProgram::default(), &path).unwrap();
+            ledger.earn(asha, 1, Money::inr(500), now).unwrap();
+            ledger.earn(asha, 2, Money::inr(300), now).unwrap();
+            // Order 3 spends 4 of order 1's 5 points, then order 1 is
+            // refunded: the 4 spent come out of the customer's other points.
+            ledger.spend(asha, 3, 4, now).unwrap();
+            ledger.earn(asha, 3, Money::inr(100), now).unwrap();
+            assert_eq!(ledger.balance(asha, now), 5);
+            ledger.reverse(1, now).unwrap();
+            assert_eq!(ledger.balance(asha, now), 0);
+
+            ledger.reverse(3, now).unwrap();
+            assert_eq!(ledger.balance(asha, now), 3);
+        }
+
+        let ledger = LoyaltyLedger::open(LoyaltyProgram::default(), &path).unwrap();
+        assert_eq!(ledger.balance(asha, now), 3);
+        assert_eq!(ledger.entries(asha).len(), 7);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn partial_refunds_take_back_their_share_of_points() {
+        let ledger = LoyaltyLedger::new(LoyaltyProgram::default());
+        let asha = CustomerId(1);
+        let now = SystemTime::now();
+        assert_eq!(ledger.earn(asha, 1, Money::inr(1_000), now).unwrap(), 10);
+
+        // A quarter back keeps 7 of the 10 points
+        ledger.reverse_part(1, Money::inr(250), now).unwrap();
+        assert_eq!(ledger.balance(asha, now), 7);
+        // With one paisa still paid, none are kept
+        ledger.reverse_part(1, Money::from_minor(74_999, Currency::Inr), now).unwrap();
+        assert_eq!(ledger.balance(asha, now), 0);
+
+        // Refunding the last paisa takes nothing more
+        ledger.reverse(1, now).unwrap();
+        assert_eq!(ledger.balance(asha, now), 0);
+        assert_eq!(ledger.entries(asha).len(), 4);
+    }
+}
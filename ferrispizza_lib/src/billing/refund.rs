@@ -0,0 +1,37 @@
+//! Refunds against a settled payment.
+//!
+//! A [`RefundReceipt`] records money returned for part or all of a
+//! [`PaymentReceipt`](super::PaymentReceipt). Refunds are quoted by
+//! [`BillingEngine::quote_refund`](super::BillingEngine::quote_refund),
+//! which enforces the refund window and never returns more than was paid.
+
+use serde::{Deserialize, Serialize};
+
+use crate::money::Money;
+
+/// Money returned to the customer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RefundReceipt {
+    pub order_id: u64,
+    pub amount: Money,
+    /// Order lines (pizza positions, from 0) refunded; empty when an
+    /// amount was refunded without naming lines.
+    #[serde(default)]
+    pub lines: Vec<usize>,
+    /// Still refundable on the payment after this refund.
+    pub remaining: Money,
+    pub timestamp: u128,
+}
+
+impl RefundReceipt {
+    /// Note which order lines the refund was for.
+    pub fn with_lines(mut self, lines: Vec<usize>) -> Self {
+        self.lines = lines;
+        self
+    }
+
+    /// Whether nothing is left to refund on the payment.
+    pub fn is_full(&self) -> bool {
+        self.remaining.is_zero()
+    }
+}
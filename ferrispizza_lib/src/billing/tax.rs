@@ -0,0 +1,253 @@
+//! Tax policies — turns billable items into itemised tax lines.
+//!
+//! [`TaxPolicy`] is the extension point used by the
+//! [`BillingEngine`](super::BillingEngine). [`GstPolicy`] implements Indian
+//! GST: intra-state supplies are split evenly into CGST and SGST, inter-state
+//! supplies carry a single IGST line.
+//!
+//! # Example
+//! ```
+//! use ferrispizza_lib::billing::{BillItem, GstPolicy, TaxCategory, TaxPolicy};
+//! use ferrispizza_lib::money::Money;
+//!
+//! let gst = GstPolicy::intra_state();
+//! let lines = gst
+//!     .tax_lines(&[BillItem::new(TaxCategory::Food, Money::inr(200))])
+//!     .unwrap();
+//!
+//! // 5% on food → 2.5% CGST + 2.5% SGST
+//! assert_eq!(lines.len(), 2);
+//! assert_eq!(lines[0].amount, Money::inr(5));
+//! ```
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::errors::BillingError;
+use crate::money::{Money, Rounding};
+
+/// Category that decides which tax rate applies to an item.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaxCategory {
+    #[default]
+    Food,
+    Beverage,
+    Packaging,
+}
+
+/// Whether the sale happens within one state or across state lines.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SupplyType {
+    /// Taxed as CGST + SGST.
+    IntraState,
+    /// Taxed as IGST.
+    InterState,
+}
+
+/// One taxable amount on the bill.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BillItem {
+    pub category: TaxCategory,
+    pub amount: Money,
+}
+
+impl BillItem {
+    pub fn new(category: TaxCategory, amount: Money) -> Self {
+        Self { category, amount }
+    }
+}
+
+/// A single itemised tax charge on a receipt.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TaxLine {
+    /// Tax component, e.g. `"CGST"`.
+    pub name: String,
+    pub category: TaxCategory,
+    /// Rate in basis points (250 = 2.5%).
+    pub rate_bps: u32,
+    /// Amount the rate was applied to.
+    pub taxable: Money,
+    pub amount: Money,
+}
+
+/// Pluggable tax computation used by the billing engine.
+pub trait TaxPolicy: Send + Sync {
+    /// Compute tax lines for the given billable items.
+    fn tax_lines(&self, items: &[BillItem]) -> Result<Vec<TaxLine>, BillingError>;
+}
+
+/// Policy that charges no tax (exports, tests).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoTax;
+
+impl TaxPolicy for NoTax {
+    fn tax_lines(&self, _items: &[BillItem]) -> Result<Vec<TaxLine>, BillingError> {
+        Ok(Vec::new())
+    }
+}
+
+/// Indian Goods and Services Tax.
+///
+/// Default rates: food 5%, beverages 5%, packaging 18%.
+#[derive(Clone, Debug)]
+pub struct GstPolicy {
+    rates_bps: BTreeMap<TaxCategory, u32>,
+    supply: SupplyType,
+    rounding: Rounding,
+}
+
+impl GstPolicy {
+    /// GST with default rates for the given supply type.
+    pub fn new(supply: SupplyType) -> Self {
+        let rates_bps = BTreeMap::from([
+            (TaxCategory::Food, 500),
+            (TaxCategory::Beverage, 500),
+            (TaxCategory::Packaging, 1800),
+        ]);
+        Self {
+            rates_bps,
+            supply,
+            rounding: Rounding::HalfUp,
+        }
+    }
+
+    /// Sale within the restaurant's own state (CGST + SGST).
+    pub fn intra_state() -> Self {
+        Self::new(SupplyType::IntraState)
+    }
+
+    /// Sale to another state (IGST).
+    pub fn inter_state() -> Self {
+        Self::new(SupplyType::InterState)
+    }
+
+    /// Override the total GST rate for a category, in basis points.
+    pub fn with_rate(mut self, category: TaxCategory, rate_bps: u32) -> Self {
+        self.rates_bps.insert(category, rate_bps);
+        self
+    }
+
+    /// Rounding applied to each tax line.
+    pub fn with_rounding(mut self, rounding: Rounding) -> Self {
+        self.rounding = rounding;
+        self
+    }
+
+    /// Total GST rate for a category in basis points (0 if unset).
+    pub fn rate_bps(&self, category: TaxCategory) -> u32 {
+        self.rates_bps.get(&category).copied().unwrap_or(0)
+    }
+
+    fn line(&self, name: &str, category: TaxCategory, rate_bps: u32, taxable: Money)
+        -> Result<TaxLine, BillingError>
+    {
+        Ok(TaxLine {
+            name: name.to_string(),
+            category,
+            rate_bps,
+            taxable,
+            amount: taxable.percent_bps(rate_bps, self.rounding)?,
+        })
+    }
+}
+
+impl Default for GstPolicy {
+    fn default() -> Self {
+        Self::intra_state()
+    }
+}
+
+impl TaxPolicy for GstPolicy {
+    fn tax_lines(&self, items: &[BillItem]) -> Result<Vec<TaxLine>, BillingError> {
+        // Aggregate per category so each component appears once per rate
+        let mut taxable: BTreeMap<TaxCategory, Money> = BTreeMap::new();
+        for item in items {
+            let entry = taxable
+                .entry(item.category)
+                .or_insert(Money::zero(item.amount.currency()));
+            *entry = entry.checked_add(item.amount)?;
+        }
+
+        let mut lines = Vec::new();
+        for (category, amount) in taxable {
+            let rate = self.rate_bps(category);
+            if rate == 0 || amount.is_zero() {
+                continue;
+            }
+
+            match self.supply {
+                SupplyType::IntraState => {
+                    // Each half is rounded separately, as printed on the invoice
+                    lines.push(self.line("CGST", category, rate / 2, amount)?);
+                    lines.push(self.line("SGST", category, rate - rate / 2, amount)?);
+                }
+                SupplyType::InterState => {
+                    lines.push(self.line("IGST", category, rate, amount)?);
+                }
+            }
+        }
+        Ok(lines)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::money::Currency;
+
+    fn items() -> Vec<BillItem> {
+        vec![
+            BillItem::new(TaxCategory::Food, Money::inr(250)),
+            BillItem::new(TaxCategory::Beverage, Money::inr(60)),
+            BillItem::new(TaxCategory::Packaging, Money::inr(20)),
+        ]
+    }
+
+    #[test]
+    fn intra_state_splits_into_cgst_and_sgst() {
+        let lines = GstPolicy::intra_state().tax_lines(&items()).unwrap();
+        let names: Vec<_> = lines.iter().map(|l| (l.name.as_str(), l.category)).collect();
+        assert_eq!(
+            names,
+            vec![
+                ("CGST", TaxCategory::Food),
+                ("SGST", TaxCategory::Food),
+                ("CGST", TaxCategory::Beverage),
+                ("SGST", TaxCategory::Beverage),
+                ("CGST", TaxCategory::Packaging),
+                ("SGST", TaxCategory::Packaging),
+            ]
+        );
+        // 2.5% of ₹250 = ₹6.25
+        assert_eq!(lines[0].amount, Money::from_minor(625, Currency::Inr));
+        // 9% of ₹20 = ₹1.80
+        assert_eq!(lines[4].amount, Money::from_minor(180, Currency::Inr));
+    }
+
+    #[test]
+    fn inter_state_uses_single_igst_line() {
+        let lines = GstPolicy::inter_state().tax_lines(&items()).unwrap();
+        assert_eq!(lines.len(), 3);
+        assert!(lines.iter().all(|l| l.name == "IGST"));
+        assert_eq!(lines[0].amount, Money::from_minor(1250, Currency::Inr));
+    }
+
+    #[test]
+    fn configurable_rates_and_zero_rate_skipped() {
+        let policy = GstPolicy::inter_state()
+            .with_rate(TaxCategory::Beverage, 2800)
+            .with_rate(TaxCategory::Packaging, 0);
+        let lines = policy.tax_lines(&items()).unwrap();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1].rate_bps, 2800);
+        assert_eq!(lines[1].amount, Money::from_minor(1680, Currency::Inr));
+    }
+
+    #[test]
+    fn no_tax_policy_is_empty() {
+        assert!(NoTax.tax_lines(&items()).unwrap().is_empty());
+    }
+}
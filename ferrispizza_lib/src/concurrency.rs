@@ -1,5 +1,11 @@
+mod kitchen;
 mod order_channel;
+mod order_queue;
+mod priority_queue;
 mod shared_state;
 
-pub use order_channel::OrderChannel;
+pub use kitchen::{Kitchen, KitchenConfig, PrepTimes};
+pub use order_channel::{ChannelMetrics, OrderChannel, OrderSender, DEFAULT_CAPACITY};
+pub use order_queue::OrderQueue;
+pub use priority_queue::{PriorityOrderQueue, PriorityPolicy, QueuedOrder};
 pub use shared_state::SharedOrderState;
@@ -0,0 +1,340 @@
+//! Kitchen — worker threads that cook paid orders.
+//!
+//! A [`Kitchen`] spawns a fixed pool of workers that share one
+//! [`OrderQueue`] — a FIFO [`OrderChannel`](super::OrderChannel) or a
+//! [`PriorityOrderQueue`](super::PriorityOrderQueue). Each worker takes the next order, moves it to
+//! `Preparing`, cooks its pizzas one by one (recording progress in
+//! [`SharedOrderState`]) and finally marks it `Ready`. An order that is
+//! already `Preparing` (e.g. requeued after a restart) picks up after the
+//! pizzas recorded as ready.
+//!
+//! # Example
+//! ```
+//! use ferrispizza_lib::concurrency::{Kitchen, KitchenConfig, OrderChannel, SharedOrderState};
+//! use ferrispizza_lib::order::{order, OrderStatus};
+//! use ferrispizza_lib::pizza::Margherita;
+//!
+//! let state = SharedOrderState::new();
+//! let channel = OrderChannel::default();
+//! let kitchen = Kitchen::start(&channel, &state, KitchenConfig::instant(2));
+//!
+//! let mut order = order::new(vec![Box::new(Margherita::new())]);
+//! order.mark_paid().unwrap();
+//! let id = order.id.0;
+//! state.add_order(order.clone()).unwrap();
+//! channel.sender().send(order).unwrap();
+//!
+//! kitchen.shutdown(); // drains the queue before returning
+//! assert_eq!(state.get_order(id).unwrap().status, OrderStatus::Ready);
+//! ```
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::errors::OrderError;
+use crate::order::{order, OrderStatus};
+use crate::pizza::Pizza;
+use super::{OrderQueue, SharedOrderState};
+
+/// How long the kitchen takes per pizza.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PrepTimes {
+    /// Dough, sauce and oven time for any pizza.
+    pub per_pizza: Duration,
+    /// Extra time for each topping.
+    pub per_topping: Duration,
+}
+
+impl PrepTimes {
+    /// Time to cook `pizza`.
+    pub fn for_pizza(&self, pizza: &dyn Pizza) -> Duration {
+        let toppings = pizza.spec().toppings.len() as u32;
+        self.per_pizza + self.per_topping * toppings
+    }
+}
+
+impl Default for PrepTimes {
+    fn default() -> Self {
+        Self {
+            per_pizza: Duration::from_secs(2),
+            per_topping: Duration::from_millis(500),
+        }
+    }
+}
+
+/// Kitchen settings.
+#[derive(Clone, Copy, Debug)]
+pub struct KitchenConfig {
+    /// Number of worker threads (at least one is always started).
+    pub workers: usize,
+    pub prep: PrepTimes,
+    /// How often idle workers check for shutdown.
+    pub poll_interval: Duration,
+}
+
+impl KitchenConfig {
+    pub fn new(workers: usize) -> Self {
+        Self {
+            workers,
+            prep: PrepTimes::default(),
+            poll_interval: Duration::from_millis(50),
+        }
+    }
+
+    /// Workers that cook without delay (tests, demos).
+    pub fn instant(workers: usize) -> Self {
+        Self::new(workers).with_prep_times(PrepTimes {
+            per_pizza: Duration::ZERO,
+            per_topping: Duration::ZERO,
+        })
+    }
+
+    pub fn with_prep_times(mut self, prep: PrepTimes) -> Self {
+        self.prep = prep;
+        self
+    }
+}
+
+impl Default for KitchenConfig {
+    fn default() -> Self {
+        Self::new(2)
+    }
+}
+
+/// A running pool of kitchen workers.
+///
+/// Dropping the kitchen lets each worker finish the order it is cooking and
+/// leaves the rest queued; [`Kitchen::shutdown`] cooks them all first.
+pub struct Kitchen {
+    workers: Vec<JoinHandle<()>>,
+    drain: Arc<AtomicBool>,
+    stop: Arc<AtomicBool>,
+}
+
+impl Kitchen {
+    /// Spawn `config.workers` threads consuming `queue`.
+    pub fn start<Q>(queue: &Q, state: &SharedOrderState, config: KitchenConfig) -> Self
+    where
+        Q: OrderQueue + Clone + 'static,
+    {
+        let drain = Arc::new(AtomicBool::new(false));
+        let stop = Arc::new(AtomicBool::new(false));
+        let workers = (0..config.workers.max(1))
+            .map(|n| {
+                let queue = queue.clone();
+                let state = state.clone();
+                let (drain, stop) = (Arc::clone(&drain), Arc::clone(&stop));
+                thread::Builder::new()
+                    .name(format!("kitchen-{}", n))
+                    .spawn(move || worker_loop(&queue, &state, &config, &drain, &stop))
+                    .expect("failed to spawn kitchen worker")
+            })
+            .collect();
+
+        Self { workers, drain, stop }
+    }
+
+    /// Number of worker threads.
+    pub fn workers(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Cook every order still queued, then stop the workers.
+    pub fn shutdown(mut self) {
+        println!("Kitchen is finishing the queued orders...");
+        self.drain.store(true, Ordering::SeqCst);
+        self.join();
+    }
+
+    fn join(&mut self) {
+        for worker in self.workers.drain(..) {
+            if worker.join().is_err() {
+                eprintln!("Kitchen worker panicked");
+            }
+        }
+    }
+}
+
+impl Drop for Kitchen {
+    /// Queued orders stay `Paid` and are picked up again on the next start.
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        self.join();
+    }
+}
+
+fn worker_loop(
+    queue: &dyn OrderQueue,
+    state: &SharedOrderState,
+    config: &KitchenConfig,
+    drain: &AtomicBool,
+    stop: &AtomicBool,
+) {
+    while !stop.load(Ordering::SeqCst) {
+        match queue.next_timeout(config.poll_interval) {
+            // Taken just as the kitchen stopped; left uncooked
+            Ok(order) if stop.load(Ordering::SeqCst) => queue.finished(&order),
+            Ok(order) => {
+                if let Err(e) = cook(&order, state, &config.prep) {
+                    eprintln!("Kitchen skipped order {}: {}", order.id.0, e);
+                }
+                queue.finished(&order);
+            }
+            // Only stop once the queue is drained
+            Err(RecvTimeoutError::Timeout) if drain.load(Ordering::SeqCst) => break,
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+}
+
+/// Take one order from `Paid` (or a half-cooked `Preparing`) to `Ready`.
+fn cook(order: &order, state: &SharedOrderState, prep: &PrepTimes) -> Result<(), OrderError> {
+    let id = order.id.0;
+    let stored = state.get_order(id).ok_or(OrderError::NotFound(id))?;
+    let done = if stored.status == OrderStatus::Preparing {
+        stored.pizzas_ready
+    } else {
+        state.transition_status(id, OrderStatus::Preparing)?;
+        0
+    };
+
+    for pizza in order.pizzas.iter().skip(done) {
+        thread::sleep(prep.for_pizza(pizza.as_ref()));
+        state.update_order(id, |o| o.pizzas_ready += 1)?;
+    }
+
+    state.transition_status(id, OrderStatus::Ready)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::concurrency::OrderChannel;
+    use crate::pizza::{Cheese, Margherita, Olives};
+
+    fn paid_order(pizzas: usize) -> order {
+        let pizzas = (0..pizzas)
+            .map(|_| Box::new(Margherita::new()) as Box<dyn Pizza>)
+            .collect();
+        let mut order = order::new(pizzas);
+        order.mark_paid().unwrap();
+        order
+    }
+
+    #[test]
+    fn prep_time_grows_with_toppings() {
+        let prep = PrepTimes {
+            per_pizza: Duration::from_millis(100),
+            per_topping: Duration::from_millis(10),
+        };
+        let loaded = Olives::new(Box::new(Cheese::new(Box::new(Margherita::new()))));
+
+        assert_eq!(prep.for_pizza(&Margherita::new()), Duration::from_millis(100));
+        assert_eq!(prep.for_pizza(&loaded), Duration::from_millis(120));
+    }
+
+    #[test]
+    fn workers_cook_every_queued_order() {
+        let state = SharedOrderState::new();
+        let channel = OrderChannel::default();
+        let kitchen = Kitchen::start(&channel, &state, KitchenConfig::instant(3));
+        assert_eq!(kitchen.workers(), 3);
+
+        let mut ids = Vec::new();
+        for n in 1..=10 {
+            let order = paid_order(n % 3 + 1);
+            ids.push(order.id.0);
+            state.add_order(order.clone()).unwrap();
+            channel.sender().send(order).unwrap();
+        }
+        kitchen.shutdown();
+
+        for id in ids {
+            let order = state.get_order(id).unwrap();
+            assert_eq!(order.status, OrderStatus::Ready);
+            assert_eq!(order.pizzas_ready, order.pizzas.len());
+        }
+    }
+
+    #[test]
+    fn works_with_priority_queue() {
+        use crate::concurrency::PriorityOrderQueue;
+
+        let state = SharedOrderState::new();
+        let queue = PriorityOrderQueue::new().with_oven_capacity(2);
+        let kitchen = Kitchen::start(&queue, &state, KitchenConfig::instant(2));
+
+        let order = paid_order(3);
+        let id = order.id.0;
+        state.add_order(order.clone()).unwrap();
+        queue.submit(order).unwrap();
+        kitchen.shutdown();
+
+        assert_eq!(state.get_order(id).unwrap().status, OrderStatus::Ready);
+        assert_eq!(queue.in_oven(), 0);
+    }
+
+    #[test]
+    fn half_cooked_orders_resume() {
+        let state = SharedOrderState::new();
+        let channel = OrderChannel::default();
+
+        let order = paid_order(3);
+        let id = order.id.0;
+        state.add_order(order).unwrap();
+        state.transition_status(id, OrderStatus::Preparing).unwrap();
+        state.update_order(id, |o| o.pizzas_ready = 2).unwrap();
+
+        let kitchen = Kitchen::start(&channel, &state, KitchenConfig::instant(1));
+        channel.sender().send(state.get_order(id).unwrap()).unwrap();
+        kitchen.shutdown();
+
+        let stored = state.get_order(id).unwrap();
+        assert_eq!(stored.status, OrderStatus::Ready);
+        assert_eq!(stored.pizzas_ready, 3);
+    }
+
+    #[test]
+    fn dropping_leaves_queued_orders_for_later() {
+        let state = SharedOrderState::new();
+        let channel = OrderChannel::default();
+        let prep = PrepTimes { per_pizza: Duration::from_millis(100), per_topping: Duration::ZERO };
+        let kitchen = Kitchen::start(&channel, &state, KitchenConfig::new(1).with_prep_times(prep));
+
+        let ids: Vec<_> = (0..3)
+            .map(|_| {
+                let order = paid_order(1);
+                state.add_order(order.clone()).unwrap();
+                channel.sender().send(order.clone()).unwrap();
+                order.id.0
+            })
+            .collect();
+        thread::sleep(Duration::from_millis(20));
+        drop(kitchen);
+
+        // The order on the worker is finished; the others wait, still paid
+        let statuses: Vec<_> = ids.iter().map(|&id| state.get_order(id).unwrap().status).collect();
+        assert_eq!(statuses, [OrderStatus::Ready, OrderStatus::Paid, OrderStatus::Paid]);
+    }
+
+    #[test]
+    fn unpaid_orders_are_skipped() {
+        let state = SharedOrderState::new();
+        let channel = OrderChannel::default();
+        let kitchen = Kitchen::start(&channel, &state, KitchenConfig::instant(1));
+
+        let order = order::new(vec![Box::new(Margherita::new())]);
+        let id = order.id.0;
+        state.add_order(order.clone()).unwrap();
+        channel.sender().send(order).unwrap();
+        kitchen.shutdown();
+
+        let stored = state.get_order(id).unwrap();
+        assert_eq!(stored.status, OrderStatus::Created);
+        assert_eq!(stored.pizzas_ready, 0);
+    }
+}
@@ -1,23 +1,263 @@
-use std::sync::mpsc::{self, Sender, Receiver};
+//! Order channel — hand-off of orders from the front counter to the kitchen.
+//!
+//! A bounded channel ([`OrderChannel::new`]) applies backpressure: once
+//! `capacity` orders are waiting, [`OrderSender::try_send`] fails with
+//! [`OrderError::QueueError`] instead of letting the queue grow. Depth and
+//! throughput counters are exposed through [`OrderChannel::metrics`].
+//!
+//! # Example
+//! ```
+//! use ferrispizza_lib::concurrency::OrderChannel;
+//! use ferrispizza_lib::errors::OrderError;
+//! use ferrispizza_lib::order::order;
+//! use ferrispizza_lib::pizza::Margherita;
+//!
+//! let channel = OrderChannel::new(1);
+//! let sender = channel.sender();
+//!
+//! sender.try_send(order::new(vec![Box::new(Margherita::new())])).unwrap();
+//! let full = sender.try_send(order::new(vec![Box::new(Margherita::new())]));
+//! assert!(matches!(full, Err(OrderError::QueueError(_))));
+//! assert_eq!(channel.metrics().depth, 1);
+//! ```
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::errors::OrderError;
 use crate::order::order;
 
+/// Capacity used by [`OrderChannel::default`].
+pub const DEFAULT_CAPACITY: usize = 64;
+
+/// How often [`OrderSender::send_timeout`] retries a full queue.
+const RETRY_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Snapshot of channel activity.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ChannelMetrics {
+    /// Orders waiting to be received.
+    pub depth: usize,
+    /// `None` for an unbounded channel.
+    pub capacity: Option<usize>,
+    /// Highest depth seen so far.
+    pub peak_depth: usize,
+    pub sent: u64,
+    pub received: u64,
+    /// Sends refused because the queue was full.
+    pub rejected: u64,
+}
+
+#[derive(Default)]
+struct Counters {
+    peak_depth: AtomicUsize,
+    sent: AtomicU64,
+    received: AtomicU64,
+    rejected: AtomicU64,
+}
+
+impl Counters {
+    /// Count a send only once it has gone through, so senders blocked on a
+    /// full queue never show up in the depth.
+    fn on_sent(&self, capacity: Option<usize>) {
+        self.sent.fetch_add(1, Ordering::SeqCst);
+        self.peak_depth.fetch_max(self.depth(capacity), Ordering::SeqCst);
+    }
+
+    fn on_receive(&self) {
+        self.received.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// A receiver can take an order before its sender has counted it, so
+    /// the difference is clamped to what the queue can actually hold.
+    fn depth(&self, capacity: Option<usize>) -> usize {
+        let sent = self.sent.load(Ordering::SeqCst);
+        let depth = sent.saturating_sub(self.received.load(Ordering::SeqCst)) as usize;
+        depth.min(capacity.unwrap_or(usize::MAX))
+    }
+}
+
+#[derive(Clone)]
+enum Inner {
+    Bounded(SyncSender<order>),
+    Unbounded(Sender<order>),
+}
+
+/// Outcome of one non-blocking send.
+enum Attempt {
+    Sent,
+    /// Queue full; the order is handed back so it can be retried.
+    Full(Box<order>),
+    Closed,
+}
+
+/// Sending half of an [`OrderChannel`]. Cheap to clone.
+#[derive(Clone)]
+pub struct OrderSender {
+    inner: Inner,
+    capacity: Option<usize>,
+    counters: Arc<Counters>,
+}
+
+impl OrderSender {
+    /// Queue `order`, blocking while a bounded queue is full.
+    pub fn send(&self, order: order) -> Result<(), OrderError> {
+        match &self.inner {
+            Inner::Bounded(tx) => tx.send(order).map_err(|_| disconnected())?,
+            Inner::Unbounded(tx) => tx.send(order).map_err(|_| disconnected())?,
+        }
+        self.counters.on_sent(self.capacity);
+        Ok(())
+    }
+
+    /// Queue `order` only if there is room right now.
+    pub fn try_send(&self, order: order) -> Result<(), OrderError> {
+        match self.attempt(order) {
+            Attempt::Sent => Ok(()),
+            Attempt::Full(_) => Err(self.reject()),
+            Attempt::Closed => Err(disconnected()),
+        }
+    }
+
+    /// Queue `order`, waiting at most `timeout` for room.
+    pub fn send_timeout(&self, order: order, timeout: Duration) -> Result<(), OrderError> {
+        let deadline = Instant::now() + timeout;
+        let mut order = order;
+        loop {
+            match self.attempt(order) {
+                Attempt::Sent => return Ok(()),
+                Attempt::Closed => return Err(disconnected()),
+                Attempt::Full(_) if Instant::now() >= deadline => return Err(self.reject()),
+                Attempt::Full(back) => {
+                    // The failed attempt is retried, not rejected
+                    order = *back;
+                    thread::sleep(RETRY_INTERVAL.min(deadline - Instant::now()));
+                }
+            }
+        }
+    }
+
+    /// Orders currently waiting.
+    pub fn depth(&self) -> usize {
+        self.counters.depth(self.capacity)
+    }
+
+    fn attempt(&self, order: order) -> Attempt {
+        let tx = match &self.inner {
+            Inner::Bounded(tx) => tx,
+            Inner::Unbounded(_) => {
+                return match self.send(order) {
+                    Ok(()) => Attempt::Sent,
+                    Err(_) => Attempt::Closed,
+                };
+            }
+        };
+
+        match tx.try_send(order) {
+            Ok(()) => {
+                self.counters.on_sent(self.capacity);
+                Attempt::Sent
+            }
+            Err(TrySendError::Full(order)) => Attempt::Full(Box::new(order)),
+            Err(TrySendError::Disconnected(_)) => Attempt::Closed,
+        }
+    }
+
+    /// Count a send that gave up on a full queue.
+    fn reject(&self) -> OrderError {
+        self.counters.rejected.fetch_add(1, Ordering::SeqCst);
+        self.full()
+    }
+
+    fn full(&self) -> OrderError {
+        OrderError::QueueError(format!(
+            "kitchen queue full ({} of {} orders waiting)",
+            self.depth(),
+            self.capacity.unwrap_or_default()
+        ))
+    }
+}
+
+fn disconnected() -> OrderError {
+    OrderError::QueueError("kitchen is not accepting orders".to_string())
+}
+
+/// FIFO hand-off of orders from the front counter to the kitchen.
+///
+/// Clones share the same queue, so several consumers (e.g. kitchen
+/// workers) can take turns receiving from it.
+#[derive(Clone)]
 pub struct OrderChannel {
-    sender: Sender<order>,
-    receiver: Receiver<order>,
+    sender: OrderSender,
+    receiver: Arc<Mutex<Receiver<order>>>,
 }
 
 impl OrderChannel {
-    pub fn new() -> Self {
+    /// Channel holding at most `capacity` waiting orders (minimum 1).
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        let (tx, rx) = mpsc::sync_channel(capacity);
+        Self::from_parts(Inner::Bounded(tx), Some(capacity), rx)
+    }
+
+    /// Channel without a size limit. Prefer [`OrderChannel::new`].
+    pub fn unbounded() -> Self {
         let (tx, rx) = mpsc::channel();
-        Self { sender: tx, receiver: rx }
+        Self::from_parts(Inner::Unbounded(tx), None, rx)
     }
 
-    pub fn sender(&self) -> Sender<order> {
+    fn from_parts(inner: Inner, capacity: Option<usize>, rx: Receiver<order>) -> Self {
+        Self {
+            sender: OrderSender {
+                inner,
+                capacity,
+                counters: Arc::new(Counters::default()),
+            },
+            receiver: Arc::new(Mutex::new(rx)),
+        }
+    }
+
+    pub fn sender(&self) -> OrderSender {
         self.sender.clone()
     }
 
     pub fn receive(&self) -> Option<order> {
-        self.receiver.recv().ok()
+        let order = self.receiver.lock().unwrap().recv().ok()?;
+        self.sender.counters.on_receive();
+        Some(order)
+    }
+
+    /// Wait at most `timeout` for the next order.
+    pub fn receive_timeout(&self, timeout: Duration) -> Result<order, RecvTimeoutError> {
+        let order = self.receiver.lock().unwrap().recv_timeout(timeout)?;
+        self.sender.counters.on_receive();
+        Ok(order)
+    }
+
+    pub fn capacity(&self) -> Option<usize> {
+        self.sender.capacity
+    }
+
+    pub fn metrics(&self) -> ChannelMetrics {
+        let c = &self.sender.counters;
+        ChannelMetrics {
+            depth: c.depth(self.sender.capacity),
+            capacity: self.sender.capacity,
+            peak_depth: c.peak_depth.load(Ordering::SeqCst),
+            sent: c.sent.load(Ordering::SeqCst),
+            received: c.received.load(Ordering::SeqCst),
+            rejected: c.rejected.load(Ordering::SeqCst),
+        }
+    }
+}
+
+impl Default for OrderChannel {
+    /// Bounded channel with [`DEFAULT_CAPACITY`].
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
     }
 }
 
@@ -27,15 +267,109 @@ mod tests {
     use crate::order::order;
     use crate::pizza::Margherita;
 
+    fn sample() -> order {
+        order::new(vec![Box::new(Margherita::new())])
+    }
+
     #[test]
     fn order_channel_sends_and_receives() {
-        let channel = OrderChannel::new();
+        let channel = OrderChannel::new(10);
         let sender = channel.sender();
 
-        let order = order::new(vec![Box::new(Margherita::new())]);
+        let order = sample();
         sender.send(order.clone()).unwrap();
 
         let received = channel.receive().unwrap();
         assert_eq!(received.id, order.id);
     }
+
+    #[test]
+    fn clones_share_one_queue() {
+        let channel = OrderChannel::unbounded();
+        let consumer = channel.clone();
+
+        channel.sender().send(sample()).unwrap();
+
+        assert!(consumer.receive_timeout(Duration::from_millis(100)).is_ok());
+        assert!(matches!(
+            channel.receive_timeout(Duration::from_millis(10)),
+            Err(RecvTimeoutError::Timeout)
+        ));
+    }
+
+    #[test]
+    fn full_queue_rejects_and_counts() {
+        let channel = OrderChannel::new(2);
+        let sender = channel.sender();
+
+        sender.try_send(sample()).unwrap();
+        sender.try_send(sample()).unwrap();
+        assert!(matches!(sender.try_send(sample()), Err(OrderError::QueueError(_))));
+
+        channel.receive().unwrap();
+        sender.try_send(sample()).unwrap();
+
+        let metrics = channel.metrics();
+        assert_eq!(metrics.depth, 2);
+        assert_eq!(metrics.peak_depth, 2);
+        assert_eq!(metrics.capacity, Some(2));
+        assert_eq!((metrics.sent, metrics.received, metrics.rejected), (3, 1, 1));
+    }
+
+    #[test]
+    fn send_timeout_waits_for_room() {
+        let channel = OrderChannel::new(1);
+        let sender = channel.sender();
+        sender.send(sample()).unwrap();
+
+        // Nobody drains the queue
+        let start = Instant::now();
+        let result = sender.send_timeout(sample(), Duration::from_millis(30));
+        assert!(matches!(result, Err(OrderError::QueueError(_))));
+        assert!(start.elapsed() >= Duration::from_millis(30));
+        assert_eq!(channel.metrics().rejected, 1);
+
+        // A consumer frees a slot in time
+        let consumer = channel.clone();
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            consumer.receive().unwrap();
+        });
+        sender.send_timeout(sample(), Duration::from_secs(2)).unwrap();
+        handle.join().unwrap();
+        assert_eq!(channel.metrics().depth, 1);
+    }
+
+    #[test]
+    fn blocked_senders_are_not_counted_as_waiting() {
+        let channel = OrderChannel::new(1);
+        let sender = channel.sender();
+        sender.send(sample()).unwrap();
+
+        let blocked: Vec<_> = (0..3)
+            .map(|_| {
+                let sender = channel.sender();
+                thread::spawn(move || sender.send(sample()).unwrap())
+            })
+            .collect();
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(channel.metrics().depth, 1);
+
+        for _ in 0..3 {
+            channel.receive().unwrap();
+        }
+        blocked.into_iter().for_each(|h| h.join().unwrap());
+        let metrics = channel.metrics();
+        assert_eq!((metrics.depth, metrics.peak_depth), (1, 1));
+    }
+
+    #[test]
+    fn unbounded_never_rejects() {
+        let channel = OrderChannel::unbounded();
+        for _ in 0..100 {
+            channel.sender().try_send(sample()).unwrap();
+        }
+        assert_eq!(channel.metrics().depth, 100);
+        assert_eq!(channel.capacity(), None);
+    }
 }
@@ -0,0 +1,55 @@
+//! Common interface for queues that feed the kitchen.
+
+use std::sync::mpsc::RecvTimeoutError;
+use std::time::Duration;
+
+use crate::errors::OrderError;
+use crate::order::order;
+use super::OrderChannel;
+
+/// A queue of paid orders waiting for the kitchen.
+///
+/// Implemented by the FIFO [`OrderChannel`] and the
+/// [`PriorityOrderQueue`](super::PriorityOrderQueue); the
+/// [`Kitchen`](super::Kitchen) works with either.
+pub trait OrderQueue: Send + Sync {
+    /// Queue `order` if there is room right now.
+    fn submit(&self, order: order) -> Result<(), OrderError>;
+
+    /// Queue `order`, waiting at most `timeout` for room.
+    fn submit_timeout(&self, order: order, timeout: Duration) -> Result<(), OrderError>;
+
+    /// Take the next order to cook, waiting at most `timeout`.
+    fn next_timeout(&self, timeout: Duration) -> Result<order, RecvTimeoutError>;
+
+    /// Tell the queue the kitchen is done with `order` (frees oven space).
+    fn finished(&self, _order: &order) {}
+
+    /// Orders currently waiting.
+    fn depth(&self) -> usize;
+
+    /// Maximum waiting orders, `None` if unbounded.
+    fn capacity(&self) -> Option<usize>;
+}
+
+impl OrderQueue for OrderChannel {
+    fn submit(&self, order: order) -> Result<(), OrderError> {
+        self.sender().try_send(order)
+    }
+
+    fn submit_timeout(&self, order: order, timeout: Duration) -> Result<(), OrderError> {
+        self.sender().send_timeout(order, timeout)
+    }
+
+    fn next_timeout(&self, timeout: Duration) -> Result<order, RecvTimeoutError> {
+        self.receive_timeout(timeout)
+    }
+
+    fn depth(&self) -> usize {
+        self.metrics().depth
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        OrderChannel::capacity(self)
+    }
+}
@@ -0,0 +1,403 @@
+//! Priority scheduling for kitchen orders.
+//!
+//! [`PriorityOrderQueue`] hands out the waiting order with the highest
+//! score, recomputed every time the kitchen asks for work:
+//!
+//! ```text
+//! score = class boost (Express / VIP)
+//!       + deadline boost (delivery due within the deadline window)
+//!       + 1 point per aging step spent waiting
+//! ```
+//!
+//! Aging means a normal order eventually outranks any newly arrived
+//! express or VIP order, so nothing starves. Ties go to the order that
+//! arrived first.
+//!
+//! With an oven capacity set, an order is only handed out once all of its
+//! pizzas fit alongside those already cooking. The top-ranked order waits
+//! for room rather than letting smaller, lower-ranked orders overtake it.
+//!
+//! # Example
+//! ```
+//! use std::time::Duration;
+//! use ferrispizza_lib::concurrency::{OrderQueue, PriorityOrderQueue};
+//! use ferrispizza_lib::order::{order, OrderPriority};
+//! use ferrispizza_lib::pizza::Margherita;
+//!
+//! let queue = PriorityOrderQueue::new();
+//! let normal = order::new(vec![Box::new(Margherita::new())]);
+//! let vip = order::new(vec![Box::new(Margherita::new())]).with_priority(OrderPriority::Vip);
+//! let vip_id = vip.id.0;
+//!
+//! queue.submit(normal).unwrap();
+//! queue.submit(vip).unwrap();
+//!
+//! assert_eq!(queue.snapshot()[0].order_id, vip_id);
+//! assert_eq!(queue.next_timeout(Duration::ZERO).unwrap().id.0, vip_id);
+//! ```
+
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::errors::OrderError;
+use crate::order::{order, OrderPriority};
+use super::OrderQueue;
+
+/// Weights used to rank waiting orders.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PriorityPolicy {
+    pub express_boost: u64,
+    pub vip_boost: u64,
+    /// Added once a delivery deadline is this close (or already missed).
+    pub deadline_window: Duration,
+    pub deadline_boost: u64,
+    /// Waiting this long is worth one point.
+    pub aging_step: Duration,
+}
+
+impl PriorityPolicy {
+    /// Score of `entry` at `now`.
+    fn score(&self, entry: &Entry, now: Instant, wall: SystemTime) -> u64 {
+        let class = match entry.order.priority {
+            OrderPriority::Normal => 0,
+            OrderPriority::Express => self.express_boost,
+            OrderPriority::Vip => self.vip_boost,
+        };
+
+        let deadline = match entry.order.deliver_by {
+            Some(due) if due <= wall + self.deadline_window => self.deadline_boost,
+            _ => 0,
+        };
+
+        let step = self.aging_step.as_millis().max(1);
+        let aging = (now.duration_since(entry.queued_at).as_millis() / step) as u64;
+
+        class + deadline + aging
+    }
+}
+
+impl Default for PriorityPolicy {
+    /// A normal order overtakes a fresh express order after 5 minutes and a
+    /// fresh VIP order after 10.
+    fn default() -> Self {
+        Self {
+            express_boost: 10,
+            vip_boost: 20,
+            deadline_window: Duration::from_secs(15 * 60),
+            deadline_boost: 15,
+            aging_step: Duration::from_secs(30),
+        }
+    }
+}
+
+/// One line of the kitchen display.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QueuedOrder {
+    pub order_id: u64,
+    pub priority: OrderPriority,
+    pub pizzas: usize,
+    pub waited: Duration,
+    pub score: u64,
+}
+
+struct Entry {
+    order: order,
+    queued_at: Instant,
+    /// Arrival sequence, for FIFO tie-breaks.
+    seq: u64,
+}
+
+#[derive(Default)]
+struct State {
+    waiting: Vec<Entry>,
+    next_seq: u64,
+    in_oven: usize,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    /// Signalled when an order arrives or leaves the oven.
+    changed: Condvar,
+    policy: PriorityPolicy,
+    capacity: Option<usize>,
+    oven_capacity: Option<usize>,
+}
+
+/// Priority-aware order queue with aging and an optional oven limit.
+///
+/// Clones share the same queue.
+#[derive(Clone)]
+pub struct PriorityOrderQueue {
+    shared: Arc<Shared>,
+}
+
+impl PriorityOrderQueue {
+    /// Unbounded queue with the default policy and no oven limit.
+    pub fn new() -> Self {
+        Self::with_policy(PriorityPolicy::default())
+    }
+
+    pub fn with_policy(policy: PriorityPolicy) -> Self {
+        Self::build(policy, None, None)
+    }
+
+    /// Refuse new orders once `capacity` are waiting.
+    pub fn with_capacity(self, capacity: usize) -> Self {
+        let shared = &self.shared;
+        Self::build(shared.policy, Some(capacity.max(1)), shared.oven_capacity)
+    }
+
+    /// Cook at most `pizzas` pizzas at a time.
+    pub fn with_oven_capacity(self, pizzas: usize) -> Self {
+        let shared = &self.shared;
+        Self::build(shared.policy, shared.capacity, Some(pizzas.max(1)))
+    }
+
+    fn build(policy: PriorityPolicy, capacity: Option<usize>, oven_capacity: Option<usize>) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                state: Mutex::new(State::default()),
+                changed: Condvar::new(),
+                policy,
+                capacity,
+                oven_capacity,
+            }),
+        }
+    }
+
+    /// Waiting orders in the order they would be cooked right now.
+    pub fn snapshot(&self) -> Vec<QueuedOrder> {
+        let state = self.shared.state.lock().unwrap();
+        let (now, wall) = (Instant::now(), SystemTime::now());
+
+        let mut rows: Vec<(QueuedOrder, u64)> = state
+            .waiting
+            .iter()
+            .map(|e| {
+                let row = QueuedOrder {
+                    order_id: e.order.id.0,
+                    priority: e.order.priority,
+                    pizzas: e.order.pizzas.len(),
+                    waited: now.duration_since(e.queued_at),
+                    score: self.shared.policy.score(e, now, wall),
+                };
+                (row, e.seq)
+            })
+            .collect();
+        rows.sort_by(|(a, a_seq), (b, b_seq)| b.score.cmp(&a.score).then(a_seq.cmp(b_seq)));
+        rows.into_iter().map(|(row, _)| row).collect()
+    }
+
+    /// Pizzas currently in the oven.
+    pub fn in_oven(&self) -> usize {
+        self.shared.state.lock().unwrap().in_oven
+    }
+
+    pub fn oven_capacity(&self) -> Option<usize> {
+        self.shared.oven_capacity
+    }
+
+    fn has_room(&self, state: &State) -> bool {
+        self.shared.capacity.is_none_or(|cap| state.waiting.len() < cap)
+    }
+
+    fn push(&self, state: &mut State, order: order) {
+        let seq = state.next_seq;
+        state.next_seq += 1;
+        state.waiting.push(Entry {
+            order,
+            queued_at: Instant::now(),
+            seq,
+        });
+        self.shared.changed.notify_all();
+    }
+
+    fn full(&self, state: &State) -> OrderError {
+        OrderError::QueueError(format!(
+            "kitchen queue full ({} of {} orders waiting)",
+            state.waiting.len(),
+            self.shared.capacity.unwrap_or_default()
+        ))
+    }
+
+    /// Index of the top-ranked order, if the oven can take it now.
+    fn pick(&self, state: &State) -> Option<usize> {
+        let (now, wall) = (Instant::now(), SystemTime::now());
+        let policy = &self.shared.policy;
+        let (index, top) = state.waiting.iter().enumerate().max_by(|(_, a), (_, b)| {
+            policy
+                .score(a, now, wall)
+                .cmp(&policy.score(b, now, wall))
+                .then(b.seq.cmp(&a.seq))
+        })?;
+
+        let pizzas = top.order.pizzas.len();
+        let fits = match self.shared.oven_capacity {
+            // An order larger than the oven goes in alone
+            Some(cap) => state.in_oven == 0 || state.in_oven + pizzas <= cap,
+            None => true,
+        };
+        fits.then_some(index)
+    }
+}
+
+impl Default for PriorityOrderQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OrderQueue for PriorityOrderQueue {
+    fn submit(&self, order: order) -> Result<(), OrderError> {
+        let mut state = self.shared.state.lock().unwrap();
+        if !self.has_room(&state) {
+            return Err(self.full(&state));
+        }
+        self.push(&mut state, order);
+        Ok(())
+    }
+
+    fn submit_timeout(&self, order: order, timeout: Duration) -> Result<(), OrderError> {
+        let state = self.shared.state.lock().unwrap();
+        let (mut state, _) = self
+            .shared
+            .changed
+            .wait_timeout_while(state, timeout, |s| !self.has_room(s))
+            .unwrap();
+        if !self.has_room(&state) {
+            return Err(self.full(&state));
+        }
+        self.push(&mut state, order);
+        Ok(())
+    }
+
+    fn next_timeout(&self, timeout: Duration) -> Result<order, RecvTimeoutError> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.shared.state.lock().unwrap();
+        loop {
+            if let Some(index) = self.pick(&state) {
+                let entry = state.waiting.remove(index);
+                state.in_oven += entry.order.pizzas.len();
+                // A slot opened up for waiting submitters
+                self.shared.changed.notify_all();
+                return Ok(entry.order);
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(RecvTimeoutError::Timeout);
+            }
+            // Scores change with time, so re-rank at least every second
+            let wait = (deadline - now).min(Duration::from_secs(1));
+            state = self.shared.changed.wait_timeout(state, wait).unwrap().0;
+        }
+    }
+
+    fn finished(&self, order: &order) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.in_oven = state.in_oven.saturating_sub(order.pizzas.len());
+        self.shared.changed.notify_all();
+    }
+
+    fn depth(&self) -> usize {
+        self.shared.state.lock().unwrap().waiting.len()
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        self.shared.capacity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pizza::{Margherita, Pizza};
+
+    fn order_with(pizzas: usize, priority: OrderPriority) -> order {
+        let pizzas = (0..pizzas)
+            .map(|_| Box::new(Margherita::new()) as Box<dyn Pizza>)
+            .collect();
+        order::new(pizzas).with_priority(priority)
+    }
+
+    fn next_id(queue: &PriorityOrderQueue) -> u64 {
+        queue.next_timeout(Duration::ZERO).unwrap().id.0
+    }
+
+    #[test]
+    fn higher_classes_jump_ahead_fifo_within_class() {
+        let queue = PriorityOrderQueue::new();
+        let a = order_with(1, OrderPriority::Normal);
+        let b = order_with(1, OrderPriority::Express);
+        let c = order_with(1, OrderPriority::Vip);
+        let d = order_with(1, OrderPriority::Express);
+        let ids: Vec<u64> = [&a, &b, &c, &d].iter().map(|o| o.id.0).collect();
+        for o in [a, b, c, d] {
+            queue.submit(o).unwrap();
+        }
+
+        let display: Vec<u64> = queue.snapshot().iter().map(|r| r.order_id).collect();
+        assert_eq!(display, vec![ids[2], ids[1], ids[3], ids[0]]);
+        let cooked: Vec<u64> = (0..4).map(|_| next_id(&queue)).collect();
+        assert_eq!(cooked, display);
+    }
+
+    #[test]
+    fn near_deadline_beats_express() {
+        let queue = PriorityOrderQueue::new();
+        let express = order_with(1, OrderPriority::Express);
+        let delivery = order_with(1, OrderPriority::Normal)
+            .with_deadline(SystemTime::now() + Duration::from_secs(600));
+        let delivery_id = delivery.id.0;
+        queue.submit(express).unwrap();
+        queue.submit(delivery).unwrap();
+
+        assert_eq!(next_id(&queue), delivery_id);
+    }
+
+    #[test]
+    fn aging_prevents_starvation() {
+        let policy = PriorityPolicy {
+            aging_step: Duration::from_millis(5),
+            ..PriorityPolicy::default()
+        };
+        let queue = PriorityOrderQueue::with_policy(policy);
+        let old = order_with(1, OrderPriority::Normal);
+        let old_id = old.id.0;
+        queue.submit(old).unwrap();
+
+        // 25 aging steps outrank a fresh VIP (boost 20)
+        std::thread::sleep(Duration::from_millis(125));
+        queue.submit(order_with(1, OrderPriority::Vip)).unwrap();
+
+        assert_eq!(next_id(&queue), old_id);
+    }
+
+    #[test]
+    fn oven_capacity_holds_orders_until_room() {
+        let queue = PriorityOrderQueue::new().with_oven_capacity(3);
+        let big = order_with(2, OrderPriority::Vip);
+        let next = order_with(2, OrderPriority::Normal);
+        queue.submit(big).unwrap();
+        queue.submit(next).unwrap();
+
+        let cooking = queue.next_timeout(Duration::ZERO).unwrap();
+        assert_eq!(queue.in_oven(), 2);
+        assert!(queue.next_timeout(Duration::from_millis(10)).is_err());
+
+        queue.finished(&cooking);
+        assert!(queue.next_timeout(Duration::ZERO).is_ok());
+        assert_eq!(queue.in_oven(), 2);
+    }
+
+    #[test]
+    fn bounded_queue_rejects_when_full() {
+        let queue = PriorityOrderQueue::new().with_capacity(1);
+        queue.submit(order_with(1, OrderPriority::Normal)).unwrap();
+
+        let result = queue.submit_timeout(order_with(1, OrderPriority::Vip), Duration::from_millis(10));
+        assert!(matches!(result, Err(OrderError::QueueError(_))));
+        assert_eq!(queue.depth(), 1);
+    }
+}
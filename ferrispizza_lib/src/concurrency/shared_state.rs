@@ -1,28 +1,300 @@
-use std::sync::{Arc, Mutex};
-use std::collections::HashMap;
-use super::super::order::order;
+use std::sync::Arc;
+use std::time::SystemTime;
+use super::super::order::{order, OrderStatus};
+use crate::billing::{LoyaltyLedger, RefundReceipt};
+use crate::customer::CustomerId;
+use crate::errors::{BillingError, OrderError};
+use crate::inventory::Inventory;
+use crate::storage::{InMemoryOrderRepository, OrderRepository};
 
+/// Thread-safe handle to the order store, shared by the CLI, payments and
+/// the kitchen. Clones share the same store.
+///
+/// With an [`Inventory`] attached, placing an order reserves its
+/// ingredients, moving it to `Preparing` uses them up and cancelling it
+/// hands them back. With a [`LoyaltyLedger`] attached, refunding an order
+/// reverses the points it spent and earned.
 #[derive(Clone)]
 pub struct SharedOrderState {
-    pub orders: Arc<Mutex<HashMap<u64, order>>>,
+    repository: Arc<dyn OrderRepository>,
+    inventory: Option<Inventory>,
+    loyalty: Option<LoyaltyLedger>,
 }
 
 impl SharedOrderState {
+    /// State backed by an [`InMemoryOrderRepository`].
     pub fn new() -> Self {
+        Self::with_repository(InMemoryOrderRepository::new())
+    }
+
+    /// State backed by `repository`, e.g. a
+    /// [`FileOrderRepository`](crate::storage::FileOrderRepository).
+    pub fn with_repository(repository: impl OrderRepository + 'static) -> Self {
         Self {
-            orders: Arc::new(Mutex::new(HashMap::new())),
+            repository: Arc::new(repository),
+            inventory: None,
+            loyalty: None,
+        }
+    }
+
+    /// Track ingredient stock for every order.
+    ///
+    /// Orders already waiting for the kitchen reserve their ingredients
+    /// again. They were accepted earlier, so one that no longer fits in the
+    /// stock is kept and simply cooked without a reservation.
+    pub fn with_inventory(mut self, inventory: Inventory) -> Self {
+        for order in self.list_orders() {
+            if matches!(order.status, OrderStatus::Created | OrderStatus::Paid)
+                && let Err(e) = inventory.reserve(&order)
+            {
+                eprintln!("Order {} holds no stock: {}", order.id.0, e);
+            }
         }
+        self.inventory = Some(inventory);
+        self
+    }
+
+    pub fn inventory(&self) -> Option<&Inventory> {
+        self.inventory.as_ref()
     }
 
-    pub fn add_order(&self, order: order) {
-        self.orders.lock().unwrap().insert(order.id.0, order);
+    /// Reverse loyalty points in `ledger` when an order is refunded.
+    pub fn with_loyalty(mut self, ledger: LoyaltyLedger) -> Self {
+        self.loyalty = Some(ledger);
+        self
+    }
+
+    /// Store a new order, first reserving its ingredients if an inventory
+    /// is attached. Fails with [`OrderError::OutOfStock`] if they are short.
+    pub fn add_order(&self, order: order) -> Result<(), OrderError> {
+        let Some(inventory) = &self.inventory else {
+            return self.repository.insert(order);
+        };
+        let id = order.id.0;
+        inventory.reserve(&order)?;
+        self.repository.insert(order).inspect_err(|_| inventory.release(id))
     }
 
     pub fn get_order(&self, id: u64) -> Option<order> {
-        self.orders.lock().unwrap().get(&id).cloned()
+        self.repository.get(id)
     }
 
     pub fn list_orders(&self) -> Vec<order> {
-        self.orders.lock().unwrap().values().cloned().collect()
+        self.repository.list()
+    }
+
+    /// Orders placed by `customer`, oldest first.
+    pub fn orders_for(&self, customer: CustomerId) -> Vec<order> {
+        let mut orders: Vec<order> = self
+            .list_orders()
+            .into_iter()
+            .filter(|o| o.customer == Some(customer))
+            .collect();
+        orders.sort_by_key(|o| o.id.0);
+        orders
+    }
+
+    /// Mutate the stored order in place while holding the lock.
+    ///
+    /// The closure sees the live order, so changes are visible to every
+    /// clone of this state once it returns.
+    pub fn update_order<F, R>(&self, id: u64, f: F) -> Result<R, OrderError>
+    where
+        F: FnOnce(&mut order) -> R,
+    {
+        let mut f = Some(f);
+        let mut result = None;
+        self.repository.modify(id, &mut |order| {
+            if let Some(f) = f.take() {
+                result = Some(f(order));
+            }
+        })?;
+        Ok(result.expect("repository ran the update"))
+    }
+
+    /// Move the stored order to `status`, enforcing the order lifecycle.
+    pub fn transition_status(&self, id: u64, status: OrderStatus) -> Result<(), OrderError> {
+        self.update_order(id, |order| order.transition(status))??;
+        self.after_transition(id, status)
+    }
+
+    /// Record a refund on the stored order, moving it to `Refunded` or
+    /// `PartiallyRefunded`. Returns the new status.
+    ///
+    /// The refund is checked against the stored payment, so refunds quoted
+    /// at the same time cannot together return more than was paid. A
+    /// partial refund takes back its share of the points the order earned.
+    pub fn record_refund(&self, id: u64, refund: RefundReceipt) -> Result<OrderStatus, BillingError> {
+        let amount = refund.amount;
+        let status = self.update_order(id, |order| order.record_refund(refund))??;
+        self.after_transition(id, status)?;
+        if status == OrderStatus::PartiallyRefunded
+            && let Some(ledger) = &self.loyalty
+        {
+            ledger.reverse_part(id, amount, SystemTime::now()).map_err(OrderError::from)?;
+        }
+        Ok(status)
+    }
+
+    /// Keep stock and loyalty points in step with an order that just moved
+    /// to `status`.
+    fn after_transition(&self, id: u64, status: OrderStatus) -> Result<(), OrderError> {
+        if let Some(inventory) = &self.inventory {
+            match status {
+                OrderStatus::Preparing => inventory.commit(id),
+                OrderStatus::Cancelled | OrderStatus::Refunded => inventory.release(id),
+                _ => {}
+            }
+        }
+        if status == OrderStatus::Refunded
+            && let Some(ledger) = &self.loyalty
+        {
+            ledger.reverse(id, SystemTime::now())?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for SharedOrderState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::billing::BillingEngine;
+    use crate::money::Money;
+    use crate::pizza::Margherita;
+
+    fn sample_order() -> order {
+        order::new(vec![Box::new(Margherita::new())])
+    }
+
+    #[test]
+    fn orders_are_not_overwritten() {
+        let state = SharedOrderState::new();
+        state.add_order(sample_order()).unwrap();
+        state.add_order(sample_order()).unwrap();
+
+        assert_eq!(state.list_orders().len(), 2);
+    }
+
+    #[test]
+    fn update_order_persists_changes() {
+        let state = SharedOrderState::new();
+        let order = sample_order();
+        let id = order.id.0;
+        state.add_order(order.clone()).unwrap();
+
+        let receipt = BillingEngine::new().generate_receipt(&order).unwrap();
+        state.update_order(id, |o| o.record_payment(receipt.clone())).unwrap().unwrap();
+
+        let stored = state.get_order(id).unwrap();
+        assert_eq!(stored.status, OrderStatus::Paid);
+        assert_eq!(stored.receipt, Some(receipt));
+    }
+
+    #[test]
+    fn transition_status_is_visible_to_clones() {
+        let state = SharedOrderState::new();
+        let order = sample_order();
+        let id = order.id.0;
+        state.add_order(order).unwrap();
+
+        let other = state.clone();
+        other.transition_status(id, OrderStatus::Cancelled).unwrap();
+
+        assert_eq!(state.get_order(id).unwrap().status, OrderStatus::Cancelled);
+    }
+
+    #[test]
+    fn refunds_quoted_together_cannot_exceed_the_payment() {
+        let state = SharedOrderState::new();
+        let order = sample_order();
+        let id = order.id.0;
+        state.add_order(order.clone()).unwrap();
+        let engine = BillingEngine::new();
+        let receipt = engine.generate_receipt(&order).unwrap();
+        state.update_order(id, |o| o.record_payment(receipt.clone())).unwrap().unwrap();
+        for status in [OrderStatus::Preparing, OrderStatus::Ready, OrderStatus::Completed] {
+            state.transition_status(id, status).unwrap();
+        }
+
+        // Both quoted from the same copy of the ₹126 payment
+        let first = engine.quote_refund(&receipt, Money::inr(100)).unwrap();
+        let second = engine.quote_refund(&receipt, Money::inr(100)).unwrap();
+        assert_eq!(state.record_refund(id, first).unwrap(), OrderStatus::PartiallyRefunded);
+        assert!(matches!(state.record_refund(id, second), Err(BillingError::OverRefund { .. })));
+        assert_eq!(state.get_order(id).unwrap().receipt.unwrap().refundable().unwrap(), Money::inr(26));
+    }
+
+    #[test]
+    fn inventory_follows_the_order_lifecycle() {
+        use crate::inventory::{Inventory, Recipe, RecipeBook};
+
+        let recipes = RecipeBook::new().with_recipe("Margherita", Recipe::from([("dough".into(), 250)]));
+        let state = SharedOrderState::new().with_inventory(Inventory::new(recipes).with_stock("dough", 500));
+        let dough = || state.inventory().unwrap().level("dough").unwrap();
+
+        let (cooked, cancelled) = (sample_order(), sample_order());
+        let (cooked_id, cancelled_id) = (cooked.id.0, cancelled.id.0);
+        state.add_order(cooked).unwrap();
+        state.add_order(cancelled).unwrap();
+        assert!(matches!(state.add_order(sample_order()), Err(OrderError::OutOfStock(_))));
+        assert_eq!(state.list_orders().len(), 2);
+
+        state.transition_status(cancelled_id, OrderStatus::Cancelled).unwrap();
+        assert_eq!(dough().available(), 250);
+
+        state.update_order(cooked_id, |o| o.mark_paid()).unwrap().unwrap();
+        state.transition_status(cooked_id, OrderStatus::Preparing).unwrap();
+        assert_eq!((dough().on_hand, dough().reserved), (250, 0));
+    }
+
+    #[test]
+    fn refunds_reverse_loyalty_points() {
+        use crate::billing::{LoyaltyLedger, LoyaltyProgram};
+        use crate::customer::Customer;
+        use crate::money::Money;
+
+        let ledger = LoyaltyLedger::new(LoyaltyProgram::default());
+        let engine = BillingEngine::new().with_loyalty(ledger.clone());
+        let state = SharedOrderState::new().with_loyalty(ledger.clone());
+        let asha = Customer::new(CustomerId(1), "Asha", "9876543210").unwrap();
+        ledger.earn(asha.id, 900, Money::inr(500), SystemTime::now()).unwrap();
+
+        let mut order = order::new(vec![Box::new(Margherita::new()); 2]).for_customer(&asha).unwrap();
+        order.pay_with_points(5);
+        let id = order.id.0;
+        state.add_order(order.clone()).unwrap();
+        let receipt = engine.generate_receipt(&order).unwrap();
+        engine.settle(&receipt).unwrap();
+        state.update_order(id, |o| o.record_payment(receipt)).unwrap().unwrap();
+        // 5 spent, 2 earned on the ₹247 still due
+        assert_eq!(ledger.balance(asha.id, SystemTime::now()), 2);
+
+        state.transition_status(id, OrderStatus::Refunded).unwrap();
+        assert_eq!(ledger.balance(asha.id, SystemTime::now()), 5);
+    }
+
+    #[test]
+    fn update_missing_order_fails() {
+        let state = SharedOrderState::new();
+        let result = state.transition_status(9999, OrderStatus::Paid);
+        assert!(matches!(result, Err(OrderError::NotFound(9999))));
+    }
+
+    #[test]
+    fn transition_status_rejects_illegal_moves() {
+        let state = SharedOrderState::new();
+        let order = sample_order();
+        let id = order.id.0;
+        state.add_order(order).unwrap();
+
+        let result = state.transition_status(id, OrderStatus::Completed);
+        assert!(matches!(result, Err(OrderError::InvalidTransition { .. })));
+        assert_eq!(state.get_order(id).unwrap().status, OrderStatus::Created);
     }
 }
@@ -0,0 +1,10 @@
+//! Customer module — who an order is for.
+//!
+//! A [`Customer`] is validated on creation (a name and a phone number are
+//! required) and kept in a [`CustomerRegistry`]. Orders link to customers
+//! by [`CustomerId`] through [`order::for_customer`](crate::order::order::for_customer).
+mod model;
+mod registry;
+
+pub use model::{Address, Customer, CustomerId, PaymentMethod};
+pub use registry::{CustomerRegistry, CustomerStore};
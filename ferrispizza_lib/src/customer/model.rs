@@ -0,0 +1,123 @@
+//! Customer records.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::errors::OrderError;
+
+/// Unique customer id.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CustomerId(pub u64);
+
+/// Way a customer likes to pay.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PaymentMethod {
+    Upi,
+    Card,
+}
+
+impl fmt::Display for PaymentMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PaymentMethod::Upi => "UPI",
+            PaymentMethod::Card => "card",
+        })
+    }
+}
+
+/// A delivery address, e.g. labelled "home" or "work".
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Address {
+    pub label: String,
+    pub text: String,
+}
+
+impl Address {
+    pub fn new(label: impl Into<String>, text: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            text: text.into(),
+        }
+    }
+}
+
+/// A registered customer.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Customer {
+    pub id: CustomerId,
+    pub name: String,
+    /// Normalised: digits only, with a leading `+` if one was given.
+    pub phone: String,
+    #[serde(default)]
+    pub addresses: Vec<Address>,
+    #[serde(default)]
+    pub preferred_payment: Option<PaymentMethod>,
+}
+
+impl Customer {
+    /// Validate and normalise `name` and `phone`.
+    ///
+    /// Fails with [`OrderError::NoCustomerName`] for a blank name and
+    /// [`OrderError::InvalidPhone`] unless the phone has 10 to 15 digits.
+    /// Spaces and dashes in the phone are ignored.
+    pub fn new(id: CustomerId, name: &str, phone: &str) -> Result<Self, OrderError> {
+        let customer = Self {
+            id,
+            name: name.trim().to_string(),
+            phone: normalise_phone(phone),
+            addresses: Vec::new(),
+            preferred_payment: None,
+        };
+        customer.validate()?;
+        Ok(customer)
+    }
+
+    pub fn with_address(mut self, address: Address) -> Self {
+        self.addresses.push(address);
+        self
+    }
+
+    pub fn with_preferred_payment(mut self, method: PaymentMethod) -> Self {
+        self.preferred_payment = Some(method);
+        self
+    }
+
+    /// Check the name and phone still hold after the fields were edited.
+    pub fn validate(&self) -> Result<(), OrderError> {
+        if self.name.trim().is_empty() {
+            return Err(OrderError::NoCustomerName);
+        }
+        let digits = self.phone.trim_start_matches('+');
+        if !(10..=15).contains(&digits.len()) || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(OrderError::InvalidPhone(self.phone.clone()));
+        }
+        Ok(())
+    }
+}
+
+/// Drop spaces, dashes and brackets so one number has one spelling.
+pub(crate) fn normalise_phone(phone: &str) -> String {
+    phone
+        .trim()
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '(' | ')'))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn name_and_phone_are_required() {
+        let ok = Customer::new(CustomerId(1), " Asha ", "+91 98765-43210").unwrap();
+        assert_eq!((ok.name.as_str(), ok.phone.as_str()), ("Asha", "+919876543210"));
+
+        assert!(matches!(Customer::new(CustomerId(2), "  ", "9876543210"), Err(OrderError::NoCustomerName)));
+        assert!(matches!(Customer::new(CustomerId(3), "Ravi", "12345"), Err(OrderError::InvalidPhone(_))));
+        assert!(matches!(Customer::new(CustomerId(4), "Ravi", "98765abcde"), Err(OrderError::InvalidPhone(_))));
+    }
+}
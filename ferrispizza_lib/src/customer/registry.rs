@@ -0,0 +1,223 @@
+//! Customer registry, optionally saved to a JSON-lines file or any other
+//! [`CustomerStore`].
+//!
+//! # Example
+//! ```
+//! use ferrispizza_lib::customer::{CustomerRegistry, PaymentMethod};
+//! use ferrispizza_lib::errors::OrderError;
+//!
+//! let registry = CustomerRegistry::new();
+//! let asha = registry.register("Asha", "98765 43210").unwrap();
+//! assert_eq!(registry.find_by_phone("9876543210").unwrap().id, asha.id);
+//! assert!(matches!(registry.register("Someone", "9876543210"), Err(OrderError::CustomerExists(_))));
+//!
+//! registry.save(asha.with_preferred_payment(PaymentMethod::Upi)).unwrap();
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::errors::{OrderError, StorageError};
+use super::model::{normalise_phone, Customer, CustomerId};
+
+/// Durable home for the customers of a [`CustomerRegistry`].
+pub trait CustomerStore: Send {
+    /// Every saved customer, read once when the registry is opened.
+    fn load(&mut self) -> Result<Vec<Customer>, StorageError>;
+
+    /// Save a new or changed customer, replacing the one with its id.
+    fn save(&mut self, customer: &Customer) -> Result<(), StorageError>;
+}
+
+struct State {
+    customers: BTreeMap<CustomerId, Customer>,
+    store: Option<Box<dyn CustomerStore>>,
+}
+
+/// Thread-safe set of customers. Clones share the same registry.
+#[derive(Clone)]
+pub struct CustomerRegistry {
+    state: Arc<Mutex<State>>,
+}
+
+impl CustomerRegistry {
+    /// Registry that lives only as long as the process.
+    pub fn new() -> Self {
+        Self::with_state(BTreeMap::new(), None)
+    }
+
+    /// Open (or create) the registry file at `path`. Each line is a full
+    /// customer record; a later line for the same id replaces earlier ones.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, StorageError> {
+        Self::with_store(CustomerFile::open(path.as_ref())?)
+    }
+
+    /// Registry holding the customers in `store`, saving changes back to it.
+    pub fn with_store(mut store: impl CustomerStore + 'static) -> Result<Self, StorageError> {
+        let customers = store.load()?.into_iter().map(|c| (c.id, c)).collect();
+        Ok(Self::with_state(customers, Some(Box::new(store))))
+    }
+
+    fn with_state(customers: BTreeMap<CustomerId, Customer>, store: Option<Box<dyn CustomerStore>>) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(State { customers, store })),
+        }
+    }
+
+    /// Add a new customer with the next free id.
+    ///
+    /// Fails if the name or phone is invalid, or with
+    /// [`OrderError::CustomerExists`] if the phone is already registered.
+    pub fn register(&self, name: &str, phone: &str) -> Result<Customer, OrderError> {
+        let mut state = self.lock();
+        let next = state.customers.keys().next_back().map_or(1, |id| id.0 + 1);
+        let customer = Customer::new(CustomerId(next), name, phone)?;
+        check_phone_free(&state, &customer)?;
+        persist(&mut state, &customer)?;
+        state.customers.insert(customer.id, customer.clone());
+        Ok(customer)
+    }
+
+    /// Store changes to an existing customer, e.g. a new address.
+    pub fn save(&self, customer: Customer) -> Result<(), OrderError> {
+        customer.validate()?;
+        let mut state = self.lock();
+        if !state.customers.contains_key(&customer.id) {
+            return Err(OrderError::UnknownCustomer(customer.id.0.to_string()));
+        }
+        check_phone_free(&state, &customer)?;
+        persist(&mut state, &customer)?;
+        state.customers.insert(customer.id, customer);
+        Ok(())
+    }
+
+    pub fn get(&self, id: CustomerId) -> Option<Customer> {
+        self.lock().customers.get(&id).cloned()
+    }
+
+    pub fn find_by_phone(&self, phone: &str) -> Option<Customer> {
+        let phone = normalise_phone(phone);
+        self.lock().customers.values().find(|c| c.phone == phone).cloned()
+    }
+
+    /// Find a customer by phone number, or failing that by id.
+    pub fn lookup(&self, key: &str) -> Option<Customer> {
+        self.find_by_phone(key)
+            .or_else(|| key.parse().ok().and_then(|id| self.get(CustomerId(id))))
+    }
+
+    /// Every customer, by id.
+    pub fn list(&self) -> Vec<Customer> {
+        self.lock().customers.values().cloned().collect()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().expect("customer registry lock poisoned")
+    }
+}
+
+impl Default for CustomerRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn check_phone_free(state: &State, customer: &Customer) -> Result<(), OrderError> {
+    let taken = state
+        .customers
+        .values()
+        .any(|c| c.id != customer.id && c.phone == customer.phone);
+    if taken {
+        return Err(OrderError::CustomerExists(customer.phone.clone()));
+    }
+    Ok(())
+}
+
+fn persist(state: &mut State, customer: &Customer) -> Result<(), StorageError> {
+    match &mut state.store {
+        Some(store) => store.save(customer),
+        None => Ok(()),
+    }
+}
+
+/// Append-only JSON-lines file holding every saved version of a customer.
+struct CustomerFile {
+    path: PathBuf,
+    file: File,
+}
+
+impl CustomerFile {
+    fn open(path: &Path) -> Result<Self, StorageError> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+        })
+    }
+}
+
+impl CustomerStore for CustomerFile {
+    fn load(&mut self) -> Result<Vec<Customer>, StorageError> {
+        let mut customers = BTreeMap::new();
+        for (n, line) in BufReader::new(File::open(&self.path)?).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let customer: Customer = serde_json::from_str(&line).map_err(|e| StorageError::Corrupt {
+                file: self.path.display().to_string(),
+                line: n + 1,
+                reason: e.to_string(),
+            })?;
+            customers.insert(customer.id, customer);
+        }
+        Ok(customers.into_values().collect())
+    }
+
+    fn save(&mut self, customer: &Customer) -> Result<(), StorageError> {
+        let line = serde_json::to_string(customer).map_err(|e| StorageError::Serialize(e.to_string()))?;
+        writeln!(self.file, "{}", line)?;
+        self.file.sync_data()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::customer::Address;
+
+    #[test]
+    fn customers_survive_reopening() {
+        let path = std::env::temp_dir().join(format!("ferrispizza-customers-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        {
+            let registry = CustomerRegistry::open(&path).unwrap();
+            let asha = registry.register("Asha", "9876543210").unwrap();
+            registry.register("Ravi", "9123456780").unwrap();
+            registry.save(asha.with_address(Address::new("home", "12 MG Road"))).unwrap();
+        }
+
+        let registry = CustomerRegistry::open(&path).unwrap();
+        assert_eq!(registry.list().len(), 2);
+        assert_eq!(registry.lookup("1").unwrap().addresses[0].text, "12 MG Road");
+        assert_eq!(registry.register("Meera", "9000000001").unwrap().id, CustomerId(3));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn save_rejects_unknown_customers_and_taken_phones() {
+        let registry = CustomerRegistry::new();
+        let asha = registry.register("Asha", "9876543210").unwrap();
+        let mut ravi = registry.register("Ravi", "9123456780").unwrap();
+
+        ravi.phone = asha.phone.clone();
+        assert!(matches!(registry.save(ravi), Err(OrderError::CustomerExists(_))));
+
+        let stranger = Customer::new(CustomerId(99), "X", "9000000000").unwrap();
+        assert!(matches!(registry.save(stranger), Err(OrderError::UnknownCustomer(ref id)) if id == "99"));
+    }
+}
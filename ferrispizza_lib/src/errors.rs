@@ -1,8 +1,14 @@
 //! Central module for error types used across the Pizza Billing system.
 
 mod billing_error;
+mod menu_error;
+mod money_error;
 mod order_error;
+mod storage_error;
 
 /// Re-exports for external use without exposing file structure.
 pub use billing_error::BillingError;
+pub use menu_error::MenuError;
+pub use money_error::MoneyError;
 pub use order_error::OrderError;
+pub use storage_error::StorageError;
@@ -1,7 +1,13 @@
 //! Billing related errors for the Pizza Billing system.
 
+use std::time::Duration;
+
 use thiserror::Error;
 
+use super::{MoneyError, OrderError};
+use crate::money::Money;
+use crate::order::OrderStatus;
+
 /// Represents failures that may occur during billing operations.
 ///
 /// This error is returned by payment processors and invoice generators.
@@ -13,6 +19,15 @@ pub enum BillingError {
     #[error("Payment failed: {0}")]
     PaymentFailed(String),
 
+    /// The provider refused the payment, e.g. an invalid CVV. Trying again
+    /// will not help.
+    #[error("Payment declined: {0}")]
+    PaymentDeclined(String),
+
+    /// Payments are paused after repeated provider failures.
+    #[error("Payments are paused after repeated failures, try again in {}s", .retry_after.as_secs().max(1))]
+    CircuitOpen { retry_after: Duration },
+
     /// Invoice generation failed.
     ///
     /// Contains a message describing the invoice generation issue.
@@ -22,4 +37,109 @@ pub enum BillingError {
     /// The selected payment method is not supported or invalid.
     #[error("Invalid payment method selected")]
     InvalidPaymentMethod,
+
+    /// The order being billed could not be read or updated.
+    #[error(transparent)]
+    Order(#[from] OrderError),
+
+    /// No coupon with this code exists.
+    #[error("Unknown coupon code: {0}")]
+    UnknownCoupon(String),
+
+    /// The coupon's validity window has not started yet.
+    #[error("Coupon {0} is not valid yet")]
+    CouponNotYetValid(String),
+
+    /// The coupon's validity window has ended.
+    #[error("Coupon {0} has expired")]
+    CouponExpired(String),
+
+    /// The order is below the coupon's minimum value.
+    #[error("Coupon {code} requires a minimum order of {minimum}")]
+    MinimumOrderNotMet { code: String, minimum: Money },
+
+    /// The customer already used the coupon as often as allowed.
+    #[error("Coupon {code} usage limit reached for {customer}")]
+    CouponUsageExceeded { code: String, customer: String },
+
+    /// Points were offered but the order has no customer, or the engine
+    /// runs no loyalty program.
+    #[error("Loyalty points need a registered customer and a loyalty program")]
+    NoLoyaltyAccount,
+
+    /// The customer has fewer unexpired points than they offered.
+    #[error("Not enough loyalty points: {requested} requested, {available} available")]
+    InsufficientPoints { requested: u32, available: u32 },
+
+    /// Points were offered but the loyalty program makes them worth nothing.
+    #[error("Loyalty points cannot be redeemed: they have no value in this program")]
+    PointsNotRedeemable,
+
+    /// The order has not been paid, so there is nothing to refund.
+    #[error("Order {0} has no payment to refund")]
+    NotPaid(u64),
+
+    /// Refunds must be for a positive amount.
+    #[error("Invalid refund amount: {0}")]
+    InvalidRefundAmount(Money),
+
+    /// More was asked back than is left on the payment.
+    #[error("Cannot refund {requested}: only {refundable} left to refund")]
+    OverRefund { requested: Money, refundable: Money },
+
+    /// The payment is older than the refund window.
+    #[error("Order {order_id} was paid more than {days} days ago and can no longer be refunded")]
+    RefundWindowClosed { order_id: u64, days: u64 },
+
+    /// No pizza at this position (from 0) on the order. Shown numbered
+    /// from 1, like kitchen tickets.
+    #[error("Order has no pizza #{}", .0 + 1)]
+    UnknownLine(usize),
+
+    /// The pizza at this position (from 0) was already refunded.
+    #[error("Pizza #{} was already refunded", .0 + 1)]
+    LineAlreadyRefunded(usize),
+
+    /// The idempotency key was already used to pay this order with
+    /// different parameters.
+    #[error("Key '{key}' was already used to pay order {order_id} differently")]
+    IdempotencyConflict { order_id: u64, key: String },
+
+    /// A payment with this key is still being charged.
+    #[error("Payment of order {order_id} with key '{key}' is still in progress")]
+    PaymentInProgress { order_id: u64, key: String },
+
+    /// A payment with this key failed after taking money that could not be
+    /// returned; it must be sorted out by hand before paying again.
+    #[error("Payment of order {order_id} with key '{key}' failed after taking money; resolve it before paying again")]
+    PaymentUnresolved { order_id: u64, key: String },
+
+    /// A split payment's legs do not add up to the bill.
+    #[error("Split payment covers {allocated} of a {total} bill")]
+    SplitMismatch { allocated: Money, total: Money },
+
+    /// A leg of a split payment would charge nothing or less.
+    #[error("Invalid split payment amount: {0}")]
+    InvalidSplitAmount(Money),
+
+    /// A payment failed part-way and money already taken could not all be
+    /// returned.
+    #[error("Payment of order {order_id} failed and {outstanding} could not be refunded")]
+    RollbackFailed { order_id: u64, outstanding: Money },
+
+    /// A refund spanning several payment methods could fail part-way,
+    /// leaving a partial refund the order cannot record yet.
+    #[error("Order {order_id} was paid by several methods and cannot be refunded while {status:?}; refund it once completed")]
+    SplitRefundNotAllowed { order_id: u64, status: OrderStatus },
+
+    /// An amount on the bill could not be computed exactly.
+    #[error(transparent)]
+    Money(#[from] MoneyError),
+}
+
+impl BillingError {
+    /// Whether the same payment might succeed if tried again.
+    pub fn is_transient(&self) -> bool {
+        matches!(self, BillingError::PaymentFailed(_))
+    }
 }
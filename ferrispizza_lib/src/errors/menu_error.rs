@@ -0,0 +1,35 @@
+//! Menu catalog errors for the Pizza Billing system.
+
+use thiserror::Error;
+
+/// Represents failures while loading or validating a menu catalog.
+#[derive(Debug, Error)]
+pub enum MenuError {
+    /// The catalog file could not be read.
+    #[error("Failed to read menu file: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// The catalog text is not valid TOML/JSON or has the wrong shape.
+    #[error("Failed to parse menu: {0}")]
+    Parse(String),
+
+    /// The file extension is neither `.toml` nor `.json`.
+    #[error("Unsupported menu format: {0}")]
+    UnsupportedFormat(String),
+
+    /// The same item id appears more than once.
+    #[error("Duplicate menu item: {0}")]
+    DuplicateItem(String),
+
+    /// A price or currency could not be interpreted.
+    #[error("Invalid price for {item}: {reason}")]
+    InvalidPrice { item: String, reason: String },
+
+    /// A preset refers to an item that is not on the menu.
+    #[error("Preset {preset} refers to unknown item {item}")]
+    UnknownReference { preset: String, item: String },
+
+    /// The `[rules]` section names an item that is not on the menu.
+    #[error("Rule refers to unknown item {0}")]
+    UnknownRuleItem(String),
+}
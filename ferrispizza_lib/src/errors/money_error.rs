@@ -0,0 +1,21 @@
+//! Monetary arithmetic errors for the Pizza Billing system.
+
+use thiserror::Error;
+
+use crate::money::Currency;
+
+/// Represents failures of checked [`Money`](crate::money::Money) operations.
+#[derive(Debug, Error, PartialEq)]
+pub enum MoneyError {
+    /// Two amounts in different currencies were combined.
+    #[error("Currency mismatch: expected {expected}, found {found}")]
+    CurrencyMismatch { expected: Currency, found: Currency },
+
+    /// The result does not fit in the minor-unit representation.
+    #[error("Monetary amount overflow")]
+    Overflow,
+
+    /// A textual amount or rate could not be interpreted.
+    #[error("Invalid monetary amount: {0}")]
+    InvalidAmount(String),
+}
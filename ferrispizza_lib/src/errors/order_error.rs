@@ -2,6 +2,9 @@
 
 use thiserror::Error;
 
+use crate::order::OrderStatus;
+use super::StorageError;
+
 /// Represents failures that may occur while creating or processing an order.
 #[derive(Debug, Error)]
 pub enum OrderError {
@@ -13,9 +16,61 @@ pub enum OrderError {
     #[error("Customer name missing")]
     NoCustomerName,
 
+    /// The phone number is not 10 to 15 digits.
+    #[error("Invalid phone number: {0}")]
+    InvalidPhone(String),
+
+    /// Another customer is already registered with this phone number.
+    #[error("A customer with phone {0} already exists")]
+    CustomerExists(String),
+
+    /// No customer with the given ID is registered.
+    #[error("Customer {0} not found")]
+    UnknownCustomer(String),
+
     /// Error occurred while interacting with the internal order queue.
     ///
     /// Usually indicates a sender/receiver channel failure.
     #[error("Order queue failure: {0}")]
     QueueError(String),
+
+    /// No order with the given ID exists in the order store.
+    #[error("Order {0} not found")]
+    NotFound(u64),
+
+    /// The requested item is not on the menu.
+    #[error("Unknown menu item: {0}")]
+    UnknownItem(String),
+
+    /// The requested item is on the menu but currently not sold.
+    #[error("Menu item unavailable: {0}")]
+    ItemUnavailable(String),
+
+    /// The order store could not persist or load orders.
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+
+    /// The order lifecycle does not allow moving between these states.
+    #[error("Invalid order transition from {from:?} to {to:?}")]
+    InvalidTransition { from: OrderStatus, to: OrderStatus },
+
+    /// Not enough stock to make this item.
+    #[error("Out of stock: {0}")]
+    OutOfStock(String),
+
+    /// A pizza has more than one crust.
+    #[error("Only one crust per pizza: {first} and {second}")]
+    MultipleCrusts { first: String, second: String },
+
+    /// A topping was added more times than allowed.
+    #[error("At most {max} x {topping} per pizza")]
+    ToppingLimit { topping: String, max: usize },
+
+    /// A pizza has more toppings than allowed in total.
+    #[error("{count} toppings on one pizza, at most {max} allowed")]
+    TooManyToppings { count: usize, max: usize },
+
+    /// Two parts declared incompatible were put on the same pizza.
+    #[error("{first} cannot be combined with {second}")]
+    IncompatibleItems { first: String, second: String },
 }
@@ -0,0 +1,40 @@
+//! Persistence errors for the Pizza Billing system.
+
+use thiserror::Error;
+
+/// Represents failures while reading or writing the order store.
+#[derive(Debug, Error)]
+pub enum StorageError {
+    /// The underlying file could not be read or written.
+    #[error("Storage I/O failure: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// A record could not be encoded.
+    #[error("Failed to encode order record: {0}")]
+    Serialize(String),
+
+    /// A stored value could not be decoded, e.g. an unknown status name.
+    #[error("Failed to decode stored value: {0}")]
+    Decode(String),
+
+    /// A stored file is damaged beyond the usual torn final write.
+    #[error("Corrupt order store {file} at line {line}: {reason}")]
+    Corrupt {
+        file: String,
+        line: usize,
+        reason: String,
+    },
+
+    /// A database row cannot be turned back into a record.
+    #[error("Corrupt row in {table} for id {id}: {reason}")]
+    CorruptRow {
+        table: String,
+        id: u64,
+        reason: String,
+    },
+
+    /// The SQLite database rejected a query or migration.
+    #[cfg(feature = "sqlite")]
+    #[error("SQLite failure: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+}
@@ -0,0 +1,14 @@
+//! Inventory module — ingredient stock behind every order.
+//!
+//! A [`RecipeBook`] says which ingredients each base, crust and topping
+//! uses; an [`Inventory`] tracks stock of those ingredients. Stock is
+//! reserved when an order is placed, taken off the shelf when the kitchen
+//! starts cooking and handed back if the order is cancelled first.
+//! Attach an inventory to a
+//! [`SharedOrderState`](crate::concurrency::SharedOrderState) to apply this
+//! to every order.
+mod recipe;
+mod stock;
+
+pub use recipe::{Recipe, RecipeBook};
+pub use stock::{Inventory, StockLevel};
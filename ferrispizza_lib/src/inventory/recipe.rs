@@ -0,0 +1,68 @@
+//! Ingredient recipes for pizza parts.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::menu::Menu;
+use crate::pizza::{PizzaPart, PizzaSpec, Placement, Size};
+
+/// Ingredient name → quantity for a whole medium portion, in whatever unit
+/// the stock is counted in (grams for the built-in menu).
+pub type Recipe = BTreeMap<String, u32>;
+
+/// Recipes keyed by part name, e.g. `"Cheese"` or `"Thin Crust"`.
+///
+/// Names match case-insensitively. Parts without a recipe use no tracked
+/// ingredients.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RecipeBook {
+    recipes: HashMap<String, Recipe>,
+}
+
+impl RecipeBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Recipes from every menu item that lists one. The built-in decorators
+    /// share their names with the default menu, so this covers them too.
+    pub fn from_menu(menu: &Menu) -> Self {
+        menu.bases
+            .iter()
+            .chain(&menu.crusts)
+            .chain(&menu.toppings)
+            .filter(|item| !item.recipe.is_empty())
+            .fold(Self::new(), |book, item| book.with_recipe(&item.name, item.recipe.clone()))
+    }
+
+    pub fn with_recipe(mut self, part: &str, recipe: Recipe) -> Self {
+        self.recipes.insert(part.to_lowercase(), recipe);
+        self
+    }
+
+    pub fn recipe(&self, part: &str) -> Option<&Recipe> {
+        self.recipes.get(&part.to_lowercase())
+    }
+
+    /// Ingredients one part needs at `size`, halved for a half topping and
+    /// rounded up.
+    pub fn part_needs(&self, part: &PizzaPart, size: Size) -> Recipe {
+        let share = if part.placement == Placement::Whole { 1 } else { 2 };
+        self.recipe(&part.name)
+            .into_iter()
+            .flatten()
+            .map(|(ingredient, &qty)| {
+                let scaled = (u64::from(qty) * u64::from(size.scale_bps())).div_ceil(10_000 * share);
+                (ingredient.clone(), u32::try_from(scaled).unwrap_or(u32::MAX))
+            })
+            .collect()
+    }
+
+    /// Parts of `spec` with what each one needs: base, toppings, crust.
+    pub fn needs<'a>(&self, spec: &'a PizzaSpec) -> Vec<(&'a PizzaPart, Recipe)> {
+        std::iter::once(&spec.base)
+            .chain(&spec.toppings)
+            .chain(&spec.crust)
+            .map(|part| (part, self.part_needs(part, spec.size)))
+            .collect()
+    }
+}
@@ -0,0 +1,295 @@
+//! Ingredient stock with per-order reservations.
+//!
+//! # Example
+//! ```
+//! use ferrispizza_lib::errors::OrderError;
+//! use ferrispizza_lib::inventory::{Inventory, Recipe, RecipeBook};
+//! use ferrispizza_lib::order::order;
+//! use ferrispizza_lib::pizza::{Cheese, Margherita};
+//!
+//! let recipes = RecipeBook::new()
+//!     .with_recipe("Margherita", Recipe::from([("dough".to_string(), 250)]))
+//!     .with_recipe("Cheese", Recipe::from([("mozzarella".to_string(), 60)]));
+//! let inventory = Inventory::new(recipes).with_stock("dough", 500).with_stock("mozzarella", 100);
+//!
+//! let first = order::new(vec![Box::new(Cheese::new(Box::new(Margherita::new())))]);
+//! inventory.reserve(&first).unwrap();
+//! assert_eq!(inventory.level("mozzarella").unwrap().available(), 40);
+//!
+//! let second = order::new(vec![Box::new(Cheese::new(Box::new(Margherita::new())))]);
+//! assert!(matches!(inventory.reserve(&second), Err(OrderError::OutOfStock(item)) if item == "Cheese"));
+//!
+//! inventory.commit(first.id.0); // the kitchen started cooking
+//! assert_eq!(inventory.level("mozzarella").unwrap().on_hand, 40);
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::errors::OrderError;
+use crate::order::order;
+use crate::utils::FileLogger;
+use super::{Recipe, RecipeBook};
+
+/// Stock of one ingredient.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StockLevel {
+    pub on_hand: u32,
+    /// Held for orders that have not started cooking.
+    pub reserved: u32,
+    /// Alert once available stock falls to this level.
+    pub low_at: u32,
+}
+
+impl StockLevel {
+    /// Stock not yet promised to an order.
+    pub fn available(&self) -> u32 {
+        self.on_hand.saturating_sub(self.reserved)
+    }
+
+    pub fn is_low(&self) -> bool {
+        self.available() <= self.low_at
+    }
+}
+
+#[derive(Default)]
+struct State {
+    stock: BTreeMap<String, StockLevel>,
+    /// What each order has reserved, until it is committed or released.
+    reservations: HashMap<u64, Recipe>,
+}
+
+/// Thread-safe ingredient stock. Clones share the same stock.
+#[derive(Clone)]
+pub struct Inventory {
+    recipes: Arc<RecipeBook>,
+    state: Arc<Mutex<State>>,
+    logger: Option<Arc<FileLogger>>,
+}
+
+impl Inventory {
+    /// Empty stock for `recipes`; add ingredients with
+    /// [`with_stock`](Self::with_stock).
+    pub fn new(recipes: RecipeBook) -> Self {
+        Self {
+            recipes: Arc::new(recipes),
+            state: Arc::new(Mutex::new(State::default())),
+            logger: None,
+        }
+    }
+
+    pub fn with_stock(self, ingredient: &str, on_hand: u32) -> Self {
+        self.restock(ingredient, on_hand);
+        self
+    }
+
+    pub fn with_low_stock_at(self, ingredient: &str, low_at: u32) -> Self {
+        self.lock().stock.entry(ingredient.to_string()).or_default().low_at = low_at;
+        self
+    }
+
+    /// Write low-stock alerts to `logger`.
+    pub fn with_logger(mut self, logger: FileLogger) -> Self {
+        self.logger = Some(Arc::new(logger));
+        self
+    }
+
+    pub fn recipes(&self) -> &RecipeBook {
+        &self.recipes
+    }
+
+    /// Add `qty` to the stock on hand.
+    pub fn restock(&self, ingredient: &str, qty: u32) {
+        let mut state = self.lock();
+        let level = state.stock.entry(ingredient.to_string()).or_default();
+        level.on_hand = level.on_hand.saturating_add(qty);
+    }
+
+    pub fn level(&self, ingredient: &str) -> Option<StockLevel> {
+        self.lock().stock.get(ingredient).copied()
+    }
+
+    /// Every ingredient, in name order.
+    pub fn levels(&self) -> Vec<(String, StockLevel)> {
+        self.lock().stock.iter().map(|(name, level)| (name.clone(), *level)).collect()
+    }
+
+    /// Ingredients at or below their alert level.
+    pub fn low_stock(&self) -> Vec<(String, StockLevel)> {
+        self.levels().into_iter().filter(|(_, level)| level.is_low()).collect()
+    }
+
+    /// Hold the ingredients for every pizza on `order`.
+    ///
+    /// Nothing is reserved unless all of it is available. Returns
+    /// [`OrderError::OutOfStock`] naming the first part that cannot be
+    /// made. Reserving an order again replaces its earlier reservation.
+    pub fn reserve(&self, order: &order) -> Result<(), OrderError> {
+        let mut state = self.lock();
+        let previous = state.reservations.remove(&order.id.0);
+        if let Some(previous) = &previous {
+            unreserve(&mut state, previous);
+        }
+
+        let mut needed = Recipe::new();
+        for pizza in &order.pizzas {
+            let spec = pizza.spec();
+            for (part, needs) in self.recipes.needs(&spec) {
+                for (ingredient, qty) in needs {
+                    let total = needed.entry(ingredient).or_default();
+                    *total = total.saturating_add(qty);
+                }
+                let short = needed.iter().any(|(ingredient, &qty)| {
+                    state.stock.get(ingredient).map_or(0, StockLevel::available) < qty
+                });
+                if short {
+                    if let Some(previous) = previous {
+                        apply(&mut state, &previous);
+                        state.reservations.insert(order.id.0, previous);
+                    }
+                    return Err(OrderError::OutOfStock(part.name.clone()));
+                }
+            }
+        }
+
+        let crossed = apply(&mut state, &needed);
+        state.reservations.insert(order.id.0, needed);
+        drop(state);
+        for (ingredient, level) in crossed {
+            self.alert(&ingredient, level);
+        }
+        Ok(())
+    }
+
+    /// The kitchen has started on order `id`: use up what it reserved.
+    /// Does nothing if the order holds no reservation.
+    pub fn commit(&self, id: u64) {
+        let mut state = self.lock();
+        if let Some(reserved) = state.reservations.remove(&id) {
+            for (ingredient, qty) in reserved {
+                let level = state.stock.entry(ingredient).or_default();
+                level.reserved = level.reserved.saturating_sub(qty);
+                level.on_hand = level.on_hand.saturating_sub(qty);
+            }
+        }
+    }
+
+    /// Order `id` was cancelled before cooking: return what it reserved.
+    pub fn release(&self, id: u64) {
+        let mut state = self.lock();
+        if let Some(reserved) = state.reservations.remove(&id) {
+            unreserve(&mut state, &reserved);
+        }
+    }
+
+    /// What order `id` currently holds.
+    pub fn reserved_for(&self, id: u64) -> Option<Recipe> {
+        self.lock().reservations.get(&id).cloned()
+    }
+
+    fn alert(&self, ingredient: &str, level: StockLevel) {
+        let message = format!(
+            "LOW STOCK {}: {} available ({} on hand, {} reserved), alert level {}",
+            ingredient,
+            level.available(),
+            level.on_hand,
+            level.reserved,
+            level.low_at
+        );
+        if let Some(logger) = &self.logger
+            && let Err(e) = logger.log_with_timestamp(&message)
+        {
+            eprintln!("Failed to persist inventory log: {}", e);
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().expect("inventory lock poisoned")
+    }
+}
+
+/// Reserve `needs`; returns the ingredients that just fell to their alert level.
+fn apply(state: &mut State, needs: &Recipe) -> Vec<(String, StockLevel)> {
+    let mut crossed = Vec::new();
+    for (ingredient, &qty) in needs {
+        let level = state.stock.entry(ingredient.clone()).or_default();
+        let was_low = level.is_low();
+        level.reserved = level.reserved.saturating_add(qty);
+        if !was_low && level.is_low() {
+            crossed.push((ingredient.clone(), *level));
+        }
+    }
+    crossed
+}
+
+fn unreserve(state: &mut State, reserved: &Recipe) {
+    for (ingredient, &qty) in reserved {
+        if let Some(level) = state.stock.get_mut(ingredient) {
+            level.reserved = level.reserved.saturating_sub(qty);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pizza::{Olives, Margherita, Pizza, Placement, Size};
+
+    fn inventory() -> Inventory {
+        let recipes = RecipeBook::new()
+            .with_recipe("Margherita", Recipe::from([("dough".into(), 200), ("mozzarella".into(), 100)]))
+            .with_recipe("Olives", Recipe::from([("olives".into(), 40)]));
+        Inventory::new(recipes)
+            .with_stock("dough", 1_000)
+            .with_stock("mozzarella", 1_000)
+            .with_stock("olives", 50)
+    }
+
+    #[test]
+    fn sizes_and_halves_scale_what_is_reserved() {
+        let inventory = inventory();
+        let pizza = Olives::new(Box::new(Margherita::new().with_size(Size::Large))).with_placement(Placement::Left);
+        let order = order::new(vec![Box::new(pizza) as Box<dyn Pizza>]);
+        inventory.reserve(&order).unwrap();
+
+        let held = inventory.reserved_for(order.id.0).unwrap();
+        assert_eq!(held["dough"], 300);
+        assert_eq!(held["olives"], 30);
+    }
+
+    #[test]
+    fn failed_reservation_holds_nothing_and_release_returns_stock() {
+        let inventory = inventory();
+        let hungry = order::new(vec![
+            Box::new(Olives::new(Box::new(Margherita::new()))) as Box<dyn Pizza>,
+            Box::new(Olives::new(Box::new(Margherita::new()))),
+        ]);
+        assert!(matches!(inventory.reserve(&hungry), Err(OrderError::OutOfStock(ref item)) if item == "Olives"));
+        assert_eq!(inventory.level("dough").unwrap().reserved, 0);
+
+        let order = order::new(vec![Box::new(Margherita::new()) as Box<dyn Pizza>]);
+        inventory.reserve(&order).unwrap();
+        inventory.release(order.id.0);
+        inventory.commit(order.id.0);
+        assert_eq!(inventory.level("dough").unwrap(), StockLevel { on_hand: 1_000, ..StockLevel::default() });
+    }
+
+    #[test]
+    fn crossing_the_alert_level_is_logged_once() {
+        let path = std::env::temp_dir().join(format!("ferrispizza-stock-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let inventory = inventory()
+            .with_low_stock_at("dough", 700)
+            .with_logger(FileLogger::new(path.to_string_lossy()));
+
+        for _ in 0..3 {
+            let order = order::new(vec![Box::new(Margherita::new()) as Box<dyn Pizza>]);
+            inventory.reserve(&order).unwrap();
+        }
+
+        let log = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(log.matches("LOW STOCK dough").count(), 1);
+        assert_eq!(inventory.low_stock().len(), 1);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -5,3 +5,8 @@ pub mod utils;
 pub mod errors;
 pub mod concurrency;
 pub mod order;
+pub mod money;
+pub mod menu;
+pub mod inventory;
+pub mod customer;
+pub mod storage;
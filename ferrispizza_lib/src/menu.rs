@@ -0,0 +1,8 @@
+//! Menu module — data-driven catalog of bases, crusts and toppings.
+//!
+//! The [`Menu`] is loaded from a TOML or JSON file and builds
+//! `Box<dyn Pizza>` decorator chains from item ids, so new items and price
+//! changes do not require a recompile.
+mod catalog;
+
+pub use catalog::{Menu, MenuItem, MenuSection, Preset};
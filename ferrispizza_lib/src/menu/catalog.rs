@@ -0,0 +1,725 @@
+//! Menu catalog — item names, prices and availability loaded from data.
+//!
+//! A [`Menu`] is read from TOML or JSON with the same shape (see
+//! `menu.toml` at the crate root, which is also the built-in default).
+//! It validates the file once on load and then builds decorator chains
+//! from item ids, so adding a pizza only needs a file edit.
+//!
+//! `price` is the medium price; small, large and family prices scale from
+//! it (see [`PriceMatrix::scaled`]) unless an item lists its own under
+//! `sizes`. Prices are whole numbers or decimal strings (`"7.50"`); bare
+//! decimals such as `7.5` are rejected, as floats cannot hold money exactly.
+//!
+//! Items may list `nutrition` facts for a whole medium portion; pizzas
+//! built from the menu carry them scaled for size and placement, and
+//! [`Menu::matching`] filters by allergen or diet.
+//!
+//! An optional `[rules]` section sets the [`PizzaRules`] every pizza built
+//! from the menu must pass; it names items by id.
+//!
+//! # Example
+//! ```
+//! use ferrispizza_lib::menu::Menu;
+//! use ferrispizza_lib::money::Money;
+//! use ferrispizza_lib::pizza::Size;
+//!
+//! let menu = Menu::from_toml_str(r#"
+//!     currency = "INR"
+//!
+//!     [[bases]]
+//!     id = "paneer"
+//!     name = "Paneer Tikka"
+//!     price = "180"
+//!     sizes = { family = "320" }
+//!
+//!     [[toppings]]
+//!     id = "onion"
+//!     name = "Onion"
+//!     price = "7.50"
+//! "#).unwrap();
+//!
+//! let pizza = menu.build_pizza("paneer", Size::Medium, None, &["onion"]).unwrap();
+//! assert_eq!(pizza.description(), "Medium Paneer Tikka + Onion");
+//! assert_eq!(pizza.cost().unwrap().to_string(), "₹187.50");
+//!
+//! let family = menu.build_pizza("paneer", Size::Family, None, &["onion"]).unwrap();
+//! assert_eq!(family.cost().unwrap().to_string(), "₹335.00");
+//! ```
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::billing::TaxCategory;
+use crate::errors::{MenuError, OrderError};
+use crate::inventory::Recipe;
+use crate::money::{Currency, Money};
+use crate::pizza::{
+    Nutrition, NutritionFilter, Pizza, PizzaPart, PizzaRules, PizzaSpec, Placement, PriceMatrix, Size,
+};
+
+/// The menu shipped with the library.
+const DEFAULT_MENU: &str = include_str!("../../menu.toml");
+
+/// Which part of a pizza a menu item is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MenuSection {
+    Base,
+    Crust,
+    Topping,
+}
+
+/// A single sellable item.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MenuItem {
+    /// Stable identifier typed by staff, e.g. `"cheese_burst"`.
+    pub id: String,
+    /// Name printed on descriptions and receipts.
+    pub name: String,
+    pub prices: PriceMatrix,
+    pub category: TaxCategory,
+    pub available: bool,
+    /// Label facts for a whole medium portion, if declared.
+    pub nutrition: Option<Nutrition>,
+    /// Ingredients used by a whole medium portion.
+    pub recipe: Recipe,
+}
+
+impl MenuItem {
+    pub fn price(&self, size: Size) -> Money {
+        self.prices.price(size)
+    }
+
+    /// Part priced and labelled for `size` and `placement`.
+    fn part(&self, size: Size, placement: Placement) -> PizzaPart {
+        let mut part = PizzaPart::new(&self.name, placement.charge(self.price(size))).with_placement(placement);
+        part.nutrition = self.nutrition.as_ref().map(|n| n.portion(size, placement));
+        part
+    }
+}
+
+/// A named combination sold as one menu entry (e.g. `thin_margherita`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Preset {
+    pub id: String,
+    pub base: String,
+    pub crust: Option<String>,
+    pub toppings: Vec<String>,
+}
+
+/// Loaded, validated menu catalog.
+#[derive(Clone, Debug)]
+pub struct Menu {
+    pub currency: Currency,
+    pub bases: Vec<MenuItem>,
+    pub crusts: Vec<MenuItem>,
+    pub toppings: Vec<MenuItem>,
+    pub presets: Vec<Preset>,
+    /// Checked on every pizza the menu builds.
+    pub rules: PizzaRules,
+}
+
+// ---- File representation ----
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawPrice {
+    Text(String),
+    Whole(i64),
+    /// Only recognised to explain why it is rejected.
+    Float(f64),
+}
+
+#[derive(Deserialize)]
+struct RawItem {
+    id: String,
+    name: String,
+    price: RawPrice,
+    /// Per-size overrides keyed by size name, e.g. `family = "299"`.
+    #[serde(default)]
+    sizes: BTreeMap<String, RawPrice>,
+    #[serde(default)]
+    category: TaxCategory,
+    #[serde(default = "available_by_default")]
+    available: bool,
+    nutrition: Option<Nutrition>,
+    #[serde(default)]
+    recipe: Recipe,
+}
+
+fn available_by_default() -> bool {
+    true
+}
+
+#[derive(Deserialize)]
+struct RawPreset {
+    id: String,
+    base: String,
+    crust: Option<String>,
+    #[serde(default)]
+    toppings: Vec<String>,
+}
+
+#[derive(Deserialize, Default)]
+struct RawRules {
+    max_toppings: Option<usize>,
+    max_per_topping: Option<usize>,
+    /// Per-topping limits keyed by topping id.
+    #[serde(default)]
+    limits: BTreeMap<String, usize>,
+    /// Pairs of item ids that may not share a pizza.
+    #[serde(default)]
+    incompatible: Vec<[String; 2]>,
+}
+
+#[derive(Deserialize)]
+struct RawMenu {
+    #[serde(default = "default_currency")]
+    currency: String,
+    #[serde(default)]
+    bases: Vec<RawItem>,
+    #[serde(default)]
+    crusts: Vec<RawItem>,
+    #[serde(default)]
+    toppings: Vec<RawItem>,
+    #[serde(default)]
+    presets: Vec<RawPreset>,
+    #[serde(default)]
+    rules: RawRules,
+}
+
+fn default_currency() -> String {
+    Currency::default().code().to_string()
+}
+
+impl Menu {
+    /// Parse a catalog written in TOML.
+    pub fn from_toml_str(text: &str) -> Result<Self, MenuError> {
+        let raw: RawMenu = toml::from_str(text).map_err(|e| MenuError::Parse(e.to_string()))?;
+        Self::from_raw(raw)
+    }
+
+    /// Parse a catalog written in JSON.
+    pub fn from_json_str(text: &str) -> Result<Self, MenuError> {
+        let raw: RawMenu =
+            serde_json::from_str(text).map_err(|e| MenuError::Parse(e.to_string()))?;
+        Self::from_raw(raw)
+    }
+
+    /// Load a `.toml` or `.json` catalog from disk.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, MenuError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Self::from_toml_str(&text),
+            Some("json") => Self::from_json_str(&text),
+            other => Err(MenuError::UnsupportedFormat(
+                other.unwrap_or_default().to_string(),
+            )),
+        }
+    }
+
+    fn from_raw(raw: RawMenu) -> Result<Self, MenuError> {
+        let currency = Currency::from_code(&raw.currency).ok_or_else(|| MenuError::InvalidPrice {
+            item: "menu".to_string(),
+            reason: format!("unknown currency {}", raw.currency),
+        })?;
+
+        let mut seen = HashSet::new();
+        let mut convert = |items: Vec<RawItem>| -> Result<Vec<MenuItem>, MenuError> {
+            items
+                .into_iter()
+                .map(|item| {
+                    if !seen.insert(item.id.clone()) {
+                        return Err(MenuError::DuplicateItem(item.id));
+                    }
+                    let medium = parse_price(&item.id, item.price, currency)?;
+                    let mut prices = PriceMatrix::scaled(medium).map_err(|e| MenuError::InvalidPrice {
+                        item: item.id.clone(),
+                        reason: e.to_string(),
+                    })?;
+                    for (name, raw) in item.sizes {
+                        let size = Size::parse(&name).ok_or_else(|| MenuError::InvalidPrice {
+                            item: item.id.clone(),
+                            reason: format!("unknown size {}", name),
+                        })?;
+                        prices = prices.with_price(size, parse_price(&item.id, raw, currency)?);
+                    }
+                    Ok(MenuItem {
+                        id: item.id,
+                        name: item.name,
+                        prices,
+                        category: item.category,
+                        available: item.available,
+                        nutrition: item.nutrition,
+                        recipe: item.recipe,
+                    })
+                })
+                .collect()
+        };
+
+        let mut menu = Menu {
+            currency,
+            bases: convert(raw.bases)?,
+            crusts: convert(raw.crusts)?,
+            toppings: convert(raw.toppings)?,
+            presets: raw
+                .presets
+                .into_iter()
+                .map(|p| Preset {
+                    id: p.id,
+                    base: p.base,
+                    crust: p.crust,
+                    toppings: p.toppings,
+                })
+                .collect(),
+            rules: PizzaRules::default(),
+        };
+
+        for preset in &menu.presets {
+            if !seen.insert(preset.id.clone()) {
+                return Err(MenuError::DuplicateItem(preset.id.clone()));
+            }
+            let unknown = |item: &str| MenuError::UnknownReference {
+                preset: preset.id.clone(),
+                item: item.to_string(),
+            };
+            if menu.base(&preset.base).is_none() {
+                return Err(unknown(&preset.base));
+            }
+            if let Some(crust) = &preset.crust
+                && menu.crust(crust).is_none()
+            {
+                return Err(unknown(crust));
+            }
+            if let Some(t) = preset.toppings.iter().find(|t| menu.topping(t).is_none()) {
+                return Err(unknown(t));
+            }
+        }
+
+        menu.rules = menu.rules_from(raw.rules)?;
+        Ok(menu)
+    }
+
+    /// Resolve the ids in a `[rules]` section to item names.
+    fn rules_from(&self, raw: RawRules) -> Result<PizzaRules, MenuError> {
+        let name = |id: &str| {
+            self.base(id)
+                .or_else(|| self.crust(id))
+                .or_else(|| self.topping(id))
+                .map(|item| item.name.clone())
+                .ok_or_else(|| MenuError::UnknownRuleItem(id.to_string()))
+        };
+
+        let mut rules = PizzaRules::default();
+        if let Some(max) = raw.max_toppings {
+            rules = rules.with_max_toppings(max);
+        }
+        if let Some(max) = raw.max_per_topping {
+            rules = rules.with_max_per_topping(max);
+        }
+        for (id, max) in &raw.limits {
+            rules = rules.with_limit(name(id)?, *max);
+        }
+        for [first, second] in &raw.incompatible {
+            rules = rules.with_incompatible(name(first)?, name(second)?);
+        }
+        Ok(rules)
+    }
+
+    /// Check a pizza against the menu's [`PizzaRules`].
+    pub fn validate(&self, pizza: &dyn Pizza) -> Result<(), OrderError> {
+        self.rules.validate(pizza)
+    }
+
+    pub fn base(&self, id: &str) -> Option<&MenuItem> {
+        self.bases.iter().find(|i| i.id == id)
+    }
+
+    pub fn crust(&self, id: &str) -> Option<&MenuItem> {
+        self.crusts.iter().find(|i| i.id == id)
+    }
+
+    pub fn topping(&self, id: &str) -> Option<&MenuItem> {
+        self.toppings.iter().find(|i| i.id == id)
+    }
+
+    pub fn preset(&self, id: &str) -> Option<&Preset> {
+        self.presets.iter().find(|p| p.id == id)
+    }
+
+    /// Items in `section` that are currently on sale.
+    pub fn available(&self, section: MenuSection) -> impl Iterator<Item = &MenuItem> {
+        let items = match section {
+            MenuSection::Base => &self.bases,
+            MenuSection::Crust => &self.crusts,
+            MenuSection::Topping => &self.toppings,
+        };
+        items.iter().filter(|i| i.available)
+    }
+
+    /// Items in `section` on sale whose facts pass `filter`.
+    pub fn matching<'a>(
+        &'a self,
+        section: MenuSection,
+        filter: &'a NutritionFilter,
+    ) -> impl Iterator<Item = &'a MenuItem> {
+        self.available(section)
+            .filter(move |i| filter.matches(i.nutrition.as_ref()))
+    }
+
+    /// Presets that can be built and whose medium pizza passes `filter`.
+    pub fn matching_presets(&self, filter: &NutritionFilter) -> Vec<&Preset> {
+        self.presets
+            .iter()
+            .filter(|p| {
+                self.custom_spec(&p.id, Size::default(), None, &[])
+                    .is_ok_and(|spec| filter.matches(spec.nutrition().as_ref()))
+            })
+            .collect()
+    }
+
+    /// Build a decorator chain: base, then toppings in order, then crust.
+    pub fn build_pizza(
+        &self,
+        base: &str,
+        size: Size,
+        crust: Option<&str>,
+        toppings: &[&str],
+    ) -> Result<Box<dyn Pizza>, OrderError> {
+        Ok(self.pizza_spec(base, size, crust, toppings)?.build())
+    }
+
+    /// Build a medium pizza from a preset id or a plain base id.
+    pub fn build(&self, id: &str) -> Result<Box<dyn Pizza>, OrderError> {
+        self.build_custom(id, Size::default(), None, &[])
+    }
+
+    /// Build a preset or base with extra toppings and an optional crust.
+    ///
+    /// Extra toppings go on after the preset's own; `crust` replaces the
+    /// preset's crust.
+    pub fn build_custom(
+        &self,
+        id: &str,
+        size: Size,
+        crust: Option<&str>,
+        extra_toppings: &[&str],
+    ) -> Result<Box<dyn Pizza>, OrderError> {
+        Ok(self.custom_spec(id, size, crust, extra_toppings)?.build())
+    }
+
+    /// Spec for a base with the given crust and toppings, priced from the
+    /// catalog for `size`. Fails if any id is unknown or not on sale, or if
+    /// the result breaks the menu's rules.
+    pub fn pizza_spec(
+        &self,
+        base: &str,
+        size: Size,
+        crust: Option<&str>,
+        toppings: &[&str],
+    ) -> Result<PizzaSpec, OrderError> {
+        let base = on_sale(self.base(base), base)?;
+        let mut spec = PizzaSpec::new(&base.name, base.price(size))
+            .with_size(size)
+            .with_category(base.category);
+        spec.base = base.part(size, Placement::Whole);
+
+        for id in toppings {
+            spec.toppings.push(self.topping_part(id, size, Placement::Whole)?);
+        }
+
+        if let Some(id) = crust {
+            let crust = on_sale(self.crust(id), id)?;
+            spec = spec.with_crust_part(crust.part(size, Placement::Whole));
+        }
+
+        self.validate(&spec)?;
+        Ok(spec)
+    }
+
+    /// A topping priced for `size`, at half rate when it covers one half.
+    ///
+    /// Push it onto [`PizzaSpec::toppings`] to build half-and-half pizzas,
+    /// then run the result through [`validate`](Self::validate).
+    pub fn topping_part(&self, id: &str, size: Size, placement: Placement) -> Result<PizzaPart, OrderError> {
+        Ok(on_sale(self.topping(id), id)?.part(size, placement))
+    }
+
+    /// Spec counterpart of [`build_custom`](Self::build_custom).
+    pub fn custom_spec(
+        &self,
+        id: &str,
+        size: Size,
+        crust: Option<&str>,
+        extra_toppings: &[&str],
+    ) -> Result<PizzaSpec, OrderError> {
+        match self.preset(id) {
+            Some(preset) => {
+                let toppings: Vec<&str> = preset
+                    .toppings
+                    .iter()
+                    .map(String::as_str)
+                    .chain(extra_toppings.iter().copied())
+                    .collect();
+                self.pizza_spec(&preset.base, size, crust.or(preset.crust.as_deref()), &toppings)
+            }
+            None => self.pizza_spec(id, size, crust, extra_toppings),
+        }
+    }
+}
+
+impl Default for Menu {
+    /// The built-in catalog from `menu.toml`.
+    fn default() -> Self {
+        Self::from_toml_str(DEFAULT_MENU).expect("built-in menu.toml is valid")
+    }
+}
+
+fn on_sale<'a>(item: Option<&'a MenuItem>, id: &str) -> Result<&'a MenuItem, OrderError> {
+    match item {
+        Some(item) if item.available => Ok(item),
+        Some(_) => Err(OrderError::ItemUnavailable(id.to_string())),
+        None => Err(OrderError::UnknownItem(id.to_string())),
+    }
+}
+
+fn parse_price(id: &str, raw: RawPrice, currency: Currency) -> Result<Money, MenuError> {
+    let text = match raw {
+        RawPrice::Text(t) => t,
+        RawPrice::Whole(n) => n.to_string(),
+        RawPrice::Float(f) => {
+            return Err(MenuError::InvalidPrice {
+                item: id.to_string(),
+                reason: format!("write decimal prices as text, e.g. \"{}\", not {}", f, f),
+            });
+        }
+    };
+    let price = Money::parse(&text, currency).map_err(|e| MenuError::InvalidPrice {
+        item: id.to_string(),
+        reason: e.to_string(),
+    })?;
+    if price.is_negative() {
+        return Err(MenuError::InvalidPrice {
+            item: id.to_string(),
+            reason: "negative price".to_string(),
+        });
+    }
+    Ok(price)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_menu_matches_built_in_pizzas() {
+        let menu = Menu::default();
+        let pizza = menu.build("cheese_burst_farmhouse").unwrap();
+
+        assert_eq!(pizza.description(), "Medium Farmhouse, CheeseBurst Crust");
+        assert_eq!(pizza.cost().unwrap(), Money::inr(200));
+        assert_eq!(menu.build("margherita").unwrap().cost().unwrap(), Money::inr(120));
+    }
+
+    #[test]
+    fn builds_toppings_before_crust() {
+        let menu = Menu::default();
+        let pizza = menu.build_pizza("margherita", Size::Medium, Some("thin"), &["cheese", "olives"]).unwrap();
+
+        assert_eq!(pizza.description(), "Medium Margherita + Cheese + Olives, Thin Crust");
+        assert_eq!(pizza.cost().unwrap(), Money::inr(120 + 10 + 15 + 20));
+        assert_eq!(pizza.topping_charges().len(), 2);
+    }
+
+    #[test]
+    fn custom_build_extends_presets() {
+        let menu = Menu::default();
+        let pizza = menu.build_custom("thin_margherita", Size::Medium, None, &["olives"]).unwrap();
+        assert_eq!(pizza.description(), "Medium Margherita + Olives, Thin Crust");
+
+        let swapped = menu.build_custom("thin_margherita", Size::Medium, Some("cheese_burst"), &[]).unwrap();
+        assert_eq!(swapped.cost().unwrap(), Money::inr(170));
+
+        assert!(matches!(
+            menu.build_custom("farmhouse", Size::Medium, Some("stuffed"), &[]),
+            Err(OrderError::UnknownItem(_))
+        ));
+    }
+
+    #[test]
+    fn every_part_is_priced_for_the_size() {
+        let menu = Menu::from_toml_str(
+            r#"
+            [[bases]]
+            id = "margherita"
+            name = "Margherita"
+            price = "120"
+            sizes = { small = "99", F = "250" }
+            [[crusts]]
+            id = "thin"
+            name = "Thin Crust"
+            price = "20"
+            [[toppings]]
+            id = "cheese"
+            name = "Cheese"
+            price = "10"
+        "#,
+        )
+        .unwrap();
+
+        let small = menu.custom_spec("margherita", Size::Small, Some("thin"), &["cheese"]).unwrap();
+        assert_eq!(small.cost().unwrap(), Money::from_minor(12_150, Currency::Inr));
+        let family = menu.build_pizza("margherita", Size::Family, Some("thin"), &["cheese"]).unwrap();
+        assert_eq!(family.description(), "Family Margherita + Cheese, Thin Crust");
+        assert_eq!(family.cost().unwrap(), Money::inr(250 + 20 + 40));
+
+        let typo = r#"
+            [[bases]]
+            id = "x"
+            name = "X"
+            price = "1"
+            sizes = { jumbo = "2" }
+        "#;
+        assert!(matches!(Menu::from_toml_str(typo), Err(MenuError::InvalidPrice { .. })));
+    }
+
+    #[test]
+    fn rules_section_is_enforced_on_build() {
+        let menu = Menu::from_toml_str(
+            r#"
+            [[bases]]
+            id = "margherita"
+            name = "Margherita"
+            price = "120"
+            [[toppings]]
+            id = "cheese"
+            name = "Cheese"
+            price = "10"
+            [[toppings]]
+            id = "pineapple"
+            name = "Pineapple"
+            price = "20"
+            [rules]
+            max_per_topping = 1
+            limits = { cheese = 3 }
+            incompatible = [["margherita", "pineapple"]]
+        "#,
+        )
+        .unwrap();
+
+        assert!(menu.build_pizza("margherita", Size::Medium, None, &["cheese"; 3]).is_ok());
+        assert!(matches!(
+            menu.build_pizza("margherita", Size::Medium, None, &["cheese"; 4]),
+            Err(OrderError::ToppingLimit { max: 3, .. })
+        ));
+        assert!(matches!(
+            menu.build_pizza("margherita", Size::Medium, None, &["pineapple"]),
+            Err(OrderError::IncompatibleItems { .. })
+        ));
+
+        let unknown = "[rules]\nincompatible = [[\"a\", \"b\"]]";
+        assert!(matches!(Menu::from_toml_str(unknown), Err(MenuError::UnknownRuleItem(_))));
+    }
+
+    #[test]
+    fn built_pizzas_carry_nutrition_and_menu_filters_by_it() {
+        use crate::pizza::{Allergen, Cheese, Diet, Margherita, Placement as P, ThinCrust};
+
+        let menu = Menu::default();
+        let spec = menu.custom_spec("thin_margherita", Size::Medium, None, &["cheese"]).unwrap();
+        let chain = ThinCrust::new(Cheese::new(Box::new(Margherita::new())));
+        assert_eq!(spec.nutrition(), chain.nutrition());
+        assert_eq!(spec.build().nutrition(), chain.nutrition());
+
+        let half = menu.topping_part("olives", Size::Large, P::Left).unwrap();
+        assert_eq!(half.nutrition.unwrap().calories, 30);
+
+        let dairy_free = NutritionFilter::new().without(Allergen::Dairy);
+        let ids: Vec<&str> = menu.matching(MenuSection::Topping, &dairy_free).map(|i| i.id.as_str()).collect();
+        assert_eq!(ids, vec!["olives", "jalapenos"]);
+        assert!(menu.matching(MenuSection::Base, &dairy_free).next().is_none());
+
+        let jain = NutritionFilter::new().suitable_for(Diet::Jain);
+        let presets: Vec<&str> = menu.matching_presets(&jain).iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(presets, vec!["thin_margherita"]);
+    }
+
+    #[test]
+    fn json_catalog_with_unavailable_item() {
+        let menu = Menu::from_json_str(
+            r#"{
+                "bases": [
+                    { "id": "veggie", "name": "Veggie", "price": 140 },
+                    { "id": "bbq", "name": "BBQ", "price": "199.99", "available": false }
+                ],
+                "toppings": [{ "id": "corn", "name": "Corn", "price": "8.50" }]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(menu.available(MenuSection::Base).count(), 1);
+        assert_eq!(menu.build_pizza("veggie", Size::Medium, None, &["corn"]).unwrap().cost().unwrap().minor_units(), 14_850);
+        assert!(matches!(menu.build("bbq"), Err(OrderError::ItemUnavailable(_))));
+        assert!(matches!(menu.build("hawaiian"), Err(OrderError::UnknownItem(_))));
+    }
+
+    #[test]
+    fn invalid_catalogs_are_rejected() {
+        let dup = r#"
+            [[bases]]
+            id = "x"
+            name = "X"
+            price = "1"
+            [[toppings]]
+            id = "x"
+            name = "X again"
+            price = "1"
+        "#;
+        assert!(matches!(Menu::from_toml_str(dup), Err(MenuError::DuplicateItem(_))));
+
+        let bad_price = r#"
+            [[bases]]
+            id = "x"
+            name = "X"
+            price = "1.234"
+        "#;
+        assert!(matches!(Menu::from_toml_str(bad_price), Err(MenuError::InvalidPrice { .. })));
+
+        let float_price = r#"{ "bases": [{ "id": "x", "name": "X", "price": 8.5 }] }"#;
+        assert!(matches!(Menu::from_json_str(float_price), Err(MenuError::InvalidPrice { .. })));
+
+        // Scaling to the family size would overflow
+        let huge_price = r#"
+            [[bases]]
+            id = "x"
+            name = "X"
+            price = "90000000000000000"
+        "#;
+        assert!(matches!(Menu::from_toml_str(huge_price), Err(MenuError::InvalidPrice { .. })));
+
+        let bad_ref = r#"
+            [[bases]]
+            id = "x"
+            name = "X"
+            price = "1"
+            [[presets]]
+            id = "combo"
+            base = "x"
+            crust = "stuffed"
+        "#;
+        assert!(matches!(Menu::from_toml_str(bad_ref), Err(MenuError::UnknownReference { .. })));
+    }
+
+    #[test]
+    fn load_picks_format_from_extension() {
+        let file = "test_menu_load.json";
+        fs::write(file, r#"{ "bases": [{ "id": "a", "name": "A", "price": "1" }] }"#).unwrap();
+        assert_eq!(Menu::load(file).unwrap().bases.len(), 1);
+        let _ = fs::remove_file(file);
+
+        assert!(matches!(Menu::load("menu.yaml"), Err(MenuError::Io(_))));
+    }
+}
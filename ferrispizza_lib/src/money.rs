@@ -0,0 +1,377 @@
+//! Money module — exact monetary amounts for pricing and billing.
+//!
+//! [`Money`] stores an integer number of minor units (paise, cents) together
+//! with its [`Currency`], so sums never drift the way `f32` prices do.
+//! Arithmetic between different currencies, or past what `i64` holds, is
+//! an error rather than a panic, so `Money` has no `+` or `-` operators.
+//!
+//! # Example
+//! ```
+//! use ferrispizza_lib::money::{Money, Rounding};
+//!
+//! let price = Money::inr(120);
+//! let topping = Money::from_minor(1250, price.currency());
+//!
+//! let total = price.checked_add(topping).unwrap();
+//! assert_eq!(total.to_string(), "₹132.50");
+//!
+//! // 5% of ₹132.50 = ₹6.625 → ₹6.63 with half-up rounding
+//! let tax = total.apply_rate(5, 100, Rounding::HalfUp).unwrap();
+//! assert_eq!(tax, Money::from_minor(663, total.currency()));
+//! ```
+
+use std::cmp::Ordering;
+use std::fmt::{self, Display};
+use std::iter::Sum;
+
+use serde::{Deserialize, Serialize};
+
+use crate::errors::MoneyError;
+
+/// ISO 4217 currencies accepted by the store.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Currency {
+    /// Indian Rupee — the store's home currency.
+    #[default]
+    Inr,
+    /// US Dollar
+    Usd,
+    /// Euro
+    Eur,
+}
+
+impl Currency {
+    /// Three-letter ISO 4217 code.
+    pub fn code(self) -> &'static str {
+        match self {
+            Currency::Inr => "INR",
+            Currency::Usd => "USD",
+            Currency::Eur => "EUR",
+        }
+    }
+
+    /// Symbol used when printing amounts.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Currency::Inr => "₹",
+            Currency::Usd => "$",
+            Currency::Eur => "€",
+        }
+    }
+
+    /// Number of decimal places in one major unit.
+    pub fn minor_digits(self) -> u32 {
+        2
+    }
+
+    /// Minor units per major unit (e.g. 100 paise per rupee).
+    pub fn minor_per_major(self) -> i64 {
+        10_i64.pow(self.minor_digits())
+    }
+
+    /// Look up a currency by its ISO code (case-insensitive).
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code.to_ascii_uppercase().as_str() {
+            "INR" => Some(Currency::Inr),
+            "USD" => Some(Currency::Usd),
+            "EUR" => Some(Currency::Eur),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+/// How to round when a calculation lands between two minor units.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    /// Ties away from zero (₹0.005 → ₹0.01). Standard for invoices.
+    HalfUp,
+    /// Ties to the nearest even minor unit (banker's rounding).
+    HalfEven,
+    /// Always towards zero.
+    Down,
+    /// Always away from zero.
+    Up,
+}
+
+impl Rounding {
+    /// Divide `num` by positive `den`, rounding the quotient with `self`.
+    fn divide(self, num: i128, den: i128) -> i128 {
+        let quot = num / den;
+        let rem = num % den;
+        if rem == 0 {
+            return quot;
+        }
+
+        let away = if num < 0 { quot - 1 } else { quot + 1 };
+        let twice = rem.abs() * 2;
+        match self {
+            Rounding::Down => quot,
+            Rounding::Up => away,
+            Rounding::HalfUp => {
+                if twice >= den { away } else { quot }
+            }
+            Rounding::HalfEven => match twice.cmp(&den) {
+                Ordering::Greater => away,
+                Ordering::Less => quot,
+                Ordering::Equal => {
+                    if quot % 2 == 0 { quot } else { away }
+                }
+            },
+        }
+    }
+}
+
+/// An exact amount of money in a single currency.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Money {
+    minor: i64,
+    currency: Currency,
+}
+
+impl Money {
+    /// Amount given in minor units (paise, cents).
+    pub const fn from_minor(minor: i64, currency: Currency) -> Self {
+        Self { minor, currency }
+    }
+
+    /// Whole major units (rupees, dollars). Takes an `i32` so the amount
+    /// always fits; larger ones go through [`Money::from_minor`] or
+    /// [`Money::parse`].
+    pub fn from_major(major: i32, currency: Currency) -> Self {
+        Self::from_minor(i64::from(major) * currency.minor_per_major(), currency)
+    }
+
+    /// Whole rupees — shorthand for the store's home currency.
+    pub fn inr(rupees: i32) -> Self {
+        Self::from_major(rupees, Currency::Inr)
+    }
+
+    /// Zero in the given currency.
+    pub const fn zero(currency: Currency) -> Self {
+        Self::from_minor(0, currency)
+    }
+
+    /// Parse a decimal string such as `"120"` or `"12.50"`.
+    ///
+    /// Rejects amounts with more decimal places than the currency allows.
+    pub fn parse(amount: &str, currency: Currency) -> Result<Self, MoneyError> {
+        let invalid = || MoneyError::InvalidAmount(amount.to_string());
+        let trimmed = amount.trim();
+        let (negative, digits) = match trimmed.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, trimmed),
+        };
+        let (major, frac) = digits.split_once('.').unwrap_or((digits, ""));
+        let places = currency.minor_digits() as usize;
+
+        let all_digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+        if major.is_empty() || frac.len() > places || !all_digits(major) || !all_digits(frac) {
+            return Err(invalid());
+        }
+
+        let major: i64 = major.parse().map_err(|_| invalid())?;
+        let frac: i64 = format!("{:0<width$}", frac, width = places)
+            .parse()
+            .unwrap_or(0);
+        let minor = major
+            .checked_mul(currency.minor_per_major())
+            .and_then(|m| m.checked_add(frac))
+            .ok_or(MoneyError::Overflow)?;
+
+        Ok(Self::from_minor(if negative { -minor } else { minor }, currency))
+    }
+
+    /// Amount in minor units.
+    pub fn minor_units(&self) -> i64 {
+        self.minor
+    }
+
+    pub fn currency(&self) -> Currency {
+        self.currency
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.minor == 0
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.minor < 0
+    }
+
+    fn same_currency(&self, other: &Money) -> Result<(), MoneyError> {
+        if self.currency == other.currency {
+            Ok(())
+        } else {
+            Err(MoneyError::CurrencyMismatch {
+                expected: self.currency,
+                found: other.currency,
+            })
+        }
+    }
+
+    pub fn checked_add(self, other: Money) -> Result<Money, MoneyError> {
+        self.same_currency(&other)?;
+        let minor = self.minor.checked_add(other.minor).ok_or(MoneyError::Overflow)?;
+        Ok(Self::from_minor(minor, self.currency))
+    }
+
+    pub fn checked_sub(self, other: Money) -> Result<Money, MoneyError> {
+        self.same_currency(&other)?;
+        let minor = self.minor.checked_sub(other.minor).ok_or(MoneyError::Overflow)?;
+        Ok(Self::from_minor(minor, self.currency))
+    }
+
+    /// Multiply by a whole quantity (e.g. number of pizzas).
+    pub fn checked_mul(self, quantity: i64) -> Result<Money, MoneyError> {
+        let minor = self.minor.checked_mul(quantity).ok_or(MoneyError::Overflow)?;
+        Ok(Self::from_minor(minor, self.currency))
+    }
+
+    /// Multiply by the fraction `numerator / denominator`, rounding the
+    /// result to a whole minor unit.
+    ///
+    /// `apply_rate(5, 100, ..)` is 5%; `apply_rate(1, 2, ..)` is half.
+    pub fn apply_rate(
+        self,
+        numerator: i64,
+        denominator: i64,
+        rounding: Rounding,
+    ) -> Result<Money, MoneyError> {
+        if denominator <= 0 {
+            return Err(MoneyError::InvalidAmount(format!(
+                "rate denominator {}",
+                denominator
+            )));
+        }
+
+        let scaled = self.minor as i128 * numerator as i128;
+        let minor = rounding.divide(scaled, denominator as i128);
+        let minor = i64::try_from(minor).map_err(|_| MoneyError::Overflow)?;
+        Ok(Self::from_minor(minor, self.currency))
+    }
+
+    /// Percentage given in basis points (1% = 100 bps).
+    pub fn percent_bps(self, bps: u32, rounding: Rounding) -> Result<Money, MoneyError> {
+        self.apply_rate(bps as i64, 10_000, rounding)
+    }
+
+    /// Round to a whole major unit (e.g. cash tender without coins).
+    pub fn round_to_major(self, rounding: Rounding) -> Result<Money, MoneyError> {
+        let step = self.currency.minor_per_major() as i128;
+        let units = rounding.divide(self.minor as i128, step);
+        let minor = i64::try_from(units * step).map_err(|_| MoneyError::Overflow)?;
+        Ok(Self::from_minor(minor, self.currency))
+    }
+
+    /// Sum amounts with overflow and currency checks.
+    ///
+    /// Empty input yields zero in `currency`.
+    pub fn checked_sum<I>(iter: I, currency: Currency) -> Result<Money, MoneyError>
+    where
+        I: IntoIterator<Item = Money>,
+    {
+        iter.into_iter()
+            .try_fold(Money::zero(currency), Money::checked_add)
+    }
+}
+
+/// Sums in the currency of the first item (zero INR for an empty iterator),
+/// failing on a currency mismatch or overflow.
+impl Sum<Money> for Result<Money, MoneyError> {
+    fn sum<I: Iterator<Item = Money>>(mut iter: I) -> Self {
+        match iter.next() {
+            Some(first) => iter.try_fold(first, Money::checked_add),
+            None => Ok(Money::default()),
+        }
+    }
+}
+
+/// Amounts in different currencies are unordered.
+impl PartialOrd for Money {
+    fn partial_cmp(&self, other: &Money) -> Option<Ordering> {
+        if self.currency == other.currency {
+            Some(self.minor.cmp(&other.minor))
+        } else {
+            None
+        }
+    }
+}
+
+/// Prints as e.g. `₹120.00` or `-$3.05`.
+impl Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let per_major = self.currency.minor_per_major().unsigned_abs();
+        let abs = self.minor.unsigned_abs();
+        let sign = if self.minor < 0 { "-" } else { "" };
+        write!(
+            f,
+            "{}{}{}.{:0width$}",
+            sign,
+            self.currency.symbol(),
+            abs / per_major,
+            abs % per_major,
+            width = self.currency.minor_digits() as usize
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn many_small_amounts_do_not_drift() {
+        let ten_paise = Money::from_minor(10, Currency::Inr);
+        let total: Result<Money, _> = std::iter::repeat_n(ten_paise, 1_000_000).sum();
+        assert_eq!(total.unwrap(), Money::inr(100_000));
+    }
+
+    #[test]
+    fn mixing_currencies_is_rejected() {
+        let err = Money::inr(1).checked_add(Money::from_major(1, Currency::Usd));
+        assert!(matches!(err, Err(MoneyError::CurrencyMismatch { .. })));
+        assert_eq!(Money::inr(1).partial_cmp(&Money::from_major(1, Currency::Eur)), None);
+    }
+
+    #[test]
+    fn overflow_is_reported() {
+        let max = Money::from_minor(i64::MAX, Currency::Inr);
+        assert!(matches!(max.checked_add(Money::inr(1)), Err(MoneyError::Overflow)));
+        assert!(matches!(max.checked_mul(2), Err(MoneyError::Overflow)));
+        assert!(matches!(max.round_to_major(Rounding::Up), Err(MoneyError::Overflow)));
+        let sum: Result<Money, _> = [max, Money::inr(1)].into_iter().sum();
+        assert!(matches!(sum, Err(MoneyError::Overflow)));
+    }
+
+    #[test]
+    fn rounding_rules() {
+        // ₹0.25 * 1/2 = 12.5 paise
+        let m = Money::from_minor(25, Currency::Inr);
+        assert_eq!(m.apply_rate(1, 2, Rounding::HalfUp).unwrap().minor_units(), 13);
+        assert_eq!(m.apply_rate(1, 2, Rounding::HalfEven).unwrap().minor_units(), 12);
+        assert_eq!(m.apply_rate(1, 2, Rounding::Down).unwrap().minor_units(), 12);
+        assert_eq!(m.apply_rate(1, 3, Rounding::Up).unwrap().minor_units(), 9);
+
+        let neg = Money::from_minor(-25, Currency::Inr);
+        assert_eq!(neg.apply_rate(1, 2, Rounding::HalfUp).unwrap().minor_units(), -13);
+
+        assert_eq!(Money::from_minor(12_950, Currency::Inr).round_to_major(Rounding::HalfUp).unwrap(), Money::inr(130));
+    }
+
+    #[test]
+    fn parse_and_display_round_trip() {
+        let m = Money::parse("12.5", Currency::Inr).unwrap();
+        assert_eq!(m.minor_units(), 1250);
+        assert_eq!(m.to_string(), "₹12.50");
+        assert_eq!(Money::parse("-0.05", Currency::Usd).unwrap().to_string(), "-$0.05");
+        assert!(Money::parse("1.234", Currency::Inr).is_err());
+        assert!(Money::parse("abc", Currency::Inr).is_err());
+    }
+}
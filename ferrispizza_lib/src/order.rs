@@ -1,6 +1,11 @@
+#[allow(non_snake_case)]
 mod Order;
+mod priority;
+mod status;
 
 pub use Order::order;
-pub use Order::OrderStatus;
+pub use Order::OrderId;
+pub use priority::OrderPriority;
+pub use status::{OrderStatus, StatusChange};
 
 
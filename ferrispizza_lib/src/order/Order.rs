@@ -3,54 +3,210 @@
 
 use std::fmt::{self, Display};
 use std::time::SystemTime;
+use crate::billing::{CouponClaim, PaymentReceipt, RefundReceipt};
+use crate::customer::{Customer, CustomerId};
+use crate::errors::{BillingError, MoneyError, OrderError};
+use crate::money::Money;
 use crate::pizza::Pizza;
-use crate::utils::IdGenerator;
+use crate::utils::{IdGenerator, IdSource};
+use super::priority::OrderPriority;
+use super::status::{OrderStatus, StatusChange};
+
+/// Process-wide generator used by [`order::new`].
+///
+/// Shared by every thread so orders created without an explicit
+/// [`IdSource`] still receive distinct IDs.
+static ORDER_IDS: IdGenerator = IdGenerator::new();
 
 /// Unique Order Id wrapper
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct OrderId(pub u64);
 
-/// Order status lifecycle
-#[derive(Clone, Debug, PartialEq)]
-pub enum OrderStatus {
-    Created,
-    Paid,
-    Completed,
-}
-
 /// Represents a customer's order
 #[derive(Clone)]
+#[allow(non_camel_case_types)]
 pub struct order {
     pub id: OrderId,
     pub pizzas: Vec<Box<dyn Pizza>>,
     pub status: OrderStatus,
     pub timestamp: SystemTime,
+    /// Receipt of the payment that settled this order, if any.
+    pub receipt: Option<PaymentReceipt>,
+    /// Every status transition applied to this order, oldest first.
+    pub history: Vec<StatusChange>,
+    /// Coupon the customer wants applied at billing time.
+    pub coupon: Option<CouponClaim>,
+    /// Loyalty points the customer wants to pay with.
+    pub redeem_points: u32,
+    /// Pizzas the kitchen has finished cooking.
+    pub pizzas_ready: usize,
+    /// Scheduling class in the kitchen queue.
+    pub priority: OrderPriority,
+    /// Promised delivery time, if the order is for delivery.
+    pub deliver_by: Option<SystemTime>,
+    /// Registered customer who placed the order; `None` for walk-ins.
+    pub customer: Option<CustomerId>,
 }
 
 impl order {
-    /// Create a new order with pizzas
+    /// Create a new order with pizzas, numbered from the process-wide
+    /// order ID generator.
     pub fn new(pizzas: Vec<Box<dyn Pizza>>) -> Self {
+        Self::with_id(pizzas, OrderId(ORDER_IDS.next_number()))
+    }
+
+    /// Create a new order whose ID is drawn from `ids`.
+    ///
+    /// Use this when the caller owns the ID sequence (e.g. the CLI app or a
+    /// persisted generator that must survive restarts). Fails if `ids`
+    /// cannot issue an ID.
+    pub fn with_id_source(pizzas: Vec<Box<dyn Pizza>>, ids: &dyn IdSource) -> Result<Self, OrderError> {
+        Ok(Self::with_id(pizzas, OrderId(ids.next_value()?)))
+    }
+
+    fn with_id(pizzas: Vec<Box<dyn Pizza>>, id: OrderId) -> Self {
         Self {
-            id: OrderId(IdGenerator::new().next_id().parse().unwrap()),
+            id,
             pizzas,
             status: OrderStatus::Created,
             timestamp: SystemTime::now(),
+            receipt: None,
+            history: Vec::new(),
+            coupon: None,
+            redeem_points: 0,
+            pizzas_ready: 0,
+            priority: OrderPriority::Normal,
+            deliver_by: None,
+            customer: None,
         }
     }
 
-    /// Calculate total cost by summing pizza prices
-    pub fn total_cost(&self) -> f32 {
-        self.pizzas.iter().map(|p| p.cost()).sum()
+    /// Place the order for `customer`, checking their name and phone.
+    pub fn for_customer(mut self, customer: &Customer) -> Result<Self, OrderError> {
+        customer.validate()?;
+        self.customer = Some(customer.id);
+        Ok(self)
+    }
+
+    /// Schedule the order in `priority` class.
+    pub fn with_priority(mut self, priority: OrderPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Promise delivery by `deadline`; the kitchen boosts it as the time nears.
+    pub fn with_deadline(mut self, deadline: SystemTime) -> Self {
+        self.deliver_by = Some(deadline);
+        self
+    }
+
+    /// Calculate total cost by summing pizza prices; fails if they are in
+    /// different currencies or the sum overflows
+    pub fn total_cost(&self) -> Result<Money, MoneyError> {
+        self.pizzas.iter().map(|p| p.cost()).collect::<Result<Vec<_>, _>>()?.into_iter().sum()
+    }
+
+    /// Ask for `claim` to be applied when the order is billed.
+    ///
+    /// Eligibility is checked by the billing engine, not here.
+    pub fn apply_coupon(&mut self, claim: CouponClaim) {
+        self.coupon = Some(claim);
+    }
+
+    /// Ask to pay with up to `points` loyalty points when the order is billed.
+    ///
+    /// The balance is checked by the billing engine, not here.
+    pub fn pay_with_points(&mut self, points: u32) {
+        self.redeem_points = points;
+    }
+
+    /// Move the order to `to`, recording the change in [`order::history`].
+    ///
+    /// Returns [`OrderError::InvalidTransition`] if the lifecycle does not
+    /// allow it; the order is left untouched in that case.
+    pub fn transition(&mut self, to: OrderStatus) -> Result<(), OrderError> {
+        let from = self.status;
+        if !from.can_transition_to(to) {
+            return Err(OrderError::InvalidTransition { from, to });
+        }
+
+        self.status = to;
+        self.history.push(StatusChange {
+            from,
+            to,
+            at: SystemTime::now(),
+        });
+        Ok(())
     }
 
     /// Mark order as paid
-    pub fn mark_paid(&mut self) {
-        self.status = OrderStatus::Paid;
+    pub fn mark_paid(&mut self) -> Result<(), OrderError> {
+        self.transition(OrderStatus::Paid)
+    }
+
+    /// Mark order as paid and keep the receipt alongside it
+    pub fn record_payment(&mut self, receipt: PaymentReceipt) -> Result<(), OrderError> {
+        self.mark_paid()?;
+        self.receipt = Some(receipt);
+        Ok(())
+    }
+
+    /// Kitchen started cooking the order
+    pub fn mark_preparing(&mut self) -> Result<(), OrderError> {
+        self.transition(OrderStatus::Preparing)
+    }
+
+    /// Order is cooked and waiting for pickup
+    pub fn mark_ready(&mut self) -> Result<(), OrderError> {
+        self.transition(OrderStatus::Ready)
     }
 
     /// Mark order complete
-    pub fn mark_completed(&mut self) {
-        self.status = OrderStatus::Completed;
+    pub fn mark_completed(&mut self) -> Result<(), OrderError> {
+        self.transition(OrderStatus::Completed)
+    }
+
+    /// Mark order cancelled
+    pub fn mark_cancelled(&mut self) -> Result<(), OrderError> {
+        self.transition(OrderStatus::Cancelled)
+    }
+
+    /// Mark order refunded
+    pub fn mark_refunded(&mut self) -> Result<(), OrderError> {
+        self.transition(OrderStatus::Refunded)
+    }
+
+    /// Status a refund of `refund` moves the order to: `Refunded` once
+    /// nothing is left to refund, `PartiallyRefunded` before that.
+    pub fn status_after(refund: &RefundReceipt) -> OrderStatus {
+        if refund.is_full() {
+            OrderStatus::Refunded
+        } else {
+            OrderStatus::PartiallyRefunded
+        }
+    }
+
+    /// Keep `refund` with the payment it returns money from and move the
+    /// order to [`status_after`](Self::status_after) it. Returns the new
+    /// status.
+    ///
+    /// What is left to refund is worked out again from this order's
+    /// payment, so a refund quoted from an older copy of the order fails
+    /// with [`BillingError::OverRefund`] rather than return more than was
+    /// paid.
+    pub fn record_refund(&mut self, mut refund: RefundReceipt) -> Result<OrderStatus, BillingError> {
+        let receipt = self.receipt.as_ref().ok_or(BillingError::NotPaid(self.id.0))?;
+        let refundable = receipt.refundable()?;
+        if refund.amount > refundable {
+            return Err(BillingError::OverRefund { requested: refund.amount, refundable });
+        }
+        refund.remaining = refundable.checked_sub(refund.amount)?;
+        let status = Self::status_after(&refund);
+        self.transition(status)?;
+        if let Some(receipt) = &mut self.receipt {
+            receipt.refunds.push(refund);
+        }
+        Ok(status)
     }
 }
 
@@ -60,16 +216,16 @@ impl Display for order {
         let pizzas_str = self
             .pizzas
             .iter()
-            .map(|p| format!("{} ({:.2})", p.description(), p.cost()))
+            .map(|p| format!("{} ({})", p.description(), p.cost().map_or_else(|e| e.to_string(), |c| c.to_string())))
             .collect::<Vec<_>>()
             .join(", ");
 
         write!(
             f,
-            "Order #{:?}\nPizzas: {}\nTotal: {:.2}\nStatus: {:?}",
+            "Order #{:?}\nPizzas: {}\nTotal: {}\nStatus: {:?}",
             self.id.0,
             pizzas_str,
-            self.total_cost(),
+            self.total_cost().map_or_else(|e| e.to_string(), |total| total.to_string()),
             self.status
         )
     }
@@ -96,7 +252,7 @@ mod tests {
         ];
 
         let order = order::new(pizzas);
-        assert_eq!(order.total_cost(), 120.0 + 130.0);
+        assert_eq!(order.total_cost().unwrap(), Money::inr(120 + 130));
     }
 
     #[test]
@@ -104,11 +260,47 @@ mod tests {
         let pizzas = vec![Box::new(Margherita::new()) as Box<dyn Pizza>];
         let mut order = order::new(pizzas);
 
-        order.mark_paid();
+        order.mark_paid().unwrap();
         assert_eq!(order.status, OrderStatus::Paid);
 
-        order.mark_completed();
+        order.mark_preparing().unwrap();
+        order.mark_ready().unwrap();
+        order.mark_completed().unwrap();
         assert_eq!(order.status, OrderStatus::Completed);
+        assert_eq!(order.history.len(), 4);
+        assert_eq!(order.history[0].from, OrderStatus::Created);
+        assert_eq!(order.history[3].to, OrderStatus::Completed);
+    }
+
+    #[test]
+    fn illegal_transition_is_rejected() {
+        let pizzas = vec![Box::new(Margherita::new()) as Box<dyn Pizza>];
+        let mut order = order::new(pizzas);
+
+        order.mark_paid().unwrap();
+        let err = order.mark_paid().unwrap_err();
+        assert!(matches!(
+            err,
+            OrderError::InvalidTransition { from: OrderStatus::Paid, to: OrderStatus::Paid }
+        ));
+        assert_eq!(order.status, OrderStatus::Paid);
+        assert_eq!(order.history.len(), 1);
+    }
+
+    #[test]
+    fn new_orders_get_distinct_ids() {
+        let a = order::new(vec![Box::new(Margherita::new()) as Box<dyn Pizza>]);
+        let b = order::new(vec![Box::new(Margherita::new()) as Box<dyn Pizza>]);
+        assert_ne!(a.id, b.id);
+    }
+
+    #[test]
+    fn with_id_source_uses_given_generator() {
+        let ids = IdGenerator::starting_at(100);
+        let a = order::with_id_source(vec![Box::new(Margherita::new()) as Box<dyn Pizza>], &ids).unwrap();
+        let b = order::with_id_source(vec![Box::new(Margherita::new()) as Box<dyn Pizza>], &ids).unwrap();
+        assert_eq!(a.id, OrderId(100));
+        assert_eq!(b.id, OrderId(101));
     }
 }
 
@@ -0,0 +1,17 @@
+//! Order priority classes used by the kitchen scheduler.
+
+use serde::{Deserialize, Serialize};
+
+/// How urgently an order should be cooked.
+///
+/// Higher classes are scheduled first; see
+/// [`PriorityOrderQueue`](crate::concurrency::PriorityOrderQueue).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum OrderPriority {
+    #[default]
+    Normal,
+    /// Customer paid for faster service.
+    Express,
+    /// Regulars and staff orders.
+    Vip,
+}
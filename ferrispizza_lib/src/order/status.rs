@@ -0,0 +1,103 @@
+//! Order lifecycle state machine.
+//!
+//! ```text
+//! Created ──► Paid ──► Preparing ──► Ready ──► Completed ──► PartiallyRefunded ─┐
+//!    │          │                                  │             │     ▲        │
+//!    ▼          ▼                                  │             │     └────────┘
+//! Cancelled  Refunded ◄────────────────────────────┴─────────────┘
+//! ```
+//!
+//! A completed order can be refunded outright or in parts; every part
+//! refund leaves it `PartiallyRefunded` until the last one.
+//!
+//! Only the edges above are legal; anything else is rejected with
+//! [`OrderError::InvalidTransition`](crate::errors::OrderError::InvalidTransition).
+
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+/// Order status lifecycle
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum OrderStatus {
+    Created,
+    Paid,
+    Preparing,
+    Ready,
+    Completed,
+    Cancelled,
+    Refunded,
+    PartiallyRefunded,
+}
+
+impl OrderStatus {
+    /// Whether the lifecycle allows moving from `self` to `to`.
+    pub fn can_transition_to(self, to: OrderStatus) -> bool {
+        use OrderStatus::*;
+
+        matches!(
+            (self, to),
+            (Created, Paid)
+                | (Created, Cancelled)
+                | (Paid, Preparing)
+                | (Paid, Refunded)
+                | (Preparing, Ready)
+                | (Ready, Completed)
+                | (Completed, Refunded)
+                | (Completed, PartiallyRefunded)
+                | (PartiallyRefunded, PartiallyRefunded)
+                | (PartiallyRefunded, Refunded)
+        )
+    }
+
+    /// Terminal states accept no further transitions.
+    pub fn is_terminal(self) -> bool {
+        matches!(self, OrderStatus::Cancelled | OrderStatus::Refunded)
+    }
+}
+
+/// A single timestamped entry in an order's status history.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StatusChange {
+    pub from: OrderStatus,
+    pub to: OrderStatus,
+    pub at: SystemTime,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OrderStatus::*;
+
+    #[test]
+    fn happy_path_is_allowed() {
+        assert!(Created.can_transition_to(Paid));
+        assert!(Paid.can_transition_to(Preparing));
+        assert!(Preparing.can_transition_to(Ready));
+        assert!(Ready.can_transition_to(Completed));
+    }
+
+    #[test]
+    fn skipping_or_reversing_is_rejected() {
+        assert!(!Created.can_transition_to(Completed));
+        assert!(!Completed.can_transition_to(Paid));
+        assert!(!Paid.can_transition_to(Paid));
+        assert!(!Preparing.can_transition_to(Cancelled));
+    }
+
+    #[test]
+    fn completed_orders_refund_in_parts() {
+        assert!(Completed.can_transition_to(PartiallyRefunded));
+        assert!(PartiallyRefunded.can_transition_to(PartiallyRefunded));
+        assert!(PartiallyRefunded.can_transition_to(Refunded));
+        assert!(!Paid.can_transition_to(PartiallyRefunded));
+        assert!(!PartiallyRefunded.can_transition_to(Completed));
+    }
+
+    #[test]
+    fn terminal_states_have_no_exits() {
+        for to in [Created, Paid, Preparing, Ready, Completed, Cancelled, Refunded, PartiallyRefunded] {
+            assert!(!Cancelled.can_transition_to(to));
+            assert!(!Refunded.can_transition_to(to));
+        }
+    }
+}
@@ -1,5 +1,13 @@
 mod decorator;
 mod adapter;
+mod idempotency;
+mod retry;
+mod circuit_breaker;
+mod split_tender;
 
 pub use decorator::{ToppingDecorator};
-pub use adapter::{PaymentAdapter, UpiPayment, CardPayment};
+pub use adapter::{PaymentAdapter, UpiAdapter, CardAdapter, UpiPayment, CardPayment};
+pub use idempotency::{IdempotencyStore, IdempotentPayment};
+pub use retry::RetryingPayment;
+pub use circuit_breaker::CircuitBreaker;
+pub use split_tender::{Allocation, SplitTender};
@@ -1,7 +1,8 @@
 //! Adapter implementations for payment processing (UPI & Card).
 //!
 //! This module provides:
-//! - `PaymentAdapter` trait (object-safe) used by the system.
+//! - `PaymentAdapter` trait (object-safe) used by the system, to pay for
+//!   orders and refund payments in full or in part.
 //! - Concrete adapters: `UpiPayment` and `CardPayment`.
 //!
 //! UPI adapter simulates random success/failure (configurable success rate).
@@ -12,8 +13,9 @@ use std::time::Duration;
 
 use rand::Rng;
 
-use crate::billing::PaymentReceipt;
+use crate::billing::{BillingEngine, PaymentReceipt, RefundReceipt, Tender};
 use crate::errors::BillingError;
+use crate::money::Money;
 use crate::order::order;
 use crate::utils::FileLogger;
 
@@ -22,7 +24,64 @@ use crate::utils::FileLogger;
 /// Implementors should perform payment processing for the provided `Order`
 /// and return a `PaymentReceipt` on success or `BillingError` on failure.
 pub trait PaymentAdapter: Send + Sync {
+    /// Short provider name, e.g. `"upi"`.
+    fn name(&self) -> &str;
+
     fn pay(&self, order: &order) -> Result<PaymentReceipt, BillingError>;
+
+    /// Take `amount` towards the bill in `receipt` without settling it
+    /// (coupon, points). Used for one leg of a split payment; whoever
+    /// charges the legs settles the bill once all of them went through.
+    fn charge(&self, receipt: &PaymentReceipt, amount: Money) -> Result<(), BillingError>;
+
+    /// Return `amount` of the payment in `receipt` to the customer.
+    ///
+    /// Fails with [`BillingError::OverRefund`] if more is asked back than
+    /// is left on the payment, and [`BillingError::RefundWindowClosed`]
+    /// once the billing engine's refund window has passed.
+    fn refund(&self, receipt: &PaymentReceipt, amount: Money) -> Result<RefundReceipt, BillingError>;
+}
+
+impl<A: PaymentAdapter + ?Sized> PaymentAdapter for Box<A> {
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn pay(&self, order: &order) -> Result<PaymentReceipt, BillingError> {
+        (**self).pay(order)
+    }
+
+    fn charge(&self, receipt: &PaymentReceipt, amount: Money) -> Result<(), BillingError> {
+        (**self).charge(receipt, amount)
+    }
+
+    fn refund(&self, receipt: &PaymentReceipt, amount: Money) -> Result<RefundReceipt, BillingError> {
+        (**self).refund(receipt, amount)
+    }
+}
+
+/// Charge the whole bill for `order` through `adapter` and settle it. The
+/// receipt records `adapter` as the only tender.
+///
+/// If settling fails the charge is refunded and the settle error returned,
+/// or [`BillingError::RollbackFailed`] if the refund failed too.
+fn charge_and_settle(
+    adapter: &impl PaymentAdapter,
+    billing: &BillingEngine,
+    order: &order,
+) -> Result<PaymentReceipt, BillingError> {
+    // Quote first so an ineligible coupon fails before any money moves
+    let mut receipt = billing.generate_receipt(order)?;
+    let due = receipt.amount_due()?;
+    adapter.charge(&receipt, due)?;
+    if let Err(e) = billing.settle(&receipt) {
+        if !due.is_zero() && adapter.refund(&receipt, due).is_err() {
+            return Err(BillingError::RollbackFailed { order_id: receipt.order_id, outstanding: due });
+        }
+        return Err(e);
+    }
+    receipt.tenders = vec![Tender { provider: adapter.name().to_string(), amount: due }];
+    Ok(receipt)
 }
 
 /// Marker traits for semantic clarity (re-exported by patterns)
@@ -38,6 +97,8 @@ pub struct UpiPayment {
     pub logger: Option<FileLogger>,
     /// simulated network delay in milliseconds
     pub delay_ms: u64,
+    /// engine used to compute the charged amount
+    pub billing: BillingEngine,
 }
 
 impl UpiPayment {
@@ -48,6 +109,7 @@ impl UpiPayment {
             success_rate: 0.8,
             logger: None,
             delay_ms: 200,
+            billing: BillingEngine::new(),
         }
     }
 
@@ -58,6 +120,7 @@ impl UpiPayment {
             success_rate: success_rate.clamp(0.0, 1.0),
             logger: None,
             delay_ms: 200,
+            billing: BillingEngine::new(),
         }
     }
 
@@ -73,51 +136,72 @@ impl UpiPayment {
         self
     }
 
+    /// Use a specific billing engine (e.g. custom tax policy).
+    pub fn with_billing(mut self, billing: BillingEngine) -> Self {
+        self.billing = billing;
+        self
+    }
+
     fn log(&self, msg: &str) {
-        if let Some(logger) = &self.logger {
-            if let Err(e) = logger.log_with_timestamp(msg) {
-                eprintln!("Failed to persist UPI log: {}", e);
-            }
+        if let Some(logger) = &self.logger
+            && let Err(e) = logger.log_with_timestamp(msg)
+        {
+            eprintln!("Failed to persist UPI log: {}", e);
         }
     }
 }
 
 impl PaymentAdapter for UpiPayment {
+    fn name(&self) -> &str {
+        "upi"
+    }
+
     fn pay(&self, order: &order) -> Result<PaymentReceipt, BillingError> {
+        charge_and_settle(self, &self.billing, order)
+    }
+
+    fn charge(&self, receipt: &PaymentReceipt, amount: Money) -> Result<(), BillingError> {
         // Simulate latency
         if self.delay_ms > 0 {
             thread::sleep(Duration::from_millis(self.delay_ms));
         }
 
         // Randomized success/failure based on success_rate
-        let mut rng = rand::thread_rng();
-        let chance: f64 = rng.r#gen(); // 0.0..1.0
+        let mut rng = rand::rng();
+        let chance: f64 = rng.random(); // 0.0..1.0
         let success = chance <= self.success_rate;
 
         // Log attempt
         let log_msg = format!(
-            "UPI payment attempt: upi_id={}, order_id={:?}, chance={:.3}, success={}",
-            self.upi_id,
-            // Debug-print order id to avoid type assumptions
-            format!("{:?}", &order.id),
-            chance,
-            success
+            "UPI payment attempt: upi_id={}, order_id={}, amount={}, chance={:.3}, success={}",
+            self.upi_id, receipt.order_id, amount, chance, success
         );
         self.log(&log_msg);
 
         if success {
-            // Use billing engine to build the receipt (consistent type)
-            let engine = crate::billing::BillingEngine::new();
-            let receipt = engine.generate_receipt(order);
-            let ok_msg = format!("UPI payment success: order_id={:?}, amount={:.2}", format!("{:?}", &order.id), receipt.total_amount);
+            let ok_msg = format!("UPI payment success: order_id={}, amount={}", receipt.order_id, amount);
             self.log(&ok_msg);
-            Ok(receipt)
+            Ok(())
         } else {
-            let err_msg = format!("UPI payment failed: order_id={:?}", format!("{:?}", &order.id));
+            let err_msg = format!("UPI payment failed: order_id={}", receipt.order_id);
             self.log(&err_msg);
             Err(BillingError::PaymentFailed("UPI transaction rejected".into()))
         }
     }
+
+    fn refund(&self, receipt: &PaymentReceipt, amount: Money) -> Result<RefundReceipt, BillingError> {
+        let refund = self.billing.quote_refund(receipt, amount)?;
+
+        if self.delay_ms > 0 {
+            thread::sleep(Duration::from_millis(self.delay_ms));
+        }
+
+        self.log(&format!(
+            "UPI refund: upi_id={}, order_id={}, amount={}, remaining={}",
+            self.upi_id, receipt.order_id, refund.amount, refund.remaining
+        ));
+        Ok(refund)
+    }
 }
 
 impl UpiAdapter for UpiPayment {}
@@ -132,6 +216,7 @@ pub struct CardPayment {
     pub fail_on_invalid_cvv: bool,
     pub logger: Option<FileLogger>,
     pub delay_ms: u64,
+    pub billing: BillingEngine,
 }
 
 impl CardPayment {
@@ -142,6 +227,7 @@ impl CardPayment {
             fail_on_invalid_cvv: true,
             logger: None,
             delay_ms: 150,
+            billing: BillingEngine::new(),
         }
     }
 
@@ -150,21 +236,39 @@ impl CardPayment {
         self
     }
 
+    pub fn with_billing(mut self, billing: BillingEngine) -> Self {
+        self.billing = billing;
+        self
+    }
+
     fn log(&self, msg: &str) {
-        if let Some(logger) = &self.logger {
-            if let Err(e) = logger.log_with_timestamp(msg) {
-                eprintln!("Failed to persist Card log: {}", e);
-            }
+        if let Some(logger) = &self.logger
+            && let Err(e) = logger.log_with_timestamp(msg)
+        {
+            eprintln!("Failed to persist Card log: {}", e);
         }
     }
 
     fn validate_cvv(&self) -> bool {
         (100..=999).contains(&self.cvv)
     }
+
+    fn last_four(&self) -> String {
+        let digits: Vec<char> = self.card_number.chars().collect();
+        digits[digits.len().saturating_sub(4)..].iter().collect()
+    }
 }
 
 impl PaymentAdapter for CardPayment {
+    fn name(&self) -> &str {
+        "card"
+    }
+
     fn pay(&self, order: &order) -> Result<PaymentReceipt, BillingError> {
+        charge_and_settle(self, &self.billing, order)
+    }
+
+    fn charge(&self, receipt: &PaymentReceipt, amount: Money) -> Result<(), BillingError> {
         if self.delay_ms > 0 {
             thread::sleep(Duration::from_millis(self.delay_ms));
         }
@@ -172,32 +276,48 @@ impl PaymentAdapter for CardPayment {
         let valid = self.validate_cvv();
 
         let log_msg = format!(
-            "Card payment attempt: card=****{}, order_id={:?}, cvv_valid={}",
-            &self.card_number.chars().rev().take(4).collect::<String>().chars().rev().collect::<String>(), // last 4 masked
-            format!("{:?}", &order.id),
+            "Card payment attempt: card=****{}, order_id={}, amount={}, cvv_valid={}",
+            self.last_four(),
+            receipt.order_id,
+            amount,
             valid
         );
 
         self.log(&log_msg);
 
         if valid {
-            let engine = crate::billing::BillingEngine::new();
-            let receipt = engine.generate_receipt(order);
-            let ok_msg = format!("Card payment success: order_id={:?}, amount={:.2}", format!("{:?}", &order.id), receipt.total_amount);
+            let ok_msg = format!("Card payment success: order_id={}, amount={}", receipt.order_id, amount);
             self.log(&ok_msg);
-            Ok(receipt)
+            Ok(())
         } else {
-            let err_msg = format!("Card payment failed (invalid CVV): order_id={:?}", format!("{:?}", &order.id));
+            let err_msg = format!("Card payment failed (invalid CVV): order_id={}", receipt.order_id);
             self.log(&err_msg);
             if self.fail_on_invalid_cvv {
-                Err(BillingError::PaymentFailed("Invalid CVV".into()))
+                Err(BillingError::PaymentDeclined("Invalid CVV".into()))
             } else {
                 // If fail_on_invalid_cvv == false, we still allow success (testing mode)
-                let engine = crate::billing::BillingEngine::new();
-                Ok(engine.generate_receipt(order))
+                Ok(())
             }
         }
     }
+
+    fn refund(&self, receipt: &PaymentReceipt, amount: Money) -> Result<RefundReceipt, BillingError> {
+        // Refunds go back to the card that paid; no CVV is needed
+        let refund = self.billing.quote_refund(receipt, amount)?;
+
+        if self.delay_ms > 0 {
+            thread::sleep(Duration::from_millis(self.delay_ms));
+        }
+
+        self.log(&format!(
+            "Card refund: card=****{}, order_id={}, amount={}, remaining={}",
+            self.last_four(),
+            receipt.order_id,
+            refund.amount,
+            refund.remaining
+        ));
+        Ok(refund)
+    }
 }
 
 impl CardAdapter for CardPayment {}
@@ -205,6 +325,8 @@ impl CardAdapter for CardPayment {}
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::billing::NoTax;
+    use crate::money::{Currency, Money};
     use crate::pizza::{Margherita, Cheese};
     use crate::order::order;
     use crate::utils::FileLogger;
@@ -225,7 +347,8 @@ mod tests {
         let result = upi.pay(&order);
         assert!(result.is_ok());
         let receipt = result.unwrap();
-        assert_eq!(receipt.total_amount, 250.0); // 120 + 130
+        assert_eq!(receipt.subtotal, Money::inr(250)); // 120 + 130
+        assert_eq!(receipt.total_amount, Money::from_minor(26_250, Currency::Inr)); // + 5% GST
     }
 
     #[test]
@@ -240,9 +363,9 @@ mod tests {
     fn card_adapter_succeeds_with_valid_cvv() {
         let order = sample_order();
         let card = CardPayment::new("4111222233334444", 123);
-        let res = card.pay(&order);
-        assert!(res.is_ok());
-        assert_eq!(res.unwrap().total_amount, 250.0);
+        let receipt = card.pay(&order).unwrap();
+        assert_eq!(receipt.total_amount, Money::from_minor(26_250, Currency::Inr));
+        assert_eq!(receipt.tenders, vec![Tender { provider: "card".to_string(), amount: receipt.total_amount }]);
     }
 
     #[test]
@@ -263,6 +386,46 @@ mod tests {
         assert!(res.is_ok());
     }
 
+    #[test]
+    fn adapter_uses_configured_billing_engine() {
+        let order = sample_order();
+        let card = CardPayment::new("4111222233334444", 123)
+            .with_billing(BillingEngine::with_tax_policy(NoTax));
+        let receipt = card.pay(&order).unwrap();
+        assert_eq!(receipt.total_amount, Money::inr(250));
+        assert!(receipt.tax_lines.is_empty());
+    }
+
+    #[test]
+    fn partial_refunds_stop_at_what_was_paid() {
+        let order = sample_order();
+        let upi = UpiPayment::with_success_rate("user@upi", 1.0).with_delay(0);
+        let mut receipt = upi.pay(&order).unwrap();
+
+        let first = upi.refund(&receipt, Money::inr(100)).unwrap();
+        assert_eq!(first.remaining, Money::from_minor(16_250, Currency::Inr));
+        receipt.refunds.push(first);
+
+        assert!(matches!(
+            upi.refund(&receipt, Money::inr(200)),
+            Err(BillingError::OverRefund { refundable, .. }) if refundable == Money::from_minor(16_250, Currency::Inr)
+        ));
+        assert!(upi.refund(&receipt, Money::from_minor(16_250, Currency::Inr)).unwrap().is_full());
+    }
+
+    #[test]
+    fn card_refunds_close_with_the_refund_window() {
+        let order = sample_order();
+        let card = CardPayment::new("4111222233334444", 123)
+            .with_billing(BillingEngine::new().with_refund_window(Duration::ZERO));
+        // The simulated card delay puts the payment outside a zero window
+        let receipt = card.pay(&order).unwrap();
+        assert!(matches!(
+            card.refund(&receipt, Money::inr(10)),
+            Err(BillingError::RefundWindowClosed { days: 0, .. })
+        ));
+    }
+
     #[test]
     fn adapters_log_without_panic() {
         let order = sample_order();
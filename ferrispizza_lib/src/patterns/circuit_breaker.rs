@@ -0,0 +1,122 @@
+//! Circuit breaker — stop calling a payment provider that keeps failing.
+//!
+//! After `threshold` consecutive failures the breaker *opens* and refuses
+//! payments with [`BillingError::CircuitOpen`] until `cooldown` has passed.
+//! The next payment is then let through as a trial: success closes the
+//! breaker again, another failure reopens it for a new cooldown.
+//!
+//! # Example
+//! ```
+//! use std::time::Duration;
+//! use ferrispizza_lib::patterns::CircuitBreaker;
+//!
+//! let breaker = CircuitBreaker::new(2, Duration::from_secs(30));
+//! breaker.record_failure();
+//! assert!(breaker.allow().is_ok());
+//! breaker.record_failure();
+//! assert!(breaker.is_open());
+//! assert!(breaker.allow().is_err());
+//! ```
+
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::errors::BillingError;
+
+struct BreakerState {
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+}
+
+/// Counts consecutive payment failures. Clones share the same count, so
+/// one breaker can guard every payment made at a counter.
+#[derive(Clone)]
+pub struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    state: Arc<Mutex<BreakerState>>,
+}
+
+impl CircuitBreaker {
+    /// Open after `threshold` (at least 1) consecutive failures, for
+    /// `cooldown`.
+    pub fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            threshold: threshold.max(1),
+            cooldown,
+            state: Arc::new(Mutex::new(BreakerState {
+                consecutive_failures: 0,
+                opened_at: None,
+            })),
+        }
+    }
+
+    /// Whether a payment may be attempted now.
+    pub fn allow(&self) -> Result<(), BillingError> {
+        let mut state = self.lock();
+        if let Some(opened_at) = state.opened_at {
+            let waited = opened_at.elapsed();
+            if waited < self.cooldown {
+                return Err(BillingError::CircuitOpen {
+                    retry_after: self.cooldown - waited,
+                });
+            }
+            // Cooled down: let one trial through, one more failure reopens
+            state.opened_at = None;
+            state.consecutive_failures = self.threshold - 1;
+        }
+        Ok(())
+    }
+
+    pub fn record_success(&self) {
+        let mut state = self.lock();
+        state.consecutive_failures = 0;
+        state.opened_at = None;
+    }
+
+    pub fn record_failure(&self) {
+        let mut state = self.lock();
+        state.consecutive_failures += 1;
+        if state.consecutive_failures >= self.threshold {
+            state.opened_at = Some(Instant::now());
+        }
+    }
+
+    /// Open and still cooling down.
+    pub fn is_open(&self) -> bool {
+        self.lock()
+            .opened_at
+            .is_some_and(|opened_at| opened_at.elapsed() < self.cooldown)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BreakerState> {
+        self.state.lock().expect("circuit breaker lock poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn reopens_when_the_trial_after_cooldown_fails() {
+        let breaker = CircuitBreaker::new(3, Duration::from_millis(20));
+        for _ in 0..3 {
+            breaker.allow().unwrap();
+            breaker.record_failure();
+        }
+        assert!(matches!(breaker.allow(), Err(BillingError::CircuitOpen { .. })));
+
+        thread::sleep(Duration::from_millis(30));
+        breaker.allow().unwrap();
+        breaker.record_failure();
+        assert!(breaker.is_open());
+
+        thread::sleep(Duration::from_millis(30));
+        breaker.allow().unwrap();
+        breaker.record_success();
+        breaker.record_failure();
+        assert!(!breaker.is_open());
+    }
+}
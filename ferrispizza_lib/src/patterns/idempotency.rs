@@ -0,0 +1,246 @@
+//! Idempotent payments — retrying a payment never charges twice.
+//!
+//! [`IdempotentPayment`] wraps any [`PaymentAdapter`] and keys each
+//! payment on the order id plus a key chosen by the client. The first
+//! successful payment for a key is remembered in a shared
+//! [`IdempotencyStore`]; paying again with the same key returns that
+//! receipt without charging. Reusing a key with different parameters
+//! (another provider, coupon, points or pizzas) is rejected. Failed
+//! payments are not remembered, so they can simply be retried, unless
+//! money was taken and could not be returned
+//! ([`BillingError::RollbackFailed`]): those keys refuse further payments
+//! with [`BillingError::PaymentUnresolved`]. Payments
+//! that are not made through one adapter, such as a
+//! [`SplitTender`](super::SplitTender), use the store directly with
+//! [`IdempotencyStore::pay_once`].
+//!
+//! # Example
+//! ```
+//! use ferrispizza_lib::order::order;
+//! use ferrispizza_lib::patterns::{IdempotencyStore, IdempotentPayment, PaymentAdapter, UpiPayment};
+//! use ferrispizza_lib::pizza::Margherita;
+//!
+//! let store = IdempotencyStore::new();
+//! let order = order::new(vec![Box::new(Margherita::new())]);
+//! let upi = || UpiPayment::with_success_rate("asha@upi", 1.0).with_delay(0);
+//!
+//! let first = IdempotentPayment::new(upi(), store.clone(), "till-1").pay(&order).unwrap();
+//! let retry = IdempotentPayment::new(upi(), store.clone(), "till-1").pay(&order).unwrap();
+//! assert_eq!(first, retry);
+//! ```
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::billing::{PaymentReceipt, RefundReceipt};
+use crate::errors::BillingError;
+use crate::money::Money;
+use crate::order::order;
+use super::adapter::PaymentAdapter;
+
+struct Attempt {
+    fingerprint: String,
+    /// `None` while the payment is still being charged.
+    receipt: Option<PaymentReceipt>,
+    /// The payment failed but kept some of the customer's money.
+    unresolved: bool,
+}
+
+/// Payments already made, by order id and client key. Clones share the
+/// same store.
+#[derive(Clone, Default)]
+pub struct IdempotencyStore {
+    attempts: Arc<Mutex<HashMap<(u64, String), Attempt>>>,
+}
+
+impl IdempotencyStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Receipt of the payment made for `order_id` with `key`, if any.
+    pub fn receipt(&self, order_id: u64, key: &str) -> Option<PaymentReceipt> {
+        self.lock()
+            .get(&(order_id, key.to_string()))
+            .and_then(|attempt| attempt.receipt.clone())
+    }
+
+    /// The receipt of an earlier payment of `order` with `key` by `method`
+    /// (a provider name or other description of how it is paid), without
+    /// charging anything. Fails with [`BillingError::IdempotencyConflict`]
+    /// if that payment had different parameters, and
+    /// [`BillingError::PaymentInProgress`] while it is still being charged.
+    pub fn replay(&self, order: &order, key: &str, method: &str) -> Result<Option<PaymentReceipt>, BillingError> {
+        let fingerprint = fingerprint(order, method);
+        match self.lock().get(&(order.id.0, key.to_string())) {
+            None => Ok(None),
+            Some(attempt) => check(order, key, &fingerprint, attempt).map(Some),
+        }
+    }
+
+    /// Pay `order` with `pay` unless it was already paid with `key`, in
+    /// which case that receipt is returned; see [`replay`](Self::replay).
+    pub fn pay_once(
+        &self,
+        order: &order,
+        key: &str,
+        method: &str,
+        pay: impl FnOnce() -> Result<PaymentReceipt, BillingError>,
+    ) -> Result<PaymentReceipt, BillingError> {
+        let id = (order.id.0, key.to_string());
+        let fingerprint = fingerprint(order, method);
+        {
+            let mut attempts = self.lock();
+            if let Some(attempt) = attempts.get(&id) {
+                return check(order, key, &fingerprint, attempt);
+            }
+            attempts.insert(id.clone(), Attempt { fingerprint: fingerprint.clone(), receipt: None, unresolved: false });
+        }
+
+        // Charge without holding the lock; other keys can pay meanwhile
+        let result = pay();
+        let mut attempts = self.lock();
+        match &result {
+            Ok(receipt) => {
+                attempts.insert(id, Attempt { fingerprint, receipt: Some(receipt.clone()), unresolved: false });
+            }
+            // Paying again could take the money a second time
+            Err(BillingError::RollbackFailed { .. }) => {
+                attempts.insert(id, Attempt { fingerprint, receipt: None, unresolved: true });
+            }
+            Err(_) => {
+                attempts.remove(&id);
+            }
+        }
+        result
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<(u64, String), Attempt>> {
+        self.attempts.lock().expect("idempotency store lock poisoned")
+    }
+}
+
+/// A [`PaymentAdapter`] that charges each order at most once per key.
+pub struct IdempotentPayment<A: PaymentAdapter> {
+    inner: A,
+    store: IdempotencyStore,
+    key: String,
+}
+
+impl<A: PaymentAdapter> IdempotentPayment<A> {
+    pub fn new(inner: A, store: IdempotencyStore, key: impl Into<String>) -> Self {
+        Self {
+            inner,
+            store,
+            key: key.into(),
+        }
+    }
+
+    /// The receipt of an earlier payment of `order` with this key, without
+    /// charging anything; see [`IdempotencyStore::replay`].
+    pub fn replay(&self, order: &order) -> Result<Option<PaymentReceipt>, BillingError> {
+        self.store.replay(order, &self.key, self.inner.name())
+    }
+}
+
+fn check(order: &order, key: &str, fingerprint: &str, attempt: &Attempt) -> Result<PaymentReceipt, BillingError> {
+    if attempt.fingerprint != fingerprint {
+        return Err(BillingError::IdempotencyConflict {
+            order_id: order.id.0,
+            key: key.to_string(),
+        });
+    }
+    if attempt.unresolved {
+        return Err(BillingError::PaymentUnresolved {
+            order_id: order.id.0,
+            key: key.to_string(),
+        });
+    }
+    attempt.receipt.clone().ok_or_else(|| BillingError::PaymentInProgress {
+        order_id: order.id.0,
+        key: key.to_string(),
+    })
+}
+
+/// Everything that decides what a payment charges.
+fn fingerprint(order: &order, method: &str) -> String {
+    let pizzas: Vec<String> = order
+        .pizzas
+        .iter()
+        .map(|p| format!("{}={}", p.description(), p.cost().map_or_else(|e| e.to_string(), |c| c.to_string())))
+        .collect();
+    format!(
+        "{}|{}|{}|{}|{:?}",
+        method,
+        pizzas.join(","),
+        order.coupon.as_ref().map_or("", |c| c.code.as_str()),
+        order.redeem_points,
+        order.customer.map(|c| c.0),
+    )
+}
+
+impl<A: PaymentAdapter> PaymentAdapter for IdempotentPayment<A> {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn pay(&self, order: &order) -> Result<PaymentReceipt, BillingError> {
+        self.store.pay_once(order, &self.key, self.inner.name(), || self.inner.pay(order))
+    }
+
+    /// Single legs of a split payment are not keyed; key the whole split
+    /// with [`IdempotencyStore::pay_once`].
+    fn charge(&self, receipt: &PaymentReceipt, amount: Money) -> Result<(), BillingError> {
+        self.inner.charge(receipt, amount)
+    }
+
+    fn refund(&self, receipt: &PaymentReceipt, amount: Money) -> Result<RefundReceipt, BillingError> {
+        self.inner.refund(receipt, amount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::billing::CouponClaim;
+    use crate::patterns::{CardPayment, UpiPayment};
+    use crate::pizza::Margherita;
+
+    fn upi(success_rate: f64) -> UpiPayment {
+        UpiPayment::with_success_rate("asha@upi", success_rate).with_delay(0)
+    }
+
+    #[test]
+    fn same_key_with_new_parameters_is_rejected() {
+        let store = IdempotencyStore::new();
+        let mut order = order::new(vec![Box::new(Margherita::new())]);
+        IdempotentPayment::new(upi(1.0), store.clone(), "k1").pay(&order).unwrap();
+
+        let card = IdempotentPayment::new(CardPayment::new("4111222233334444", 123), store.clone(), "k1");
+        assert!(matches!(card.pay(&order), Err(BillingError::IdempotencyConflict { .. })));
+
+        order.apply_coupon(CouponClaim::new("FLAT50", "walk-in"));
+        let again = IdempotentPayment::new(upi(1.0), store.clone(), "k1");
+        assert!(matches!(again.replay(&order), Err(BillingError::IdempotencyConflict { .. })));
+    }
+
+    #[test]
+    fn failed_payments_can_be_retried_with_the_same_key() {
+        let store = IdempotencyStore::new();
+        let order = order::new(vec![Box::new(Margherita::new())]);
+
+        assert!(IdempotentPayment::new(upi(0.0), store.clone(), "k1").pay(&order).is_err());
+        assert_eq!(store.receipt(order.id.0, "k1"), None);
+
+        let receipt = IdempotentPayment::new(upi(1.0), store.clone(), "k1").pay(&order).unwrap();
+        assert_eq!(store.receipt(order.id.0, "k1"), Some(receipt));
+
+        // Money that could not be given back blocks the key
+        let stuck = || Err(BillingError::RollbackFailed { order_id: order.id.0, outstanding: Money::inr(50) });
+        assert!(store.pay_once(&order, "k2", "split", stuck).is_err());
+        assert!(matches!(
+            store.pay_once(&order, "k2", "split", || unreachable!()),
+            Err(BillingError::PaymentUnresolved { .. })
+        ));
+    }
+}
@@ -0,0 +1,300 @@
+//! Automatic retries for flaky payment providers.
+//!
+//! [`RetryingPayment`] wraps any [`PaymentAdapter`] and tries again when a
+//! payment fails for a reason that may go away, waiting longer after each
+//! attempt (exponential backoff) plus a random jitter so that tills do not
+//! retry in lockstep. Which errors are retried is configurable and defaults
+//! to [`BillingError::is_transient`]; declined cards or invalid coupons are
+//! returned at once. Every attempt is logged when a [`FileLogger`] is
+//! attached, and an optional [`CircuitBreaker`] stops retrying altogether
+//! once the provider keeps failing. Refunds are tried once and never held
+//! back by the breaker: a refund that seems to fail may still have gone
+//! through, and retrying it could pay the customer twice.
+//!
+//! # Example
+//! ```
+//! use std::time::Duration;
+//! use ferrispizza_lib::order::order;
+//! use ferrispizza_lib::patterns::{PaymentAdapter, RetryingPayment, UpiPayment};
+//! use ferrispizza_lib::pizza::Margherita;
+//!
+//! let upi = UpiPayment::with_success_rate("asha@upi", 1.0).with_delay(0);
+//! let payment = RetryingPayment::new(upi)
+//!     .with_max_attempts(4)
+//!     .with_backoff(Duration::from_millis(10), Duration::from_millis(80));
+//!
+//! let order = order::new(vec![Box::new(Margherita::new())]);
+//! assert!(payment.pay(&order).is_ok());
+//! ```
+
+use std::thread;
+use std::time::Duration;
+
+use rand::Rng;
+
+use crate::billing::{PaymentReceipt, RefundReceipt};
+use crate::errors::BillingError;
+use crate::money::Money;
+use crate::order::order;
+use crate::utils::FileLogger;
+use super::adapter::PaymentAdapter;
+use super::circuit_breaker::CircuitBreaker;
+
+/// A [`PaymentAdapter`] that retries failed payments with backoff.
+pub struct RetryingPayment<A: PaymentAdapter> {
+    inner: A,
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: f64,
+    retry_on: fn(&BillingError) -> bool,
+    logger: Option<FileLogger>,
+    breaker: Option<CircuitBreaker>,
+}
+
+impl<A: PaymentAdapter> RetryingPayment<A> {
+    /// Three attempts, 200ms backoff doubling up to 2s, half of each wait
+    /// randomised.
+    pub fn new(inner: A) -> Self {
+        Self {
+            inner,
+            max_attempts: 3,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(2),
+            jitter: 0.5,
+            retry_on: BillingError::is_transient,
+            logger: None,
+            breaker: None,
+        }
+    }
+
+    /// Total attempts including the first (at least 1).
+    pub fn with_max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts.max(1);
+        self
+    }
+
+    /// Wait `base` after the first failure, doubling each time up to `max`.
+    pub fn with_backoff(mut self, base: Duration, max: Duration) -> Self {
+        self.base_delay = base;
+        self.max_delay = max.max(base);
+        self
+    }
+
+    /// Fraction (0.0–1.0) of each wait that is randomised.
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Which errors are worth another attempt.
+    pub fn with_retry_on(mut self, retry_on: fn(&BillingError) -> bool) -> Self {
+        self.retry_on = retry_on;
+        self
+    }
+
+    /// Attach an optional `FileLogger` to record every attempt.
+    pub fn with_logger(mut self, logger: FileLogger) -> Self {
+        self.logger = Some(logger);
+        self
+    }
+
+    /// Refuse to charge through the provider while `breaker` is open, and
+    /// report each retryable payment failure and success to it. Refunds go
+    /// through regardless. Give each provider its own breaker.
+    pub fn with_breaker(mut self, breaker: CircuitBreaker) -> Self {
+        self.breaker = Some(breaker);
+        self
+    }
+
+    /// Wait before attempt `attempt + 1`, before jitter.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+
+    fn jittered(&self, delay: Duration) -> Duration {
+        let random: f64 = rand::rng().random();
+        delay.mul_f64(1.0 - self.jitter * random)
+    }
+
+    fn log(&self, msg: &str) {
+        if let Some(logger) = &self.logger
+            && let Err(e) = logger.log_with_timestamp(msg)
+        {
+            eprintln!("Failed to persist payment retry log: {}", e);
+        }
+    }
+
+    fn with_retries<T>(
+        &self,
+        what: &str,
+        order_id: u64,
+        max_attempts: u32,
+        breaker: Option<&CircuitBreaker>,
+        call: impl Fn() -> Result<T, BillingError>,
+    ) -> Result<T, BillingError> {
+        let provider = self.inner.name();
+        let mut attempt = 1;
+        loop {
+            if let Some(breaker) = breaker
+                && let Err(e) = breaker.allow()
+            {
+                self.log(&format!("{} {} for order {} not attempted: {}", provider, what, order_id, e));
+                return Err(e);
+            }
+
+            let result = call();
+            let error = match result {
+                Ok(value) => {
+                    if let Some(breaker) = breaker {
+                        breaker.record_success();
+                    }
+                    self.log(&format!(
+                        "{} {} for order {}: attempt {}/{} succeeded",
+                        provider, what, order_id, attempt, max_attempts
+                    ));
+                    return Ok(value);
+                }
+                Err(e) => e,
+            };
+
+            let retryable = (self.retry_on)(&error);
+            if retryable && let Some(breaker) = breaker {
+                breaker.record_failure();
+            }
+            self.log(&format!(
+                "{} {} for order {}: attempt {}/{} failed: {}",
+                provider, what, order_id, attempt, max_attempts, error
+            ));
+            if !retryable || attempt >= max_attempts {
+                return Err(error);
+            }
+
+            let delay = self.jittered(self.backoff(attempt));
+            self.log(&format!("{} {} for order {}: retrying in {}ms", provider, what, order_id, delay.as_millis()));
+            thread::sleep(delay);
+            attempt += 1;
+        }
+    }
+}
+
+impl<A: PaymentAdapter> PaymentAdapter for RetryingPayment<A> {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn pay(&self, order: &order) -> Result<PaymentReceipt, BillingError> {
+        self.with_retries("payment", order.id.0, self.max_attempts, self.breaker.as_ref(), || self.inner.pay(order))
+    }
+
+    fn charge(&self, receipt: &PaymentReceipt, amount: Money) -> Result<(), BillingError> {
+        self.with_retries("charge", receipt.order_id, self.max_attempts, self.breaker.as_ref(), || {
+            self.inner.charge(receipt, amount)
+        })
+    }
+
+    fn refund(&self, receipt: &PaymentReceipt, amount: Money) -> Result<RefundReceipt, BillingError> {
+        self.with_retries("refund", receipt.order_id, 1, None, || self.inner.refund(receipt, amount))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use crate::billing::BillingEngine;
+    use crate::patterns::{CardPayment, UpiPayment};
+    use crate::pizza::Margherita;
+
+    /// Fails with a transient error until its `failures` run out.
+    struct Flaky {
+        failures: AtomicU32,
+        calls: AtomicU32,
+        upi: UpiPayment,
+    }
+
+    impl Flaky {
+        fn new(failures: u32) -> Self {
+            Self {
+                failures: AtomicU32::new(failures),
+                calls: AtomicU32::new(0),
+                upi: UpiPayment::with_success_rate("asha@upi", 1.0).with_delay(0),
+            }
+        }
+    }
+
+    impl PaymentAdapter for &Flaky {
+        fn name(&self) -> &str {
+            "flaky"
+        }
+
+        fn pay(&self, order: &order) -> Result<PaymentReceipt, BillingError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if self.failures.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1)).is_ok() {
+                return Err(BillingError::PaymentFailed("gateway timeout".into()));
+            }
+            self.upi.pay(order)
+        }
+
+        fn charge(&self, receipt: &PaymentReceipt, amount: Money) -> Result<(), BillingError> {
+            self.upi.charge(receipt, amount)
+        }
+
+        fn refund(&self, receipt: &PaymentReceipt, amount: Money) -> Result<RefundReceipt, BillingError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if self.failures.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1)).is_ok() {
+                return Err(BillingError::PaymentFailed("gateway timeout".into()));
+            }
+            self.upi.refund(receipt, amount)
+        }
+    }
+
+    fn quick<A: PaymentAdapter>(inner: A) -> RetryingPayment<A> {
+        RetryingPayment::new(inner).with_backoff(Duration::from_millis(1), Duration::from_millis(4))
+    }
+
+    #[test]
+    fn retries_transient_failures_but_not_declines() {
+        let order = order::new(vec![Box::new(Margherita::new())]);
+
+        let flaky = Flaky::new(2);
+        assert!(quick(&flaky).pay(&order).is_ok());
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 3);
+
+        let flaky = Flaky::new(5);
+        assert!(quick(&flaky).with_max_attempts(4).pay(&order).is_err());
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 4);
+
+        let bad_cvv = CardPayment::new("4111222233334444", 7);
+        assert!(matches!(quick(bad_cvv).pay(&order), Err(BillingError::PaymentDeclined(_))));
+    }
+
+    #[test]
+    fn open_breaker_stops_further_attempts() {
+        let order = order::new(vec![Box::new(Margherita::new())]);
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+        let flaky = Flaky::new(10);
+
+        let payment = quick(&flaky).with_max_attempts(5).with_breaker(breaker.clone());
+        assert!(matches!(payment.pay(&order), Err(BillingError::CircuitOpen { .. })));
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 2);
+        assert!(breaker.is_open());
+
+        // Money can still be given back while payments are paused, but a
+        // failed refund is not sent again
+        let receipt = BillingEngine::new().generate_receipt(&order).unwrap();
+        assert!(payment.refund(&receipt, receipt.total_amount).is_err());
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 3);
+        flaky.failures.store(0, Ordering::SeqCst);
+        assert!(payment.refund(&receipt, receipt.total_amount).is_ok());
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let payment = RetryingPayment::new(UpiPayment::new("asha@upi"))
+            .with_backoff(Duration::from_millis(100), Duration::from_millis(350));
+        let waits: Vec<u128> = (1..=4).map(|n| payment.backoff(n).as_millis()).collect();
+        assert_eq!(waits, vec![100, 200, 350, 350]);
+    }
+}
@@ -0,0 +1,285 @@
+//! Split payments — one bill paid with several payment methods.
+//!
+//! A [`SplitTender`] takes one leg per payment method, each a fixed
+//! [`Allocation::Amount`] or an [`Allocation::Share`] of what the fixed
+//! amounts leave. The legs must cover the bill exactly. They are charged in
+//! order; if one fails, the legs already charged are refunded and the error
+//! is returned, so the customer never pays for part of a bill. Once every
+//! leg went through the bill is settled (coupon, points) and the receipt
+//! lists each leg in [`PaymentReceipt::tenders`]. To make retrying a split
+//! payment safe, pay through
+//! [`IdempotencyStore::pay_once`](super::IdempotencyStore::pay_once) with
+//! the tender's [`Display`](fmt::Display) form as the method.
+//!
+//! # Example
+//! ```
+//! use ferrispizza_lib::billing::BillingEngine;
+//! use ferrispizza_lib::money::Money;
+//! use ferrispizza_lib::order::order;
+//! use ferrispizza_lib::patterns::{Allocation, CardPayment, SplitTender, UpiPayment};
+//! use ferrispizza_lib::pizza::Margherita;
+//!
+//! let order = order::new(vec![Box::new(Margherita::new())]); // ₹126.00 with GST
+//! let receipt = SplitTender::new(BillingEngine::new())
+//!     .with_leg(UpiPayment::with_success_rate("asha@upi", 1.0).with_delay(0), Allocation::Amount(Money::inr(100)))
+//!     .with_leg(CardPayment::new("4111222233334444", 123), Allocation::Share(1))
+//!     .pay(&order)
+//!     .unwrap();
+//!
+//! assert_eq!(receipt.tenders[0].amount, Money::inr(100));
+//! assert_eq!(receipt.tenders[1].amount, Money::inr(26));
+//! ```
+
+use std::fmt;
+
+use crate::billing::{BillingEngine, PaymentReceipt, Tender};
+use crate::errors::BillingError;
+use crate::money::{Money, Rounding};
+use crate::order::order;
+use super::adapter::PaymentAdapter;
+
+/// How much of the bill one leg pays.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Allocation {
+    /// Exactly this amount.
+    Amount(Money),
+    /// This many parts of what the fixed amounts leave; `Share(1)` on two
+    /// legs halves it.
+    Share(u32),
+}
+
+/// Pays one order with several [`PaymentAdapter`]s.
+pub struct SplitTender {
+    billing: BillingEngine,
+    legs: Vec<(Box<dyn PaymentAdapter>, Allocation)>,
+}
+
+impl SplitTender {
+    /// Bill with `billing`; it should be the engine the adapters refund with.
+    pub fn new(billing: BillingEngine) -> Self {
+        Self {
+            billing,
+            legs: Vec::new(),
+        }
+    }
+
+    /// Add a leg, charged after those added before it.
+    pub fn with_leg(mut self, adapter: impl PaymentAdapter + 'static, allocation: Allocation) -> Self {
+        self.legs.push((Box::new(adapter), allocation));
+        self
+    }
+
+    /// What each leg pays of `due`, in leg order.
+    ///
+    /// Fails with [`BillingError::SplitMismatch`] unless the legs cover
+    /// `due` exactly, and [`BillingError::InvalidSplitAmount`] for a leg
+    /// that would pay nothing.
+    pub fn allocate(&self, due: Money) -> Result<Vec<Money>, BillingError> {
+        let currency = due.currency();
+        let fixed = Money::checked_sum(
+            self.legs.iter().filter_map(|(_, a)| match a {
+                Allocation::Amount(amount) => Some(*amount),
+                Allocation::Share(_) => None,
+            }),
+            currency,
+        )?;
+        let parts: u32 = self.legs.iter().map(|(_, a)| match a {
+            Allocation::Share(parts) => *parts,
+            Allocation::Amount(_) => 0,
+        }).sum();
+        let shared = self.legs.iter().any(|(_, a)| matches!(a, Allocation::Share(_)));
+
+        let rest = due.checked_sub(fixed)?;
+        if rest.is_negative() || (!shared && !rest.is_zero()) || self.legs.is_empty() {
+            return Err(BillingError::SplitMismatch { allocated: fixed, total: due });
+        }
+
+        // The last share takes what rounding leaves
+        let last_share = self.legs.iter().rposition(|(_, a)| matches!(a, Allocation::Share(_)));
+        let mut left = rest;
+        let mut amounts = Vec::with_capacity(self.legs.len());
+        for (idx, (_, allocation)) in self.legs.iter().enumerate() {
+            let amount = match *allocation {
+                Allocation::Amount(amount) => amount,
+                Allocation::Share(_) if Some(idx) == last_share => left,
+                Allocation::Share(n) => {
+                    let share = rest.apply_rate(i64::from(n), i64::from(parts.max(1)), Rounding::Down)?;
+                    left = left.checked_sub(share)?;
+                    share
+                }
+            };
+            if amount.is_zero() || amount.is_negative() {
+                return Err(BillingError::InvalidSplitAmount(amount));
+            }
+            amounts.push(amount);
+        }
+        Ok(amounts)
+    }
+
+    /// Charge every leg and settle the bill.
+    ///
+    /// If a leg fails the earlier legs are refunded and that leg's error
+    /// returned; [`BillingError::RollbackFailed`] says some could not be.
+    pub fn pay(&self, order: &order) -> Result<PaymentReceipt, BillingError> {
+        let mut receipt = self.billing.generate_receipt(order)?;
+        let amounts = self.allocate(receipt.amount_due()?)?;
+
+        let mut charged = Vec::with_capacity(amounts.len());
+        for ((adapter, _), &amount) in self.legs.iter().zip(&amounts) {
+            if let Err(e) = adapter.charge(&receipt, amount) {
+                return Err(self.roll_back(&receipt, &charged).err().unwrap_or(e));
+            }
+            charged.push(Tender { provider: adapter.name().to_string(), amount });
+        }
+        if let Err(e) = self.billing.settle(&receipt) {
+            return Err(self.roll_back(&receipt, &charged).err().unwrap_or(e));
+        }
+
+        receipt.tenders = charged;
+        Ok(receipt)
+    }
+
+    /// Refund the legs charged so far, latest first. Every leg is tried;
+    /// fails with the error to report instead of the original one if not
+    /// all of them could be refunded.
+    fn roll_back(&self, receipt: &PaymentReceipt, charged: &[Tender]) -> Result<(), BillingError> {
+        let mut outstanding = Money::zero(receipt.total_amount.currency());
+        for (tender, (adapter, _)) in charged.iter().zip(&self.legs).rev() {
+            if adapter.refund(receipt, tender.amount).is_err() {
+                outstanding = outstanding.checked_add(tender.amount)?;
+            }
+        }
+        if outstanding.is_zero() {
+            return Ok(());
+        }
+        Err(BillingError::RollbackFailed {
+            order_id: receipt.order_id,
+            outstanding,
+        })
+    }
+}
+
+/// The legs in order, e.g. `upi:₹100.00 + card*1`.
+impl fmt::Display for SplitTender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, (adapter, allocation)) in self.legs.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(" + ")?;
+            }
+            match allocation {
+                Allocation::Amount(amount) => write!(f, "{}:{}", adapter.name(), amount)?,
+                Allocation::Share(parts) => write!(f, "{}*{}", adapter.name(), parts)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use crate::billing::RefundReceipt;
+    use crate::patterns::UpiPayment;
+    use crate::pizza::{Farmhouse, Margherita};
+
+    fn upi(success_rate: f64) -> UpiPayment {
+        UpiPayment::with_success_rate("asha@upi", success_rate).with_delay(0)
+    }
+
+    /// Remembers what it charged and refunded; `decline` fails its charges
+    /// and `stuck` its refunds.
+    #[derive(Default)]
+    struct Recorder {
+        decline: bool,
+        stuck: bool,
+        charged: Mutex<Vec<Money>>,
+        refunded: Mutex<Vec<Money>>,
+    }
+
+    impl PaymentAdapter for Arc<Recorder> {
+        fn name(&self) -> &str {
+            "recorder"
+        }
+
+        fn pay(&self, _order: &order) -> Result<PaymentReceipt, BillingError> {
+            unreachable!("split legs are only charged")
+        }
+
+        fn charge(&self, _receipt: &PaymentReceipt, amount: Money) -> Result<(), BillingError> {
+            if self.decline {
+                return Err(BillingError::PaymentDeclined("test".into()));
+            }
+            self.charged.lock().unwrap().push(amount);
+            Ok(())
+        }
+
+        fn refund(&self, receipt: &PaymentReceipt, amount: Money) -> Result<RefundReceipt, BillingError> {
+            if self.stuck {
+                return Err(BillingError::PaymentFailed("gateway down".into()));
+            }
+            self.refunded.lock().unwrap().push(amount);
+            BillingEngine::new().quote_refund(receipt, amount)
+        }
+    }
+
+    fn recorder(decline: bool, stuck: bool) -> Arc<Recorder> {
+        Arc::new(Recorder { decline, stuck, ..Recorder::default() })
+    }
+
+    #[test]
+    fn allocations_must_cover_the_bill() {
+        let due = Money::inr(126);
+        let split = |legs: Vec<Allocation>| {
+            legs.into_iter()
+                .fold(SplitTender::new(BillingEngine::new()), |s, a| s.with_leg(upi(1.0), a))
+                .allocate(due)
+        };
+
+        let thirds = split(vec![Allocation::Share(1), Allocation::Share(1), Allocation::Share(1)]).unwrap();
+        assert_eq!(thirds, vec![Money::inr(42), Money::inr(42), Money::inr(42)]);
+        let uneven = split(vec![Allocation::Amount(Money::inr(26)), Allocation::Share(1), Allocation::Share(2)]).unwrap();
+        assert_eq!(uneven, vec![Money::inr(26), Money::parse("33.33", due.currency()).unwrap(), Money::parse("66.67", due.currency()).unwrap()]);
+
+        assert!(matches!(split(vec![Allocation::Amount(Money::inr(100))]), Err(BillingError::SplitMismatch { .. })));
+        assert!(matches!(
+            split(vec![Allocation::Amount(Money::inr(126)), Allocation::Share(1)]),
+            Err(BillingError::InvalidSplitAmount(_))
+        ));
+        assert!(matches!(split(vec![]), Err(BillingError::SplitMismatch { .. })));
+    }
+
+    #[test]
+    fn failed_leg_refunds_the_legs_already_charged() {
+        let order = order::new(vec![Box::new(Margherita::new()), Box::new(Farmhouse::new())]);
+        let (first, second) = (recorder(false, false), recorder(false, false));
+        let result = SplitTender::new(BillingEngine::new())
+            .with_leg(first.clone(), Allocation::Amount(Money::inr(100)))
+            .with_leg(second.clone(), Allocation::Share(1))
+            .with_leg(recorder(true, false), Allocation::Share(1))
+            .pay(&order);
+
+        assert!(matches!(result, Err(BillingError::PaymentDeclined(_))));
+        for leg in [first, second] {
+            assert_eq!(leg.charged.lock().unwrap().len(), 1);
+            assert_eq!(*leg.refunded.lock().unwrap(), *leg.charged.lock().unwrap());
+        }
+    }
+
+    #[test]
+    fn unrefundable_legs_are_reported() {
+        let order = order::new(vec![Box::new(Margherita::new()), Box::new(Farmhouse::new())]);
+        let refunded = recorder(false, false);
+        let result = SplitTender::new(BillingEngine::new())
+            .with_leg(recorder(false, true), Allocation::Amount(Money::inr(100)))
+            .with_leg(refunded.clone(), Allocation::Amount(Money::inr(50)))
+            .with_leg(recorder(true, false), Allocation::Share(1))
+            .pay(&order);
+
+        assert!(matches!(
+            result,
+            Err(BillingError::RollbackFailed { outstanding, .. }) if outstanding == Money::inr(100)
+        ));
+        assert_eq!(*refunded.refunded.lock().unwrap(), vec![Money::inr(50)]);
+    }
+}
@@ -2,17 +2,75 @@
 // Declare internal structure privately
 mod base;
 mod crust;
+mod nutrition;
+mod placement;
+mod rules;
+mod size;
+mod spec;
 mod toppings;
 
 // Public re-exports
-pub use base::{Margherita, Farmhouse};
-pub use crust::{ThinCrust, CheeseBurst};
-pub use toppings::{Cheese, Olives, Jalapenos};
+pub use base::{Margherita, Farmhouse, NamedBase};
+pub use crust::{ThinCrust, CheeseBurst, NamedCrust};
+pub use nutrition::{Allergen, Diet, Nutrition, NutritionFilter};
+pub use placement::Placement;
+pub use rules::PizzaRules;
+pub use size::{PriceMatrix, Size};
+pub use spec::{PizzaPart, PizzaSpec};
+pub use toppings::{Cheese, Olives, Jalapenos, NamedTopping};
+
+use crate::billing::TaxCategory;
+use crate::errors::MoneyError;
+use crate::money::Money;
 
 /// Trait for all pizzas.
 pub trait Pizza: Send + Sync {
     fn description(&self) -> String;
-    fn cost(&self) -> f32;
+
+    /// Price of the pizza with everything on it. Fails if the parts'
+    /// prices cannot be added up (mixed currencies or overflow).
+    fn cost(&self) -> Result<Money, MoneyError>;
+
+    /// Tax category the item is billed under.
+    fn tax_category(&self) -> TaxCategory {
+        TaxCategory::Food
+    }
+
+    /// Size the pizza is baked in; decorators report their base's size.
+    fn size(&self) -> Size {
+        Size::default()
+    }
+
+    /// Structured view of the pizza.
+    ///
+    /// Built-in bases, crusts and toppings fill in their own part. The
+    /// default treats the whole pizza as a single base, which is all an
+    /// opaque implementation can offer; a cost that cannot be worked out
+    /// is given as zero.
+    fn spec(&self) -> PizzaSpec {
+        PizzaSpec::new(self.description(), self.cost().unwrap_or_default())
+            .with_size(self.size())
+            .with_category(self.tax_category())
+    }
+
+    /// Calories, allergens and dietary flags, accumulated over every part.
+    /// `None` when any part has no facts.
+    fn nutrition(&self) -> Option<Nutrition> {
+        self.spec().nutrition()
+    }
+
+    /// Names of every crust in the chain, innermost first.
+    ///
+    /// [`spec`](Self::spec) keeps only the outermost crust; this lets
+    /// [`PizzaRules`] spot crusts stacked on crusts.
+    fn crusts(&self) -> Vec<String> {
+        self.spec().crust.into_iter().map(|c| c.name).collect()
+    }
+
+    /// Toppings on this pizza with the price each one added, innermost first.
+    fn topping_charges(&self) -> Vec<(String, Money)> {
+        self.spec().toppings.into_iter().map(|t| (t.name, t.price)).collect()
+    }
     
     fn clone_box(&self) -> Box<dyn Pizza>;
 }
@@ -37,8 +95,8 @@ mod tests {
             "Test Pizza".to_string()
         }
 
-        fn cost(&self) -> f32 {
-            100.0
+        fn cost(&self) -> Result<Money, MoneyError> {
+            Ok(Money::inr(100))
         }
         fn clone_box(&self) -> Box<dyn crate::pizza::Pizza> {
         Box::new(self.clone())
@@ -50,15 +108,15 @@ mod tests {
     #[test]
     fn margherita_description_and_cost() {
         let p = crate::pizza::Margherita::new();
-        assert_eq!(p.description(), "Margherita");
-        assert_eq!(p.cost(), 120.0);
+        assert_eq!(p.description(), "Medium Margherita");
+        assert_eq!(p.cost().unwrap(), Money::inr(120));
     }
 
     #[test]
     fn farmhouse_description_and_cost() {
         let p = crate::pizza::Farmhouse::new();
-        assert_eq!(p.description(), "Farmhouse");
-        assert_eq!(p.cost(), 150.0);
+        assert_eq!(p.description(), "Medium Farmhouse");
+        assert_eq!(p.cost().unwrap(), Money::inr(150));
     }
 
     // ---- Decorator tests ----
@@ -67,20 +125,40 @@ mod tests {
     fn cheese_decorator_adds_cost() {
         let p = Cheese::new(Box::new(TestPizza));
         assert_eq!(p.description(), "Test Pizza + Cheese");
-        assert_eq!(p.cost(), 110.0); // +10
+        assert_eq!(p.cost().unwrap(), Money::inr(110)); // +10
     }
 
     #[test]
     fn olives_decorator_adds_cost() {
         let p = Olives::new(Box::new(TestPizza));
         assert_eq!(p.description(), "Test Pizza + Olives");
-        assert_eq!(p.cost(), 115.0); // +15
+        assert_eq!(p.cost().unwrap(), Money::inr(115)); // +15
     }
 
     #[test]
     fn multiple_decorators_accumulate() {
         let p = Cheese::new(Box::new(Olives::new(Box::new(TestPizza))));
         assert_eq!(p.description(), "Test Pizza + Olives + Cheese");
-        assert_eq!(p.cost(), 125.0); // 100 + 15 + 10
+        assert_eq!(p.cost().unwrap(), Money::inr(125)); // 100 + 15 + 10
+    }
+
+    // ---- Size tests ----
+
+    #[test]
+    fn bases_crusts_and_toppings_scale_with_size() {
+        let p = ThinCrust::new(Cheese::new(Box::new(Margherita::new().with_size(Size::Large))));
+        assert_eq!(p.description(), "Large Margherita + Cheese, Thin Crust");
+        assert_eq!(p.size(), Size::Large);
+        assert_eq!(p.cost().unwrap(), Money::inr(180 + 15 + 30));
+
+        let small = Olives::new(Box::new(Farmhouse::new().with_size(Size::Small)));
+        assert_eq!(small.cost().unwrap(), Money::from_minor(11_250 + 1_125, crate::money::Currency::Inr));
+    }
+
+    #[test]
+    fn explicit_price_matrix_overrides_scaling() {
+        let prices = PriceMatrix::scaled(Money::inr(10)).unwrap().with_price(Size::Family, Money::inr(12));
+        let p = Cheese::with_prices(Box::new(Farmhouse::new().with_size(Size::Family)), prices);
+        assert_eq!(p.cost().unwrap(), Money::inr(300 + 12));
     }
 }
@@ -1,28 +1,63 @@
-use crate::pizza::Pizza;
+use crate::billing::TaxCategory;
+use crate::errors::MoneyError;
+use crate::money::Money;
+use crate::pizza::{Allergen, Diet, Nutrition, Pizza, PizzaSpec, Placement, PriceMatrix, Size};
 
 /// Margherita Pizza
 #[derive(Clone)]
 pub struct Margherita {
-    price: f32,
+    prices: PriceMatrix,
+    size: Size,
 }
 
 impl Margherita {
+    /// Medium, ₹120; other sizes scale from that.
     pub fn new() -> Self {
-        Self { price: 120.0 }
+        Self::with_price(Money::inr(120))
     }
 
-    pub fn with_price(price: f32) -> Self {
-        Self { price }
+    /// Custom medium price; other sizes scale from it.
+    ///
+    /// # Panics
+    /// Panics if a scaled price overflows.
+    pub fn with_price(price: Money) -> Self {
+        Self::with_prices(PriceMatrix::scaled(price).expect("scaled price overflow"))
+    }
+
+    /// Explicit price for every size.
+    pub fn with_prices(prices: PriceMatrix) -> Self {
+        Self { prices, size: Size::default() }
+    }
+
+    pub fn with_size(mut self, size: Size) -> Self {
+        self.size = size;
+        self
+    }
+}
+
+impl Default for Margherita {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl Pizza for Margherita {
     fn description(&self) -> String {
-        "Margherita".to_string()
+        format!("{} Margherita", self.size)
     }
 
-    fn cost(&self) -> f32 {
-        self.price
+    fn cost(&self) -> Result<Money, MoneyError> {
+        Ok(self.prices.price(self.size))
+    }
+
+    fn size(&self) -> Size {
+        self.size
+    }
+
+    fn spec(&self) -> PizzaSpec {
+        PizzaSpec::new("Margherita", self.prices.price(self.size))
+            .with_size(self.size)
+            .with_base_nutrition(margherita_facts().portion(self.size, Placement::Whole))
     }
 
     fn clone_box(&self) -> Box<dyn crate::pizza::Pizza> {
@@ -30,29 +65,149 @@ impl Pizza for Margherita {
     }
 }
 
+/// Medium Margherita: 800 kcal, vegetarian and Jain.
+fn margherita_facts() -> Nutrition {
+    Nutrition::new(800)
+        .with_allergen(Allergen::Gluten)
+        .with_allergen(Allergen::Dairy)
+        .with_diet(Diet::Vegetarian)
+        .with_diet(Diet::Jain)
+}
+
 /// Farmhouse Pizza
 #[derive(Clone)]
 pub struct Farmhouse {
-    price: f32,
+    prices: PriceMatrix,
+    size: Size,
 }
 
 impl Farmhouse {
+    /// Medium, ₹150; other sizes scale from that.
     pub fn new() -> Self {
-        Self { price: 150.0 }
+        Self::with_price(Money::inr(150))
     }
 
-    pub fn with_price(price: f32) -> Self {
-        Self { price }
+    /// Custom medium price; other sizes scale from it.
+    ///
+    /// # Panics
+    /// Panics if a scaled price overflows.
+    pub fn with_price(price: Money) -> Self {
+        Self::with_prices(PriceMatrix::scaled(price).expect("scaled price overflow"))
+    }
+
+    /// Explicit price for every size.
+    pub fn with_prices(prices: PriceMatrix) -> Self {
+        Self { prices, size: Size::default() }
+    }
+
+    pub fn with_size(mut self, size: Size) -> Self {
+        self.size = size;
+        self
+    }
+}
+
+impl Default for Farmhouse {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl Pizza for Farmhouse {
     fn description(&self) -> String {
-        "Farmhouse".to_string()
+        format!("{} Farmhouse", self.size)
+    }
+
+    fn cost(&self) -> Result<Money, MoneyError> {
+        Ok(self.prices.price(self.size))
+    }
+
+    fn size(&self) -> Size {
+        self.size
+    }
+
+    fn spec(&self) -> PizzaSpec {
+        PizzaSpec::new("Farmhouse", self.prices.price(self.size))
+            .with_size(self.size)
+            .with_base_nutrition(farmhouse_facts().portion(self.size, Placement::Whole))
+    }
+
+    fn clone_box(&self) -> Box<dyn crate::pizza::Pizza> {
+        Box::new(self.clone())
+    }
+}
+
+/// Medium Farmhouse: 950 kcal; the onion rules out Jain.
+fn farmhouse_facts() -> Nutrition {
+    Nutrition::new(950)
+        .with_allergen(Allergen::Gluten)
+        .with_allergen(Allergen::Dairy)
+        .with_diet(Diet::Vegetarian)
+}
+
+/// Base pizza defined at runtime, e.g. from a menu catalog
+///
+/// `price` is for the pizza's own size; the catalog resolves it.
+#[derive(Clone)]
+pub struct NamedBase {
+    name: String,
+    price: Money,
+    size: Size,
+    category: TaxCategory,
+    nutrition: Option<Nutrition>,
+}
+
+impl NamedBase {
+    pub fn new(name: impl Into<String>, price: Money) -> Self {
+        Self {
+            name: name.into(),
+            price,
+            size: Size::default(),
+            category: TaxCategory::Food,
+            nutrition: None,
+        }
+    }
+
+    pub fn with_size(mut self, size: Size) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// Bill the item under a different tax category
+    pub fn with_category(mut self, category: TaxCategory) -> Self {
+        self.category = category;
+        self
+    }
+
+    /// Label facts for the base at its size
+    pub fn with_nutrition(mut self, nutrition: impl Into<Option<Nutrition>>) -> Self {
+        self.nutrition = nutrition.into();
+        self
+    }
+}
+
+impl Pizza for NamedBase {
+    fn description(&self) -> String {
+        format!("{} {}", self.size, self.name)
+    }
+
+    fn cost(&self) -> Result<Money, MoneyError> {
+        Ok(self.price)
+    }
+
+    fn tax_category(&self) -> TaxCategory {
+        self.category
+    }
+
+    fn size(&self) -> Size {
+        self.size
     }
 
-    fn cost(&self) -> f32 {
-        self.price
+    fn spec(&self) -> PizzaSpec {
+        let mut spec = PizzaSpec::new(&self.name, self.price)
+            .with_size(self.size)
+            .with_category(self.category);
+        spec.base.nutrition = self.nutrition.clone();
+        spec
     }
 
     fn clone_box(&self) -> Box<dyn crate::pizza::Pizza> {
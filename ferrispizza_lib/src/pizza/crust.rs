@@ -1,17 +1,30 @@
-use super::super::pizza::Pizza;
-use super::base::{Margherita, Farmhouse};
+use super::super::pizza::{Allergen, Diet, Nutrition, Pizza, PizzaPart, PizzaSpec, Placement, PriceMatrix, Size};
+use crate::billing::TaxCategory;
+use crate::errors::MoneyError;
+use crate::money::Money;
 
 #[derive(Clone)]
 pub struct ThinCrust {
     pizza: Box<dyn Pizza>,
+    prices: PriceMatrix,
 }
 
 impl ThinCrust {
+    /// +20 on a medium, scaled by size
     pub fn new(pizza: impl Pizza + 'static) -> Self {
+        Self::with_prices(pizza, PriceMatrix::scaled(Money::inr(20)).expect("built-in price"))
+    }
+
+    pub fn with_prices(pizza: impl Pizza + 'static, prices: PriceMatrix) -> Self {
         Self {
             pizza: Box::new(pizza),
+            prices,
         }
     }
+
+    fn price(&self) -> Money {
+        self.prices.price(self.pizza.size())
+    }
 }
 
 impl Pizza for ThinCrust {
@@ -19,8 +32,24 @@ impl Pizza for ThinCrust {
         format!("{}, Thin Crust", self.pizza.description())
     }
 
-    fn cost(&self) -> f32 {
-        self.pizza.cost() + 20.0
+    fn cost(&self) -> Result<Money, MoneyError> {
+        self.pizza.cost()?.checked_add(self.price())
+    }
+
+    fn size(&self) -> Size {
+        self.pizza.size()
+    }
+
+    fn spec(&self) -> PizzaSpec {
+        let part = PizzaPart::new("Thin Crust", self.price())
+            .with_nutrition(thin_crust_facts().portion(self.pizza.size(), Placement::Whole));
+        self.pizza.spec().with_crust_part(part)
+    }
+
+    fn crusts(&self) -> Vec<String> {
+        let mut crusts = self.pizza.crusts();
+        crusts.push("Thin Crust".to_string());
+        crusts
     }
 
     fn clone_box(&self) -> Box<dyn crate::pizza::Pizza> {
@@ -28,17 +57,37 @@ impl Pizza for ThinCrust {
     }
 }
 
+/// Medium crust: 120 kcal
+fn thin_crust_facts() -> Nutrition {
+    Nutrition::new(120)
+        .with_allergen(Allergen::Gluten)
+        .with_diet(Diet::Vegetarian)
+        .with_diet(Diet::Vegan)
+        .with_diet(Diet::Jain)
+}
+
 #[derive(Clone)]
 pub struct CheeseBurst {
     pizza: Box<dyn Pizza>,
+    prices: PriceMatrix,
 }
 
 impl CheeseBurst {
+    /// +50 on a medium, scaled by size
     pub fn new(pizza: impl Pizza + 'static) -> Self {
+        Self::with_prices(pizza, PriceMatrix::scaled(Money::inr(50)).expect("built-in price"))
+    }
+
+    pub fn with_prices(pizza: impl Pizza + 'static, prices: PriceMatrix) -> Self {
         Self {
             pizza: Box::new(pizza),
+            prices,
         }
     }
+
+    fn price(&self) -> Money {
+        self.prices.price(self.pizza.size())
+    }
 }
 
 impl Pizza for CheeseBurst {
@@ -46,29 +95,129 @@ impl Pizza for CheeseBurst {
         format!("{}, CheeseBurst Crust", self.pizza.description())
     }
 
-    fn cost(&self) -> f32 {
-        self.pizza.cost() + 50.0
+    fn cost(&self) -> Result<Money, MoneyError> {
+        self.pizza.cost()?.checked_add(self.price())
+    }
+
+    fn size(&self) -> Size {
+        self.pizza.size()
+    }
+    fn spec(&self) -> PizzaSpec {
+        let part = PizzaPart::new("CheeseBurst Crust", self.price())
+            .with_nutrition(cheese_burst_facts().portion(self.pizza.size(), Placement::Whole));
+        self.pizza.spec().with_crust_part(part)
     }
+
+    fn crusts(&self) -> Vec<String> {
+        let mut crusts = self.pizza.crusts();
+        crusts.push("CheeseBurst Crust".to_string());
+        crusts
+    }
+
     fn clone_box(&self) -> Box<dyn crate::pizza::Pizza> {
         Box::new(self.clone())
     }
 }
 
+/// Medium crust: 300 kcal
+fn cheese_burst_facts() -> Nutrition {
+    Nutrition::new(300)
+        .with_allergen(Allergen::Gluten)
+        .with_allergen(Allergen::Dairy)
+        .with_diet(Diet::Vegetarian)
+        .with_diet(Diet::Jain)
+}
 
-#[test]
-fn test_thin_crust_margherita() {
-    let pizza = Margherita::new();
-    let crust = ThinCrust::new(pizza);
+/// Crust defined at runtime, e.g. from a menu catalog
+///
+/// `price` is for the size of the pizza underneath; the catalog resolves it.
+#[derive(Clone)]
+pub struct NamedCrust {
+    pizza: Box<dyn Pizza>,
+    name: String,
+    price: Money,
+    nutrition: Option<Nutrition>,
+}
 
-    assert_eq!(crust.description(), "Margherita, Thin Crust");
-    assert_eq!(crust.cost(), 100.0 + 40.0);
+impl NamedCrust {
+    pub fn new(pizza: Box<dyn Pizza>, name: impl Into<String>, price: Money) -> Self {
+        Self {
+            pizza,
+            name: name.into(),
+            price,
+            nutrition: None,
+        }
+    }
+
+    /// Label facts for the crust at the pizza's size
+    pub fn with_nutrition(mut self, nutrition: impl Into<Option<Nutrition>>) -> Self {
+        self.nutrition = nutrition.into();
+        self
+    }
+}
+
+impl Pizza for NamedCrust {
+    fn description(&self) -> String {
+        format!("{}, {}", self.pizza.description(), self.name)
+    }
+
+    fn cost(&self) -> Result<Money, MoneyError> {
+        self.pizza.cost()?.checked_add(self.price)
+    }
+
+    fn tax_category(&self) -> TaxCategory {
+        self.pizza.tax_category()
+    }
+
+    fn size(&self) -> Size {
+        self.pizza.size()
+    }
+
+    fn spec(&self) -> PizzaSpec {
+        let mut part = PizzaPart::new(&self.name, self.price);
+        part.nutrition = self.nutrition.clone();
+        self.pizza.spec().with_crust_part(part)
+    }
+
+    fn crusts(&self) -> Vec<String> {
+        let mut crusts = self.pizza.crusts();
+        crusts.push(self.name.clone());
+        crusts
+    }
+
+    fn clone_box(&self) -> Box<dyn crate::pizza::Pizza> {
+        Box::new(self.clone())
+    }
 }
 
-#[test]
-fn test_thick_crust_farmhouse() {
-    let pizza = Farmhouse::new();
-    let crust = CheeseBurst::new(pizza);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pizza::{Margherita, Farmhouse};
+
+    #[test]
+    fn test_thin_crust_margherita() {
+        let pizza = Margherita::new();
+        let crust = ThinCrust::new(pizza);
 
-    assert_eq!(crust.description(), "Farmhouse, CheeseBurst Crust");
-    assert_eq!(crust.cost(), 150.0 + 50.0);
+        assert_eq!(crust.description(), "Medium Margherita, Thin Crust");
+        assert_eq!(crust.cost().unwrap(), Money::inr(120 + 20));
+    }
+
+    #[test]
+    fn test_thick_crust_farmhouse() {
+        let pizza = Farmhouse::new();
+        let crust = CheeseBurst::new(pizza);
+
+        assert_eq!(crust.description(), "Medium Farmhouse, CheeseBurst Crust");
+        assert_eq!(crust.cost().unwrap(), Money::inr(150 + 50));
+    }
+
+    #[test]
+    fn test_named_crust() {
+        let crust = NamedCrust::new(Box::new(Margherita::new()), "Pan Crust", Money::inr(30));
+
+        assert_eq!(crust.description(), "Medium Margherita, Pan Crust");
+        assert_eq!(crust.cost().unwrap(), Money::inr(150));
+    }
 }
@@ -0,0 +1,238 @@
+//! Nutrition, allergen and dietary labelling.
+//!
+//! Each base, crust and topping may carry [`Nutrition`] facts for a whole
+//! medium portion. A pizza's facts accumulate through its parts: calories
+//! add up, allergens are the union and dietary flags the intersection. If
+//! any part has no facts the pizza has none either, so a label is never
+//! guessed.
+//!
+//! # Example
+//! ```
+//! use ferrispizza_lib::pizza::{Allergen, Cheese, Diet, Margherita, Olives, Pizza};
+//!
+//! let pizza = Olives::new(Box::new(Cheese::new(Box::new(Margherita::new()))));
+//! let facts = pizza.nutrition().unwrap();
+//! assert_eq!(facts.calories, 800 + 110 + 40);
+//! assert!(facts.contains(Allergen::Dairy));
+//! assert!(facts.is(Diet::Vegetarian));
+//! assert!(!facts.is(Diet::Vegan));
+//! ```
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Placement, Size};
+
+/// Ingredients that must be declared on a label.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Allergen {
+    Gluten,
+    Dairy,
+    Nuts,
+}
+
+impl Allergen {
+    pub const ALL: [Allergen; 3] = [Allergen::Gluten, Allergen::Dairy, Allergen::Nuts];
+
+    pub fn parse(text: &str) -> Option<Allergen> {
+        Allergen::ALL
+            .into_iter()
+            .find(|a| a.to_string().eq_ignore_ascii_case(text))
+    }
+}
+
+impl fmt::Display for Allergen {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Allergen::Gluten => "gluten",
+            Allergen::Dairy => "dairy",
+            Allergen::Nuts => "nuts",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Diets a part or pizza is suitable for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Diet {
+    Vegetarian,
+    Vegan,
+    /// No meat, eggs or root vegetables (onion, garlic, potato).
+    Jain,
+}
+
+impl Diet {
+    pub const ALL: [Diet; 3] = [Diet::Vegetarian, Diet::Vegan, Diet::Jain];
+
+    pub fn parse(text: &str) -> Option<Diet> {
+        Diet::ALL.into_iter().find(|d| d.to_string().eq_ignore_ascii_case(text))
+    }
+}
+
+impl fmt::Display for Diet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Diet::Vegetarian => "vegetarian",
+            Diet::Vegan => "vegan",
+            Diet::Jain => "jain",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Label facts for one part or a whole pizza.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Nutrition {
+    /// Kilocalories.
+    #[serde(default)]
+    pub calories: u32,
+    #[serde(default)]
+    pub allergens: BTreeSet<Allergen>,
+    #[serde(default)]
+    pub diets: BTreeSet<Diet>,
+}
+
+impl Nutrition {
+    pub fn new(calories: u32) -> Self {
+        Self {
+            calories,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_allergen(mut self, allergen: Allergen) -> Self {
+        self.allergens.insert(allergen);
+        self
+    }
+
+    pub fn with_diet(mut self, diet: Diet) -> Self {
+        self.diets.insert(diet);
+        self
+    }
+
+    pub fn contains(&self, allergen: Allergen) -> bool {
+        self.allergens.contains(&allergen)
+    }
+
+    pub fn is(&self, diet: Diet) -> bool {
+        self.diets.contains(&diet)
+    }
+
+    /// Facts for `self` and `other` on the same pizza.
+    pub fn combine(&self, other: &Nutrition) -> Nutrition {
+        Nutrition {
+            calories: self.calories.saturating_add(other.calories),
+            allergens: self.allergens.union(&other.allergens).copied().collect(),
+            diets: self.diets.intersection(&other.diets).copied().collect(),
+        }
+    }
+
+    /// Facts for a part given per whole medium portion, served at `size`
+    /// and `placement`. Calories scale like prices and round up.
+    pub fn portion(&self, size: Size, placement: Placement) -> Nutrition {
+        let share = if placement.is_whole() { 1 } else { 2 };
+        let calories = (u64::from(self.calories) * u64::from(size.scale_bps())).div_ceil(10_000 * share);
+        Nutrition {
+            calories: u32::try_from(calories).unwrap_or(u32::MAX),
+            ..self.clone()
+        }
+    }
+
+    /// One line for a menu or receipt: "950 kcal | contains gluten, dairy | vegetarian".
+    pub fn label(&self) -> String {
+        let allergens = if self.allergens.is_empty() {
+            "no allergens".to_string()
+        } else {
+            format!("contains {}", join(&self.allergens))
+        };
+        let diets = if self.diets.is_empty() {
+            "no dietary flags".to_string()
+        } else {
+            join(&self.diets)
+        };
+        format!("{} kcal | {} | {}", self.calories, allergens, diets)
+    }
+}
+
+fn join<T: fmt::Display>(items: &BTreeSet<T>) -> String {
+    items.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+}
+
+/// Allergens to avoid and diets to satisfy, e.g. "dairy-free and vegan".
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NutritionFilter {
+    without: BTreeSet<Allergen>,
+    diets: BTreeSet<Diet>,
+}
+
+impl NutritionFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn without(mut self, allergen: Allergen) -> Self {
+        self.without.insert(allergen);
+        self
+    }
+
+    pub fn suitable_for(mut self, diet: Diet) -> Self {
+        self.diets.insert(diet);
+        self
+    }
+
+    /// Parse space-separated terms: a diet (`vegan`) or `no-<allergen>`
+    /// (`no-dairy`). Returns `None` on an unknown term.
+    pub fn parse(text: &str) -> Option<Self> {
+        text.split_whitespace().try_fold(Self::new(), |filter, term| {
+            match term.strip_prefix("no-") {
+                Some(allergen) => Some(filter.without(Allergen::parse(allergen)?)),
+                None => Some(filter.suitable_for(Diet::parse(term)?)),
+            }
+        })
+    }
+
+    /// Whether nothing is filtered out.
+    pub fn is_empty(&self) -> bool {
+        self.without.is_empty() && self.diets.is_empty()
+    }
+
+    /// Whether facts satisfy the filter. Unknown facts only pass an empty
+    /// filter.
+    pub fn matches(&self, facts: Option<&Nutrition>) -> bool {
+        match facts {
+            Some(facts) => {
+                self.without.iter().all(|a| !facts.contains(*a)) && self.diets.iter().all(|d| facts.is(*d))
+            }
+            None => self.is_empty(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn portions_scale_with_size_and_halves() {
+        let olives = Nutrition::new(45).with_diet(Diet::Vegan);
+        assert_eq!(olives.portion(Size::Large, Placement::Whole).calories, 68);
+        assert_eq!(olives.portion(Size::Medium, Placement::Left).calories, 23);
+        assert!(olives.portion(Size::Small, Placement::Right).is(Diet::Vegan));
+    }
+
+    #[test]
+    fn filter_parses_terms_and_rejects_unknown_facts() {
+        let filter = NutritionFilter::parse("no-dairy vegan").unwrap();
+        let vegan = Nutrition::new(10).with_diet(Diet::Vegan).with_allergen(Allergen::Gluten);
+
+        assert!(filter.matches(Some(&vegan)));
+        assert!(!filter.matches(Some(&vegan.clone().with_allergen(Allergen::Dairy))));
+        assert!(!filter.matches(None));
+        assert!(NutritionFilter::new().matches(None));
+        assert_eq!(NutritionFilter::parse("no-meat"), None);
+    }
+}
@@ -0,0 +1,64 @@
+//! Where a topping goes on the pizza.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::money::{Money, Rounding};
+
+/// Part of the pizza a topping covers.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Placement {
+    #[default]
+    Whole,
+    Left,
+    Right,
+}
+
+impl Placement {
+    /// Parse `whole`, `left` or `right`, or their first letter.
+    pub fn parse(text: &str) -> Option<Placement> {
+        match text.to_ascii_lowercase().as_str() {
+            "w" | "whole" => Some(Placement::Whole),
+            "l" | "left" => Some(Placement::Left),
+            "r" | "right" => Some(Placement::Right),
+            _ => None,
+        }
+    }
+
+    pub fn is_whole(self) -> bool {
+        self == Placement::Whole
+    }
+
+    /// `" (left half)"` after a half topping's name, nothing for whole ones.
+    pub(crate) fn suffix(self) -> String {
+        if self.is_whole() {
+            String::new()
+        } else {
+            format!(" ({})", self)
+        }
+    }
+
+    /// Charge for a topping whose whole-pizza price is `full`: half rate on
+    /// a half, rounded half-up to the minor unit.
+    pub fn charge(self, full: Money) -> Money {
+        match self {
+            Placement::Whole => full,
+            Placement::Left | Placement::Right => full
+                .apply_rate(1, 2, Rounding::HalfUp)
+                .expect("half of a price always fits"),
+        }
+    }
+}
+
+impl fmt::Display for Placement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Placement::Whole => "whole",
+            Placement::Left => "left half",
+            Placement::Right => "right half",
+        };
+        f.write_str(name)
+    }
+}
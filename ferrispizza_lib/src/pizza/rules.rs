@@ -0,0 +1,181 @@
+//! Validation rules for assembled pizzas.
+//!
+//! Decorators stack freely, so nothing in the types stops two crusts or a
+//! tower of cheese. [`PizzaRules`] checks a finished pizza and reports the
+//! first problem as an [`OrderError`]:
+//!
+//! - at most one crust,
+//! - at most `max_per_topping` of any topping (overridable per topping),
+//! - at most `max_toppings` toppings in total,
+//! - no two parts declared incompatible.
+//!
+//! Names are compared case-insensitively. A half topping counts as one.
+//!
+//! # Example
+//! ```
+//! use ferrispizza_lib::errors::OrderError;
+//! use ferrispizza_lib::pizza::{CheeseBurst, Margherita, PizzaRules, ThinCrust};
+//!
+//! let rules = PizzaRules::default();
+//! let pizza = ThinCrust::new(CheeseBurst::new(Margherita::new()));
+//! assert!(matches!(rules.validate(&pizza), Err(OrderError::MultipleCrusts { .. })));
+//! ```
+
+use std::collections::HashMap;
+
+use crate::errors::OrderError;
+use super::Pizza;
+
+/// Limits applied to every pizza before it goes on an order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PizzaRules {
+    max_per_topping: usize,
+    max_toppings: usize,
+    /// Per-topping overrides of `max_per_topping`, keyed by lowercase name.
+    limits: HashMap<String, usize>,
+    /// Pairs of lowercase part names that may not share a pizza.
+    incompatible: Vec<(String, String)>,
+}
+
+impl Default for PizzaRules {
+    /// Two of any topping, five toppings in total, nothing incompatible.
+    fn default() -> Self {
+        Self {
+            max_per_topping: 2,
+            max_toppings: 5,
+            limits: HashMap::new(),
+            incompatible: Vec::new(),
+        }
+    }
+}
+
+impl PizzaRules {
+    pub fn with_max_per_topping(mut self, max: usize) -> Self {
+        self.max_per_topping = max;
+        self
+    }
+
+    pub fn with_max_toppings(mut self, max: usize) -> Self {
+        self.max_toppings = max;
+        self
+    }
+
+    /// Allow `max` of one topping, overriding the per-topping default.
+    pub fn with_limit(mut self, topping: impl AsRef<str>, max: usize) -> Self {
+        self.limits.insert(topping.as_ref().to_lowercase(), max);
+        self
+    }
+
+    /// Forbid `first` and `second` on the same pizza. Either may be the
+    /// base, the crust or a topping.
+    pub fn with_incompatible(mut self, first: impl AsRef<str>, second: impl AsRef<str>) -> Self {
+        self.incompatible
+            .push((first.as_ref().to_lowercase(), second.as_ref().to_lowercase()));
+        self
+    }
+
+    /// Most of `topping` allowed on one pizza.
+    pub fn limit_for(&self, topping: &str) -> usize {
+        self.limits
+            .get(&topping.to_lowercase())
+            .copied()
+            .unwrap_or(self.max_per_topping)
+    }
+
+    /// Check `pizza` against every rule.
+    pub fn validate(&self, pizza: &dyn Pizza) -> Result<(), OrderError> {
+        let crusts = pizza.crusts();
+        if let [first, second, ..] = crusts.as_slice() {
+            return Err(OrderError::MultipleCrusts {
+                first: first.clone(),
+                second: second.clone(),
+            });
+        }
+
+        let spec = pizza.spec();
+        if spec.toppings.len() > self.max_toppings {
+            return Err(OrderError::TooManyToppings {
+                count: spec.toppings.len(),
+                max: self.max_toppings,
+            });
+        }
+
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for topping in &spec.toppings {
+            let count = counts.entry(topping.name.to_lowercase()).or_default();
+            *count += 1;
+            let max = self.limit_for(&topping.name);
+            if *count > max {
+                return Err(OrderError::ToppingLimit {
+                    topping: topping.name.clone(),
+                    max,
+                });
+            }
+        }
+
+        let parts: Vec<&str> = std::iter::once(&spec.base)
+            .chain(&spec.toppings)
+            .map(|p| p.name.as_str())
+            .chain(crusts.iter().map(String::as_str))
+            .collect();
+        let find = |name: &str| parts.iter().find(|p| p.eq_ignore_ascii_case(name));
+        for (a, b) in &self.incompatible {
+            if let (Some(first), Some(second)) = (find(a), find(b)) {
+                return Err(OrderError::IncompatibleItems {
+                    first: first.to_string(),
+                    second: second.to_string(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::money::Money;
+    use crate::pizza::{Cheese, Jalapenos, Margherita, Olives, Placement, PizzaSpec, ThinCrust};
+
+    #[test]
+    fn per_topping_and_total_limits() {
+        let rules = PizzaRules::default().with_limit("olives", 1);
+        let mut pizza: Box<dyn Pizza> = Box::new(Margherita::new());
+        for _ in 0..3 {
+            pizza = Box::new(Cheese::new(pizza));
+        }
+        assert!(matches!(
+            rules.validate(pizza.as_ref()),
+            Err(OrderError::ToppingLimit { max: 2, .. })
+        ));
+
+        let olives = Olives::new(Box::new(Olives::new(Box::new(Margherita::new())).with_placement(Placement::Left)));
+        assert!(matches!(
+            rules.validate(&olives),
+            Err(OrderError::ToppingLimit { ref topping, max: 1 }) if topping == "Olives"
+        ));
+
+        let spec = ["A", "B", "C", "D", "E", "F"]
+            .iter()
+            .fold(PizzaSpec::new("Margherita", Money::inr(120)), |s, t| s.with_topping(*t, Money::inr(5)));
+        assert!(matches!(
+            rules.validate(&spec),
+            Err(OrderError::TooManyToppings { count: 6, max: 5 })
+        ));
+    }
+
+    #[test]
+    fn incompatible_parts_are_rejected() {
+        let rules = PizzaRules::default().with_incompatible("thin crust", "jalapenos");
+        let ok = Jalapenos::new(Box::new(Margherita::new()));
+        assert!(rules.validate(&ok).is_ok());
+
+        let clash = ThinCrust::new(ok);
+        assert!(matches!(
+            rules.validate(&clash),
+            Err(OrderError::IncompatibleItems { ref first, ref second })
+                if first == "Thin Crust" && second == "Jalapenos"
+        ));
+    }
+}
@@ -0,0 +1,134 @@
+//! Pizza sizes and per-size prices.
+//!
+//! Every base, crust and topping carries a [`PriceMatrix`]: one price per
+//! [`Size`]. [`PriceMatrix::scaled`] fills the matrix from the medium price
+//! using each size's standard scale; individual cells can be overridden.
+//!
+//! # Example
+//! ```
+//! use ferrispizza_lib::money::Money;
+//! use ferrispizza_lib::pizza::{PriceMatrix, Size};
+//!
+//! let prices = PriceMatrix::scaled(Money::inr(120)).unwrap().with_price(Size::Family, Money::inr(220));
+//! assert_eq!(prices.price(Size::Small), Money::inr(90));
+//! assert_eq!(prices.price(Size::Large), Money::inr(180));
+//! assert_eq!(prices.price(Size::Family), Money::inr(220));
+//! ```
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::money::{Money, Rounding};
+use crate::errors::MoneyError;
+
+/// Size a pizza is baked in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Size {
+    Small,
+    #[default]
+    Medium,
+    Large,
+    Family,
+}
+
+impl Size {
+    pub const ALL: [Size; 4] = [Size::Small, Size::Medium, Size::Large, Size::Family];
+
+    /// Price relative to medium, in basis points (medium = 10 000).
+    pub fn scale_bps(self) -> u32 {
+        match self {
+            Size::Small => 7_500,
+            Size::Medium => 10_000,
+            Size::Large => 15_000,
+            Size::Family => 20_000,
+        }
+    }
+
+    /// Parse a size name or its first letter (`"large"`, `"L"`).
+    pub fn parse(text: &str) -> Option<Size> {
+        match text.to_ascii_lowercase().as_str() {
+            "s" | "small" => Some(Size::Small),
+            "m" | "medium" => Some(Size::Medium),
+            "l" | "large" => Some(Size::Large),
+            "f" | "family" => Some(Size::Family),
+            _ => None,
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl fmt::Display for Size {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Size::Small => "Small",
+            Size::Medium => "Medium",
+            Size::Large => "Large",
+            Size::Family => "Family",
+        };
+        f.write_str(name)
+    }
+}
+
+/// One price per size.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PriceMatrix {
+    prices: [Money; 4],
+}
+
+impl PriceMatrix {
+    pub fn new(small: Money, medium: Money, large: Money, family: Money) -> Self {
+        Self {
+            prices: [small, medium, large, family],
+        }
+    }
+
+    /// Same price at every size.
+    pub fn flat(price: Money) -> Self {
+        Self { prices: [price; 4] }
+    }
+
+    /// Prices derived from the medium price with [`Size::scale_bps`],
+    /// rounded half-up to the minor unit.
+    ///
+    /// Fails if a scaled price overflows.
+    pub fn scaled(medium: Money) -> Result<Self, MoneyError> {
+        let at = |size: Size| medium.percent_bps(size.scale_bps(), Rounding::HalfUp);
+        Ok(Self::new(at(Size::Small)?, medium, at(Size::Large)?, at(Size::Family)?))
+    }
+
+    /// Override the price for one size.
+    pub fn with_price(mut self, size: Size, price: Money) -> Self {
+        self.prices[size.index()] = price;
+        self
+    }
+
+    pub fn price(&self, size: Size) -> Money {
+        self.prices[size.index()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::money::Currency;
+
+    #[test]
+    fn scaled_rounds_to_minor_units() {
+        let prices = PriceMatrix::scaled(Money::from_minor(1_001, Currency::Inr)).unwrap();
+        assert_eq!(prices.price(Size::Small), Money::from_minor(751, Currency::Inr));
+        assert_eq!(prices.price(Size::Family), Money::from_minor(2_002, Currency::Inr));
+    }
+
+    #[test]
+    fn parse_names_and_letters() {
+        assert_eq!(Size::parse("L"), Some(Size::Large));
+        assert_eq!(Size::parse("family"), Some(Size::Family));
+        assert_eq!(Size::parse("huge"), None);
+        assert_eq!(Size::ALL.map(|s| s.to_string()), ["Small", "Medium", "Large", "Family"]);
+    }
+}
@@ -0,0 +1,341 @@
+//! Structured pizza specification.
+//!
+//! A decorator chain only exposes a description and a price. [`PizzaSpec`]
+//! keeps the parts — base, crust, toppings in order, size and notes — so
+//! billing, the kitchen and the CLI can look inside a pizza, edit it and
+//! store it.
+//!
+//! Every built-in pizza reports its spec through [`Pizza::spec`], and
+//! [`PizzaSpec::build`] turns a spec back into a decorator chain. The spec
+//! is also a [`Pizza`] itself and bills exactly like the chain it came from.
+//!
+//! # Example
+//! ```
+//! use ferrispizza_lib::money::Money;
+//! use ferrispizza_lib::pizza::{Cheese, Margherita, Pizza, PizzaSpec, ThinCrust};
+//!
+//! let pizza = ThinCrust::new(Cheese::new(Box::new(Margherita::new())));
+//! let spec = pizza.spec();
+//! assert_eq!(spec.base.name, "Margherita");
+//! assert_eq!(spec.topping_names(), vec!["Cheese"]);
+//!
+//! // Round-trips through serde and back into a chain
+//! let json = serde_json::to_string(&spec).unwrap();
+//! let rebuilt = serde_json::from_str::<PizzaSpec>(&json).unwrap().build();
+//! assert_eq!(rebuilt.description(), pizza.description());
+//! assert_eq!(rebuilt.cost().unwrap(), Money::inr(150));
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use crate::billing::TaxCategory;
+use crate::errors::MoneyError;
+use crate::money::Money;
+use super::{NamedBase, NamedCrust, NamedTopping, Nutrition, Pizza, Placement, Size};
+
+/// One component of a pizza and the price it adds.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PizzaPart {
+    pub name: String,
+    /// Amount charged, after size and placement.
+    pub price: Money,
+    /// Only toppings go on a half; bases and crusts are always whole.
+    #[serde(default)]
+    pub placement: Placement,
+    /// Label facts for this part as served, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nutrition: Option<Nutrition>,
+}
+
+impl PizzaPart {
+    pub fn new(name: impl Into<String>, price: Money) -> Self {
+        Self {
+            name: name.into(),
+            price,
+            placement: Placement::Whole,
+            nutrition: None,
+        }
+    }
+
+    pub fn with_placement(mut self, placement: Placement) -> Self {
+        self.placement = placement;
+        self
+    }
+
+    pub fn with_nutrition(mut self, nutrition: Nutrition) -> Self {
+        self.nutrition = Some(nutrition);
+        self
+    }
+}
+
+/// Everything needed to bill, cook and rebuild a pizza.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PizzaSpec {
+    pub base: PizzaPart,
+    #[serde(default)]
+    pub crust: Option<PizzaPart>,
+    /// Toppings in the order they were added.
+    #[serde(default)]
+    pub toppings: Vec<PizzaPart>,
+    #[serde(default)]
+    pub size: Size,
+    /// Free-text instructions for the kitchen, e.g. "well done".
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub category: TaxCategory,
+}
+
+impl PizzaSpec {
+    /// A plain base with no crust or toppings.
+    pub fn new(base: impl Into<String>, price: Money) -> Self {
+        Self {
+            base: PizzaPart::new(base, price),
+            crust: None,
+            toppings: Vec::new(),
+            size: Size::default(),
+            notes: None,
+            category: TaxCategory::Food,
+        }
+    }
+
+    /// Add a topping over the whole pizza, on top of the existing ones.
+    pub fn with_topping(self, name: impl Into<String>, price: Money) -> Self {
+        self.with_topping_on(name, price, Placement::Whole)
+    }
+
+    /// Add a topping to part of the pizza. `price` is what it charges there.
+    pub fn with_topping_on(self, name: impl Into<String>, price: Money, placement: Placement) -> Self {
+        self.with_topping_part(PizzaPart::new(name, price).with_placement(placement))
+    }
+
+    pub fn with_topping_part(mut self, part: PizzaPart) -> Self {
+        self.toppings.push(part);
+        self
+    }
+
+    /// Set (or replace) the crust.
+    pub fn with_crust(self, name: impl Into<String>, price: Money) -> Self {
+        self.with_crust_part(PizzaPart::new(name, price))
+    }
+
+    pub fn with_crust_part(mut self, part: PizzaPart) -> Self {
+        self.crust = Some(part);
+        self
+    }
+
+    /// Label facts for the base as served.
+    pub fn with_base_nutrition(mut self, nutrition: Nutrition) -> Self {
+        self.base.nutrition = Some(nutrition);
+        self
+    }
+
+    pub fn with_size(mut self, size: Size) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn with_notes(mut self, notes: impl Into<String>) -> Self {
+        self.notes = Some(notes.into());
+        self
+    }
+
+    /// Bill the pizza under a different tax category.
+    pub fn with_category(mut self, category: TaxCategory) -> Self {
+        self.category = category;
+        self
+    }
+
+    pub fn topping_names(&self) -> Vec<&str> {
+        self.toppings.iter().map(|t| t.name.as_str()).collect()
+    }
+
+    /// Remove the first topping called `name`; returns whether one was found.
+    pub fn remove_topping(&mut self, name: &str) -> bool {
+        match self.toppings.iter().position(|t| t.name.eq_ignore_ascii_case(name)) {
+            Some(i) => {
+                self.toppings.remove(i);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Label facts for the whole pizza; `None` unless every part has them.
+    pub fn nutrition(&self) -> Option<Nutrition> {
+        let base = self.base.nutrition.clone()?;
+        self.toppings
+            .iter()
+            .chain(&self.crust)
+            .try_fold(base, |total, part| Some(total.combine(part.nutrition.as_ref()?)))
+    }
+
+    /// Rebuild the decorator chain: base, then toppings, then crust.
+    pub fn build(&self) -> Box<dyn Pizza> {
+        let mut pizza: Box<dyn Pizza> = Box::new(
+            NamedBase::new(&self.base.name, self.base.price)
+                .with_size(self.size)
+                .with_category(self.category)
+                .with_nutrition(self.base.nutrition.clone()),
+        );
+        for topping in &self.toppings {
+            pizza = Box::new(
+                NamedTopping::new(pizza, &topping.name, topping.price)
+                    .with_placement(topping.placement)
+                    .with_nutrition(topping.nutrition.clone()),
+            );
+        }
+        if let Some(crust) = &self.crust {
+            pizza = Box::new(NamedCrust::new(pizza, &crust.name, crust.price).with_nutrition(crust.nutrition.clone()));
+        }
+        pizza
+    }
+
+    /// Where each topping goes. All-whole pizzas get a single line;
+    /// half-and-half pizzas get one line per area that has toppings.
+    pub fn topping_layout(&self) -> Vec<String> {
+        if self.toppings.is_empty() {
+            return Vec::new();
+        }
+        if self.toppings.iter().all(|t| t.placement.is_whole()) {
+            return vec![format!("Toppings: {}", self.topping_names().join(", "))];
+        }
+
+        [(Placement::Whole, "Whole"), (Placement::Left, "Left half"), (Placement::Right, "Right half")]
+            .into_iter()
+            .filter_map(|(placement, label)| {
+                let names: Vec<&str> = self
+                    .toppings
+                    .iter()
+                    .filter(|t| t.placement == placement)
+                    .map(|t| t.name.as_str())
+                    .collect();
+                (!names.is_empty()).then(|| format!("{}: {}", label, names.join(", ")))
+            })
+            .collect()
+    }
+
+    /// Lines for the kitchen ticket, one component per line.
+    pub fn ticket_lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("{} {}", self.size, self.base.name)];
+        if let Some(crust) = &self.crust {
+            lines.push(format!("Crust: {}", crust.name));
+        }
+        lines.extend(self.topping_layout());
+        if let Some(notes) = &self.notes {
+            lines.push(format!("Notes: {}", notes));
+        }
+        lines
+    }
+}
+
+impl Pizza for PizzaSpec {
+    fn description(&self) -> String {
+        let mut text = format!("{} {}", self.size, self.base.name);
+        for topping in &self.toppings {
+            text.push_str(" + ");
+            text.push_str(&topping.name);
+            text.push_str(&topping.placement.suffix());
+        }
+        if let Some(crust) = &self.crust {
+            text.push_str(", ");
+            text.push_str(&crust.name);
+        }
+        text
+    }
+
+    fn cost(&self) -> Result<Money, MoneyError> {
+        self.toppings
+            .iter()
+            .chain(&self.crust)
+            .try_fold(self.base.price, |total, part| total.checked_add(part.price))
+    }
+
+    fn tax_category(&self) -> TaxCategory {
+        self.category
+    }
+
+    fn size(&self) -> Size {
+        self.size
+    }
+
+    fn spec(&self) -> PizzaSpec {
+        self.clone()
+    }
+
+    fn nutrition(&self) -> Option<Nutrition> {
+        PizzaSpec::nutrition(self)
+    }
+
+    fn clone_box(&self) -> Box<dyn Pizza> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pizza::{Cheese, CheeseBurst, Farmhouse, Jalapenos, Margherita, Olives};
+
+    #[test]
+    fn chain_and_spec_agree() {
+        let chain = CheeseBurst::new(Jalapenos::new(Box::new(Olives::new(Box::new(Farmhouse::new())))));
+        let spec = chain.spec();
+
+        assert_eq!((spec.base.name.as_str(), spec.base.price), ("Farmhouse", Money::inr(150)));
+        let crust = spec.crust.as_ref().unwrap();
+        assert_eq!((crust.name.as_str(), crust.price), ("CheeseBurst Crust", Money::inr(50)));
+        assert_eq!(spec.topping_names(), vec!["Olives", "Jalapenos"]);
+        assert_eq!(spec.description(), chain.description());
+        assert_eq!(spec.cost().unwrap(), chain.cost().unwrap());
+        assert_eq!(spec.nutrition().unwrap().calories, 950 + 40 + 15 + 300);
+        assert_eq!(spec.build().spec(), spec);
+    }
+
+    #[test]
+    fn edit_and_serialise() {
+        let mut spec = PizzaSpec::new("Margherita", Money::inr(120))
+            .with_topping("Cheese", Money::inr(10))
+            .with_topping("Olives", Money::inr(15))
+            .with_size(Size::Large)
+            .with_notes("cut in 8");
+        assert!(spec.remove_topping("cheese"));
+        assert!(!spec.remove_topping("paneer"));
+
+        let json = serde_json::to_string(&spec).unwrap();
+        let back: PizzaSpec = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, spec);
+        assert_eq!(back.cost().unwrap(), Money::inr(135));
+        assert_eq!(
+            back.ticket_lines(),
+            vec!["Large Margherita", "Toppings: Olives", "Notes: cut in 8"]
+        );
+
+        // A spec read back with a foreign price cannot be costed
+        let mixed = back.with_topping("Corn", Money::from_minor(50, crate::money::Currency::Usd));
+        assert!(mixed.cost().is_err());
+    }
+
+    #[test]
+    fn half_toppings_cost_half_and_show_in_layout() {
+        let chain = Olives::new(Box::new(
+            Jalapenos::new(Box::new(Cheese::new(Box::new(Margherita::new())))).with_placement(Placement::Left),
+        ))
+        .with_placement(Placement::Right);
+
+        assert_eq!(
+            chain.description(),
+            "Medium Margherita + Cheese + Jalapenos (left half) + Olives (right half)"
+        );
+        // 120 + 10 + 12/2 + 15/2
+        assert_eq!(chain.cost().unwrap(), Money::from_minor(14_350, crate::money::Currency::Inr));
+
+        let spec = chain.spec();
+        assert_eq!(spec.toppings[1].placement, Placement::Left);
+        assert_eq!(spec.build().description(), chain.description());
+        assert_eq!(
+            spec.topping_layout(),
+            vec!["Whole: Cheese", "Left half: Jalapenos", "Right half: Olives"]
+        );
+    }
+}
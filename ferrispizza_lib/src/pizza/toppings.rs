@@ -1,35 +1,73 @@
 //! Toppings module — Decorator implementations
 
-use crate::pizza::Pizza;
+use crate::billing::TaxCategory;
+use crate::errors::MoneyError;
+use crate::money::Money;
+use crate::pizza::{Allergen, Diet, Nutrition, Pizza, PizzaPart, PizzaSpec, Placement, PriceMatrix, Size};
 use crate::patterns::ToppingDecorator;
 
 /// Cheese topping decorator
 #[derive(Clone)]
 pub struct Cheese {
     pizza: Box<dyn Pizza>,
-    price: f32,
+    prices: PriceMatrix,
+    placement: Placement,
 }
 
 impl Cheese {
-    /// Default: +10
+    /// Default: +10 on a medium, scaled by size
     pub fn new(pizza: Box<dyn Pizza>) -> Self {
-        Self { pizza, price: 10.0 }
+        Self::with_price(pizza, Money::inr(10))
     }
 
-    /// Custom pricing
-    pub fn with_price(pizza: Box<dyn Pizza>, price: f32) -> Self {
-        Self { pizza, price }
+    /// Custom medium price, scaled by size
+    ///
+    /// # Panics
+    /// Panics if a scaled price overflows.
+    pub fn with_price(pizza: Box<dyn Pizza>, price: Money) -> Self {
+        Self::with_prices(pizza, PriceMatrix::scaled(price).expect("scaled price overflow"))
+    }
+
+    /// Explicit price for every size
+    pub fn with_prices(pizza: Box<dyn Pizza>, prices: PriceMatrix) -> Self {
+        Self { pizza, prices, placement: Placement::Whole }
+    }
+
+    /// Put the topping on one half only, at half price
+    pub fn with_placement(mut self, placement: Placement) -> Self {
+        self.placement = placement;
+        self
+    }
+
+    fn price(&self) -> Money {
+        self.placement.charge(self.prices.price(self.pizza.size()))
     }
 }
 
 impl Pizza for Cheese {
     fn description(&self) -> String {
-        format!("{} + Cheese", self.pizza.description())
+        format!("{} + Cheese{}", self.pizza.description(), self.placement.suffix())
+    }
+
+    fn spec(&self) -> PizzaSpec {
+        let part = PizzaPart::new("Cheese", self.price())
+            .with_placement(self.placement)
+            .with_nutrition(cheese_facts().portion(self.pizza.size(), self.placement));
+        self.pizza.spec().with_topping_part(part)
+    }
+
+    fn cost(&self) -> Result<Money, MoneyError> {
+        self.pizza.cost()?.checked_add(self.price())
     }
 
-    fn cost(&self) -> f32 {
-        self.pizza.cost() + self.price
+    fn size(&self) -> Size {
+        self.pizza.size()
     }
+
+    fn crusts(&self) -> Vec<String> {
+        self.pizza.crusts()
+    }
+
     fn clone_box(&self) -> Box<dyn crate::pizza::Pizza> {
         Box::new(self.clone())
     }
@@ -37,32 +75,77 @@ impl Pizza for Cheese {
 
 impl ToppingDecorator for Cheese {}
 
+/// Whole medium portion
+fn cheese_facts() -> Nutrition {
+    Nutrition::new(110)
+        .with_allergen(Allergen::Dairy)
+        .with_diet(Diet::Vegetarian)
+        .with_diet(Diet::Jain)
+}
+
 
 /// Olives topping decorator
 #[derive(Clone)]
 pub struct Olives {
     pizza: Box<dyn Pizza>,
-    price: f32,
+    prices: PriceMatrix,
+    placement: Placement,
 }
 
 impl Olives {
+    /// Default: +15 on a medium, scaled by size
     pub fn new(pizza: Box<dyn Pizza>) -> Self {
-        Self { pizza, price: 15.0 }
+        Self::with_price(pizza, Money::inr(15))
+    }
+
+    /// Custom medium price, scaled by size
+    ///
+    /// # Panics
+    /// Panics if a scaled price overflows.
+    pub fn with_price(pizza: Box<dyn Pizza>, price: Money) -> Self {
+        Self::with_prices(pizza, PriceMatrix::scaled(price).expect("scaled price overflow"))
+    }
+
+    /// Explicit price for every size
+    pub fn with_prices(pizza: Box<dyn Pizza>, prices: PriceMatrix) -> Self {
+        Self { pizza, prices, placement: Placement::Whole }
     }
 
-    pub fn with_price(pizza: Box<dyn Pizza>, price: f32) -> Self {
-        Self { pizza, price }
+    /// Put the topping on one half only, at half price
+    pub fn with_placement(mut self, placement: Placement) -> Self {
+        self.placement = placement;
+        self
+    }
+
+    fn price(&self) -> Money {
+        self.placement.charge(self.prices.price(self.pizza.size()))
     }
 }
 
 impl Pizza for Olives {
     fn description(&self) -> String {
-        format!("{} + Olives", self.pizza.description())
+        format!("{} + Olives{}", self.pizza.description(), self.placement.suffix())
+    }
+
+    fn spec(&self) -> PizzaSpec {
+        let part = PizzaPart::new("Olives", self.price())
+            .with_placement(self.placement)
+            .with_nutrition(olives_facts().portion(self.pizza.size(), self.placement));
+        self.pizza.spec().with_topping_part(part)
+    }
+
+    fn cost(&self) -> Result<Money, MoneyError> {
+        self.pizza.cost()?.checked_add(self.price())
+    }
+
+    fn size(&self) -> Size {
+        self.pizza.size()
     }
 
-    fn cost(&self) -> f32 {
-        self.pizza.cost() + self.price
+    fn crusts(&self) -> Vec<String> {
+        self.pizza.crusts()
     }
+
     fn clone_box(&self) -> Box<dyn crate::pizza::Pizza> {
         Box::new(self.clone())
     }
@@ -70,35 +153,160 @@ impl Pizza for Olives {
 
 impl ToppingDecorator for Olives {}
 
+/// Whole medium portion
+fn olives_facts() -> Nutrition {
+    Nutrition::new(40)
+        .with_diet(Diet::Vegetarian)
+        .with_diet(Diet::Vegan)
+        .with_diet(Diet::Jain)
+}
+
 
 /// Jalapenos topping decorator (will test soon)
 #[derive(Clone)]
 pub struct Jalapenos {
     pizza: Box<dyn Pizza>,
-    price: f32,
+    prices: PriceMatrix,
+    placement: Placement,
 }
 
 impl Jalapenos {
+    /// Default: +12 on a medium, scaled by size
     pub fn new(pizza: Box<dyn Pizza>) -> Self {
-        Self { pizza, price: 12.0 }
+        Self::with_price(pizza, Money::inr(12))
+    }
+
+    /// Custom medium price, scaled by size
+    ///
+    /// # Panics
+    /// Panics if a scaled price overflows.
+    pub fn with_price(pizza: Box<dyn Pizza>, price: Money) -> Self {
+        Self::with_prices(pizza, PriceMatrix::scaled(price).expect("scaled price overflow"))
+    }
+
+    /// Explicit price for every size
+    pub fn with_prices(pizza: Box<dyn Pizza>, prices: PriceMatrix) -> Self {
+        Self { pizza, prices, placement: Placement::Whole }
     }
 
-    pub fn with_price(pizza: Box<dyn Pizza>, price: f32) -> Self {
-        Self { pizza, price }
+    /// Put the topping on one half only, at half price
+    pub fn with_placement(mut self, placement: Placement) -> Self {
+        self.placement = placement;
+        self
+    }
+
+    fn price(&self) -> Money {
+        self.placement.charge(self.prices.price(self.pizza.size()))
     }
 }
 
 impl Pizza for Jalapenos {
     fn description(&self) -> String {
-        format!("{} + Jalapenos", self.pizza.description())
+        format!("{} + Jalapenos{}", self.pizza.description(), self.placement.suffix())
+    }
+
+    fn spec(&self) -> PizzaSpec {
+        let part = PizzaPart::new("Jalapenos", self.price())
+            .with_placement(self.placement)
+            .with_nutrition(jalapenos_facts().portion(self.pizza.size(), self.placement));
+        self.pizza.spec().with_topping_part(part)
     }
 
-    fn cost(&self) -> f32 {
-        self.pizza.cost() + self.price
+    fn cost(&self) -> Result<Money, MoneyError> {
+        self.pizza.cost()?.checked_add(self.price())
     }
+
+    fn size(&self) -> Size {
+        self.pizza.size()
+    }
+
+    fn crusts(&self) -> Vec<String> {
+        self.pizza.crusts()
+    }
+
     fn clone_box(&self) -> Box<dyn crate::pizza::Pizza> {
         Box::new(self.clone())
     }
 }
 
 impl ToppingDecorator for Jalapenos {}
+
+/// Whole medium portion
+fn jalapenos_facts() -> Nutrition {
+    Nutrition::new(15)
+        .with_diet(Diet::Vegetarian)
+        .with_diet(Diet::Vegan)
+        .with_diet(Diet::Jain)
+}
+
+
+/// Topping decorator defined at runtime, e.g. from a menu catalog
+///
+/// `price` is what the topping charges on the pizza underneath, already
+/// resolved for size and placement by the catalog.
+#[derive(Clone)]
+pub struct NamedTopping {
+    pizza: Box<dyn Pizza>,
+    name: String,
+    price: Money,
+    placement: Placement,
+    nutrition: Option<Nutrition>,
+}
+
+impl NamedTopping {
+    pub fn new(pizza: Box<dyn Pizza>, name: impl Into<String>, price: Money) -> Self {
+        Self {
+            pizza,
+            name: name.into(),
+            price,
+            placement: Placement::Whole,
+            nutrition: None,
+        }
+    }
+
+    /// Mark the topping as covering one half; `price` is not changed
+    pub fn with_placement(mut self, placement: Placement) -> Self {
+        self.placement = placement;
+        self
+    }
+
+    /// Label facts for the topping as served
+    pub fn with_nutrition(mut self, nutrition: impl Into<Option<Nutrition>>) -> Self {
+        self.nutrition = nutrition.into();
+        self
+    }
+}
+
+impl Pizza for NamedTopping {
+    fn description(&self) -> String {
+        format!("{} + {}{}", self.pizza.description(), self.name, self.placement.suffix())
+    }
+
+    fn spec(&self) -> PizzaSpec {
+        let mut part = PizzaPart::new(&self.name, self.price).with_placement(self.placement);
+        part.nutrition = self.nutrition.clone();
+        self.pizza.spec().with_topping_part(part)
+    }
+
+    fn cost(&self) -> Result<Money, MoneyError> {
+        self.pizza.cost()?.checked_add(self.price)
+    }
+
+    fn tax_category(&self) -> TaxCategory {
+        self.pizza.tax_category()
+    }
+
+    fn size(&self) -> Size {
+        self.pizza.size()
+    }
+
+    fn crusts(&self) -> Vec<String> {
+        self.pizza.crusts()
+    }
+
+    fn clone_box(&self) -> Box<dyn crate::pizza::Pizza> {
+        Box::new(self.clone())
+    }
+}
+
+impl ToppingDecorator for NamedTopping {}
@@ -0,0 +1,22 @@
+//! Storage module — where orders live between requests and restarts.
+//!
+//! [`OrderRepository`] is the extension point used by
+//! [`SharedOrderState`](crate::concurrency::SharedOrderState). Two stores
+//! ship with the library:
+//! - [`InMemoryOrderRepository`] — the default; orders vanish on exit.
+//! - [`FileOrderRepository`] — append-only JSON-lines log plus periodic
+//!   snapshots in a directory, so orders survive restarts.
+//! - `SqliteOrderRepository` — relational tables in a SQLite database, with
+//!   versioned schema migrations (cargo feature `sqlite`). Customers can be
+//!   kept in the same database with `SqliteCustomerStore`.
+mod file_store;
+mod record;
+mod repository;
+#[cfg(feature = "sqlite")]
+mod sqlite_store;
+
+pub use file_store::{FileOrderRepository, DEFAULT_SNAPSHOT_EVERY};
+pub use record::OrderRecord;
+pub use repository::{InMemoryOrderRepository, OrderRepository};
+#[cfg(feature = "sqlite")]
+pub use sqlite_store::{SqliteCustomerStore, SqliteOrderRepository, MIGRATIONS};
@@ -0,0 +1,385 @@
+//! File-backed order store: append-only JSON-lines log plus snapshots.
+//!
+//! The store owns a directory with two files:
+//! - `orders.snapshot.jsonl` — one [`OrderRecord`] per line, every order
+//!   as of the last compaction;
+//! - `orders.log.jsonl` — one entry per change since then.
+//!
+//! Every change is appended to the log and synced before the call returns.
+//! After `snapshot_every` appends the store writes a fresh snapshot (to a
+//! temporary file, then renamed into place) and truncates the log. On open
+//! the snapshot is loaded and the log replayed on top of it. Replaying is
+//! idempotent, so a crash between the rename and the truncate loses
+//! nothing. A torn final log line from a crash mid-write is dropped.
+//!
+//! # Example
+//! ```
+//! use ferrispizza_lib::concurrency::SharedOrderState;
+//! use ferrispizza_lib::order::order;
+//! use ferrispizza_lib::pizza::Margherita;
+//! use ferrispizza_lib::storage::FileOrderRepository;
+//!
+//! let dir = std::env::temp_dir().join(format!("ferrispizza-doc-{}", std::process::id()));
+//! let state = SharedOrderState::with_repository(FileOrderRepository::open(&dir).unwrap());
+//! let order = order::new(vec![Box::new(Margherita::new())]);
+//! let id = order.id.0;
+//! state.add_order(order).unwrap();
+//! drop(state);
+//!
+//! // A fresh process sees the same order
+//! let reopened = FileOrderRepository::open(&dir).unwrap();
+//! let state = SharedOrderState::with_repository(reopened);
+//! assert_eq!(state.get_order(id).unwrap().total_cost().unwrap().to_string(), "₹120.00");
+//! # std::fs::remove_dir_all(&dir).unwrap();
+//! ```
+
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{OrderError, StorageError};
+use crate::order::order;
+use super::record::OrderRecord;
+use super::repository::OrderRepository;
+
+const SNAPSHOT_FILE: &str = "orders.snapshot.jsonl";
+const LOG_FILE: &str = "orders.log.jsonl";
+
+/// Appends between snapshots unless configured otherwise.
+pub const DEFAULT_SNAPSHOT_EVERY: usize = 100;
+
+/// One line of the change log.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum LogEntry {
+    /// Full state of an order after a change.
+    Upsert(OrderRecord),
+}
+
+struct State {
+    orders: HashMap<u64, order>,
+    log: File,
+    /// Log entries written since the last snapshot.
+    appended: usize,
+}
+
+/// Order store that survives restarts.
+pub struct FileOrderRepository {
+    dir: PathBuf,
+    snapshot_every: usize,
+    state: Mutex<State>,
+}
+
+impl FileOrderRepository {
+    /// Open (or create) the store in `dir`, loading every saved order.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, StorageError> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+
+        let mut orders = HashMap::new();
+        for record in read_lines::<OrderRecord>(&dir.join(SNAPSHOT_FILE), false)?.0 {
+            orders.insert(record.id, order::from(record));
+        }
+        let (entries, torn) = read_lines::<LogEntry>(&dir.join(LOG_FILE), true)?;
+        let appended = entries.len();
+        for LogEntry::Upsert(record) in entries {
+            orders.insert(record.id, order::from(record));
+        }
+
+        let log = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(LOG_FILE))?;
+
+        let repo = Self {
+            dir,
+            snapshot_every: DEFAULT_SNAPSHOT_EVERY,
+            state: Mutex::new(State { orders, log, appended }),
+        };
+        if torn {
+            // Later appends must not land after the partial line
+            repo.compact()?;
+        }
+        Ok(repo)
+    }
+
+    /// Write a snapshot after every `n` log entries (minimum 1).
+    pub fn with_snapshot_every(mut self, n: usize) -> Self {
+        self.snapshot_every = n.max(1);
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Highest order id in the store, to resume id generation after restart.
+    pub fn max_id(&self) -> Option<u64> {
+        self.state.lock().unwrap().orders.keys().copied().max()
+    }
+
+    /// Write a snapshot now and truncate the log.
+    pub fn compact(&self) -> Result<(), StorageError> {
+        let mut state = self.state.lock().unwrap();
+        self.snapshot(&mut state)
+    }
+
+    fn snapshot(&self, state: &mut State) -> Result<(), StorageError> {
+        let tmp = self.dir.join(format!("{}.tmp", SNAPSHOT_FILE));
+        {
+            let mut out = BufWriter::new(File::create(&tmp)?);
+            let mut ids: Vec<&u64> = state.orders.keys().collect();
+            ids.sort();
+            for id in ids {
+                write_line(&mut out, &OrderRecord::from(&state.orders[id]))?;
+            }
+            out.flush()?;
+            out.get_ref().sync_all()?;
+        }
+        fs::rename(&tmp, self.dir.join(SNAPSHOT_FILE))?;
+
+        state.log.set_len(0)?;
+        state.log.sync_all()?;
+        state.appended = 0;
+        Ok(())
+    }
+
+    fn append(&self, state: &mut State, record: OrderRecord) -> Result<(), StorageError> {
+        write_or_roll_back(&mut state.log, |log| {
+            write_line(log, &LogEntry::Upsert(record))?;
+            Ok(log.sync_data()?)
+        })?;
+        state.appended += 1;
+
+        // The entry is durable in the log; a failed snapshot is retried on
+        // the next append rather than failing this one
+        if state.appended >= self.snapshot_every
+            && let Err(e) = self.snapshot(state)
+        {
+            eprintln!("Could not snapshot order store {}: {}", self.dir.display(), e);
+        }
+        Ok(())
+    }
+}
+
+impl OrderRepository for FileOrderRepository {
+    fn insert(&self, order: order) -> Result<(), OrderError> {
+        let mut state = self.state.lock().unwrap();
+        let record = OrderRecord::from(&order);
+        let id = order.id.0;
+
+        // In the map first, so a snapshot taken by `append` includes it
+        let previous = state.orders.insert(id, order);
+        if let Err(e) = self.append(&mut state, record) {
+            match previous {
+                Some(previous) => state.orders.insert(id, previous),
+                None => state.orders.remove(&id),
+            };
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    fn get(&self, id: u64) -> Option<order> {
+        self.state.lock().unwrap().orders.get(&id).cloned()
+    }
+
+    fn list(&self) -> Vec<order> {
+        self.state.lock().unwrap().orders.values().cloned().collect()
+    }
+
+    fn modify(&self, id: u64, f: &mut dyn FnMut(&mut order)) -> Result<(), OrderError> {
+        let mut state = self.state.lock().unwrap();
+        let order = state.orders.get_mut(&id).ok_or(OrderError::NotFound(id))?;
+        let original = order.clone();
+        let before = OrderRecord::from(&*order);
+        f(order);
+        let after = OrderRecord::from(&*order);
+
+        // Rejected transitions leave the order untouched; don't log them
+        if after != before
+            && let Err(e) = self.append(&mut state, after)
+        {
+            state.orders.insert(id, original);
+            return Err(e.into());
+        }
+        Ok(())
+    }
+}
+
+/// Run `write` against the end of `log`. If it fails part-way the log is
+/// cut back to where it was, so a half-written line never ends up in the
+/// middle of the log where reopening would reject it.
+fn write_or_roll_back(
+    log: &mut File,
+    write: impl FnOnce(&mut File) -> Result<(), StorageError>,
+) -> Result<(), StorageError> {
+    let len = log.metadata()?.len();
+    let result = write(log);
+    if result.is_err() {
+        log.set_len(len)?;
+        log.seek(SeekFrom::End(0))?;
+    }
+    result
+}
+
+fn write_line<T: Serialize>(out: &mut impl Write, value: &T) -> Result<(), StorageError> {
+    let mut line = serde_json::to_string(value).map_err(|e| StorageError::Serialize(e.to_string()))?;
+    line.push('\n');
+    out.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+/// Parse every line of `path` (missing file = empty). With `allow_torn`, a
+/// bad final line is skipped and reported instead of failing.
+fn read_lines<T>(path: &Path, allow_torn: bool) -> Result<(Vec<T>, bool), StorageError>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((Vec::new(), false)),
+        Err(e) => return Err(e.into()),
+    };
+
+    let lines: Vec<String> = BufReader::new(file).lines().collect::<Result<_, _>>()?;
+    let last = lines.len();
+    let mut values = Vec::new();
+    for (n, line) in lines.iter().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(line) {
+            Ok(value) => values.push(value),
+            Err(_) if allow_torn && n + 1 == last => return Ok((values, true)),
+            Err(e) => {
+                return Err(StorageError::Corrupt {
+                    file: path.display().to_string(),
+                    line: n + 1,
+                    reason: e.to_string(),
+                });
+            }
+        }
+    }
+    Ok((values, false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order::OrderStatus;
+    use crate::pizza::{Margherita, Pizza};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ferrispizza-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn sample() -> order {
+        order::new(vec![Box::new(Margherita::new()) as Box<dyn Pizza>])
+    }
+
+    #[test]
+    fn orders_and_changes_survive_reopen() {
+        let dir = temp_dir("reopen");
+        let (a, b) = (sample(), sample());
+        let (a_id, b_id) = (a.id.0, b.id.0);
+        {
+            let repo = FileOrderRepository::open(&dir).unwrap();
+            repo.insert(a).unwrap();
+            repo.insert(b).unwrap();
+            repo.modify(a_id, &mut |o| o.mark_cancelled().unwrap()).unwrap();
+        }
+
+        let repo = FileOrderRepository::open(&dir).unwrap();
+        assert_eq!(repo.list().len(), 2);
+        assert_eq!(repo.get(a_id).unwrap().status, OrderStatus::Cancelled);
+        assert_eq!(repo.get(b_id).unwrap().status, OrderStatus::Created);
+        assert_eq!(repo.max_id(), Some(a_id.max(b_id)));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn snapshot_truncates_log() {
+        let dir = temp_dir("snapshot");
+        let repo = FileOrderRepository::open(&dir).unwrap().with_snapshot_every(3);
+        for _ in 0..4 {
+            repo.insert(sample()).unwrap();
+        }
+
+        let log = fs::read_to_string(dir.join(LOG_FILE)).unwrap();
+        let snapshot = fs::read_to_string(dir.join(SNAPSHOT_FILE)).unwrap();
+        assert_eq!(log.lines().count(), 1);
+        assert_eq!(snapshot.lines().count(), 3);
+
+        drop(repo);
+        assert_eq!(FileOrderRepository::open(&dir).unwrap().list().len(), 4);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn unchanged_orders_are_not_logged() {
+        let dir = temp_dir("unchanged");
+        let repo = FileOrderRepository::open(&dir).unwrap();
+        let order = sample();
+        let id = order.id.0;
+        repo.insert(order).unwrap();
+
+        // Illegal transition: the closure runs but nothing changes
+        repo.modify(id, &mut |o| assert!(o.mark_completed().is_err())).unwrap();
+
+        let log = fs::read_to_string(dir.join(LOG_FILE)).unwrap();
+        assert_eq!(log.lines().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn torn_tail_is_dropped_but_corruption_is_reported() {
+        let dir = temp_dir("torn");
+        let order = sample();
+        let id = order.id.0;
+        FileOrderRepository::open(&dir).unwrap().insert(order).unwrap();
+
+        let mut log = OpenOptions::new().append(true).open(dir.join(LOG_FILE)).unwrap();
+        log.write_all(b"{\"upsert\":{\"id\":").unwrap();
+        drop(log);
+
+        let repo = FileOrderRepository::open(&dir).unwrap();
+        assert!(repo.get(id).is_some());
+        repo.insert(sample()).unwrap();
+        drop(repo);
+        assert_eq!(FileOrderRepository::open(&dir).unwrap().list().len(), 2);
+
+        fs::write(dir.join(LOG_FILE), "garbage\n{}\n").unwrap();
+        assert!(matches!(
+            FileOrderRepository::open(&dir),
+            Err(StorageError::Corrupt { line: 1, .. })
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn failed_writes_leave_no_garbage_in_the_log() {
+        let dir = temp_dir("failed-write");
+        let repo = FileOrderRepository::open(&dir).unwrap();
+        repo.insert(sample()).unwrap();
+
+        // A write that dies half-way, as on a full disk
+        let result = write_or_roll_back(&mut repo.state.lock().unwrap().log, |log| {
+            log.write_all(b"{\"upsert\":{\"id\":")?;
+            Err(std::io::Error::other("no space left on device").into())
+        });
+        assert!(result.is_err());
+
+        // The next entry starts on a clean line and the store reopens
+        repo.insert(sample()).unwrap();
+        drop(repo);
+        assert_eq!(FileOrderRepository::open(&dir).unwrap().list().len(), 2);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,116 @@
+//! Serialisable form of an order.
+//!
+//! `order` holds `Box<dyn Pizza>` chains, which cannot be serialised
+//! directly. Each pizza is stored as its [`PizzaSpec`], which implements
+//! [`Pizza`] itself, so a reloaded order bills exactly like the original.
+
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::billing::{CouponClaim, PaymentReceipt};
+use crate::customer::CustomerId;
+use crate::order::{order, OrderId, OrderPriority, OrderStatus, StatusChange};
+use crate::pizza::{Pizza, PizzaSpec};
+
+/// One order as written to disk.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OrderRecord {
+    pub id: u64,
+    pub pizzas: Vec<PizzaSpec>,
+    pub status: OrderStatus,
+    pub timestamp: SystemTime,
+    pub receipt: Option<PaymentReceipt>,
+    pub history: Vec<StatusChange>,
+    pub coupon: Option<CouponClaim>,
+    #[serde(default)]
+    pub pizzas_ready: usize,
+    #[serde(default)]
+    pub priority: OrderPriority,
+    #[serde(default)]
+    pub deliver_by: Option<SystemTime>,
+    #[serde(default)]
+    pub customer_id: Option<u64>,
+    /// Loyalty points offered towards the bill.
+    #[serde(default)]
+    pub redeem_points: u32,
+}
+
+impl From<&order> for OrderRecord {
+    fn from(order: &order) -> Self {
+        Self {
+            id: order.id.0,
+            pizzas: order.pizzas.iter().map(|p| p.spec()).collect(),
+            status: order.status,
+            timestamp: order.timestamp,
+            receipt: order.receipt.clone(),
+            history: order.history.clone(),
+            coupon: order.coupon.clone(),
+            pizzas_ready: order.pizzas_ready,
+            priority: order.priority,
+            deliver_by: order.deliver_by,
+            customer_id: order.customer.map(|c| c.0),
+            redeem_points: order.redeem_points,
+        }
+    }
+}
+
+impl From<OrderRecord> for order {
+    fn from(record: OrderRecord) -> Self {
+        order {
+            id: OrderId(record.id),
+            pizzas: record
+                .pizzas
+                .into_iter()
+                .map(|p| Box::new(p) as Box<dyn Pizza>)
+                .collect(),
+            status: record.status,
+            timestamp: record.timestamp,
+            receipt: record.receipt,
+            history: record.history,
+            coupon: record.coupon,
+            redeem_points: record.redeem_points,
+            pizzas_ready: record.pizzas_ready,
+            priority: record.priority,
+            deliver_by: record.deliver_by,
+            customer: record.customer_id.map(CustomerId),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::billing::BillingEngine;
+    use crate::money::Money;
+    use crate::pizza::{Cheese, Margherita, ThinCrust};
+
+    #[test]
+    fn round_trip_keeps_billing_identical() {
+        let mut original = order::new(vec![
+            Box::new(ThinCrust::new(Cheese::new(Box::new(Margherita::new())))) as Box<dyn Pizza>,
+            Box::new(Margherita::new()),
+        ])
+        .with_priority(OrderPriority::Express);
+        let receipt = BillingEngine::new().generate_receipt(&original).unwrap();
+        original.record_payment(receipt.clone()).unwrap();
+
+        let json = serde_json::to_string(&OrderRecord::from(&original)).unwrap();
+        let restored: order = serde_json::from_str::<OrderRecord>(&json).unwrap().into();
+
+        assert_eq!(restored.id, original.id);
+        assert_eq!(restored.status, OrderStatus::Paid);
+        assert_eq!(restored.priority, OrderPriority::Express);
+        assert_eq!(restored.receipt, Some(receipt.clone()));
+        assert_eq!(restored.history, original.history);
+        assert_eq!(restored.pizzas[0].description(), "Medium Margherita + Cheese, Thin Crust");
+        assert_eq!(restored.pizzas[0].topping_charges(), vec![("Cheese".to_string(), Money::inr(10))]);
+        assert_eq!(restored.pizzas[0].spec().crust.unwrap().name, "Thin Crust");
+
+        let again = BillingEngine::new().generate_receipt(&restored).unwrap();
+        assert_eq!(again.total_amount, receipt.total_amount);
+
+        let offered = OrderRecord { redeem_points: 40, ..OrderRecord::from(&original) };
+        assert_eq!(order::from(offered).redeem_points, 40);
+    }
+}
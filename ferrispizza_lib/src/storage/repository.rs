@@ -0,0 +1,58 @@
+//! Order repository trait and the in-memory store.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::errors::OrderError;
+use crate::order::order;
+
+/// Durable (or not) home for orders, keyed by order id.
+///
+/// Implementations must be safe to share between threads; every call is
+/// atomic with respect to the others.
+pub trait OrderRepository: Send + Sync {
+    /// Store a new order, replacing any order with the same id.
+    fn insert(&self, order: order) -> Result<(), OrderError>;
+
+    fn get(&self, id: u64) -> Option<order>;
+
+    fn list(&self) -> Vec<order>;
+
+    /// Run `f` on the stored order while holding the store's lock, then
+    /// persist the result. `f` is called exactly once on success.
+    fn modify(&self, id: u64, f: &mut dyn FnMut(&mut order)) -> Result<(), OrderError>;
+}
+
+/// Orders kept in a `HashMap` for the lifetime of the process.
+#[derive(Default)]
+pub struct InMemoryOrderRepository {
+    orders: Mutex<HashMap<u64, order>>,
+}
+
+impl InMemoryOrderRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl OrderRepository for InMemoryOrderRepository {
+    fn insert(&self, order: order) -> Result<(), OrderError> {
+        self.orders.lock().unwrap().insert(order.id.0, order);
+        Ok(())
+    }
+
+    fn get(&self, id: u64) -> Option<order> {
+        self.orders.lock().unwrap().get(&id).cloned()
+    }
+
+    fn list(&self) -> Vec<order> {
+        self.orders.lock().unwrap().values().cloned().collect()
+    }
+
+    fn modify(&self, id: u64, f: &mut dyn FnMut(&mut order)) -> Result<(), OrderError> {
+        let mut orders = self.orders.lock().unwrap();
+        let order = orders.get_mut(&id).ok_or(OrderError::NotFound(id))?;
+        f(order);
+        Ok(())
+    }
+}
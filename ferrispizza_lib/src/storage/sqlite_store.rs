@@ -0,0 +1,809 @@
+//! SQLite order store (cargo feature `sqlite`).
+//!
+//! Orders live in a local database file, laid out for ad-hoc SQL reporting:
+//!
+//! | table                 | one row per                                  |
+//! |-----------------------|----------------------------------------------|
+//! | `orders`              | order (status, customer, coupon, timestamps) |
+//! | `order_lines`         | pizza on an order (base, crust, size, price) |
+//! | `order_line_toppings` | topping on a pizza, with its price           |
+//! | `status_history`      | lifecycle transition                         |
+//! | `payment_receipts`    | settled payment, with totals and full detail |
+//! | `refunds`             | money returned against a payment             |
+//! | `customers`           | registered customer, with addresses          |
+//!
+//! Amounts are stored in minor units (`*_minor`) next to an ISO currency
+//! code; times are milliseconds since the Unix epoch (`*_ms`).
+//!
+//! The schema is versioned with `PRAGMA user_version`. Each entry in
+//! [`MIGRATIONS`] runs once, in its own transaction, when a database older
+//! than it is opened. New schema changes go at the end of the list.
+//!
+//! Like the file store, the repository keeps every order in memory and
+//! writes each change through to the database before returning.
+//! [`SqliteCustomerStore`] keeps a
+//! [`CustomerRegistry`](crate::customer::CustomerRegistry) in the same file.
+//!
+//! # Example
+//! ```
+//! use ferrispizza_lib::concurrency::SharedOrderState;
+//! use ferrispizza_lib::order::order;
+//! use ferrispizza_lib::pizza::Margherita;
+//! use ferrispizza_lib::storage::SqliteOrderRepository;
+//!
+//! let repo = SqliteOrderRepository::open_in_memory().unwrap();
+//! let state = SharedOrderState::with_repository(repo);
+//! state.add_order(order::new(vec![Box::new(Margherita::new())])).unwrap();
+//! assert_eq!(state.list_orders().len(), 1);
+//! ```
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::billing::{CouponClaim, PaymentReceipt};
+use crate::customer::{Customer, CustomerId, CustomerStore};
+use crate::errors::{MoneyError, OrderError, StorageError};
+use crate::money::{Currency, Money};
+use crate::order::{order, StatusChange};
+use crate::pizza::{Pizza, PizzaPart, PizzaSpec};
+use super::record::OrderRecord;
+use super::repository::OrderRepository;
+
+/// Schema migrations; `MIGRATIONS[n]` upgrades `user_version` n to n + 1.
+pub const MIGRATIONS: &[&str] = &[
+    // 1: initial schema
+    r#"
+    CREATE TABLE orders (
+        id              INTEGER PRIMARY KEY,
+        status          TEXT    NOT NULL,
+        priority        TEXT    NOT NULL,
+        created_at_ms   INTEGER NOT NULL,
+        deliver_by_ms   INTEGER,
+        pizzas_ready    INTEGER NOT NULL DEFAULT 0,
+        coupon_code     TEXT,
+        coupon_customer TEXT
+    );
+
+    CREATE TABLE order_lines (
+        order_id     INTEGER NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+        position     INTEGER NOT NULL,
+        description  TEXT    NOT NULL,
+        base         TEXT,
+        crust        TEXT,
+        price_minor  INTEGER NOT NULL,
+        currency     TEXT    NOT NULL,
+        tax_category TEXT    NOT NULL,
+        PRIMARY KEY (order_id, position)
+    );
+
+    CREATE TABLE order_line_toppings (
+        order_id    INTEGER NOT NULL,
+        position    INTEGER NOT NULL,
+        seq         INTEGER NOT NULL,
+        name        TEXT    NOT NULL,
+        price_minor INTEGER NOT NULL,
+        currency    TEXT    NOT NULL,
+        PRIMARY KEY (order_id, position, seq),
+        FOREIGN KEY (order_id, position)
+            REFERENCES order_lines(order_id, position) ON DELETE CASCADE
+    );
+
+    CREATE TABLE status_history (
+        order_id    INTEGER NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+        seq         INTEGER NOT NULL,
+        from_status TEXT    NOT NULL,
+        to_status   TEXT    NOT NULL,
+        at_ms       INTEGER NOT NULL,
+        PRIMARY KEY (order_id, seq)
+    );
+
+    CREATE TABLE payment_receipts (
+        order_id       INTEGER PRIMARY KEY REFERENCES orders(id) ON DELETE CASCADE,
+        subtotal_minor INTEGER NOT NULL,
+        discount_minor INTEGER NOT NULL,
+        tax_minor      INTEGER NOT NULL,
+        total_minor    INTEGER NOT NULL,
+        currency       TEXT    NOT NULL,
+        paid_at_ms     INTEGER NOT NULL,
+        coupon_code    TEXT,
+        -- Complete receipt (discount and tax lines) as JSON
+        detail         TEXT    NOT NULL
+    );
+
+    CREATE INDEX status_history_by_time ON status_history(at_ms);
+    CREATE INDEX orders_by_status ON orders(status);
+    "#,
+    // 2: structured pizza specs; `base` and `crust` now hold component names
+    r#"
+    ALTER TABLE order_lines ADD COLUMN base_price_minor INTEGER;
+    ALTER TABLE order_lines ADD COLUMN crust_price_minor INTEGER;
+    ALTER TABLE order_lines ADD COLUMN size TEXT NOT NULL DEFAULT 'medium';
+    ALTER TABLE order_lines ADD COLUMN notes TEXT;
+    "#,
+    // 3: half-and-half toppings
+    r#"
+    ALTER TABLE order_line_toppings ADD COLUMN placement TEXT NOT NULL DEFAULT 'whole';
+    "#,
+    // 4: nutrition labels, as JSON; NULL where a part had none
+    r#"
+    ALTER TABLE order_lines ADD COLUMN base_nutrition TEXT;
+    ALTER TABLE order_lines ADD COLUMN crust_nutrition TEXT;
+    ALTER TABLE order_line_toppings ADD COLUMN nutrition TEXT;
+    "#,
+    // 5: registered customer who placed the order
+    r#"
+    ALTER TABLE orders ADD COLUMN customer_id INTEGER;
+    CREATE INDEX orders_by_customer ON orders(customer_id);
+    "#,
+    // 6: refunds; the full list is also in payment_receipts.detail
+    r#"
+    CREATE TABLE refunds (
+        order_id       INTEGER NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+        seq            INTEGER NOT NULL,
+        amount_minor   INTEGER NOT NULL,
+        currency       TEXT    NOT NULL,
+        refunded_at_ms INTEGER NOT NULL,
+        -- Order line positions refunded, as JSON; empty for an amount refund
+        lines          TEXT    NOT NULL,
+        PRIMARY KEY (order_id, seq)
+    );
+    "#,
+    // 7: registered customers; orders.customer_id refers to these
+    r#"
+    CREATE TABLE customers (
+        id                INTEGER PRIMARY KEY,
+        name              TEXT    NOT NULL,
+        phone             TEXT    NOT NULL UNIQUE,
+        preferred_payment TEXT,
+        -- Delivery addresses as JSON
+        addresses         TEXT    NOT NULL
+    );
+    "#,
+    // 8: loyalty points offered towards the bill
+    r#"
+    ALTER TABLE orders ADD COLUMN redeem_points INTEGER NOT NULL DEFAULT 0;
+    "#,
+];
+
+/// Order store backed by a SQLite database.
+pub struct SqliteOrderRepository {
+    conn: Mutex<Connection>,
+    orders: Mutex<HashMap<u64, order>>,
+}
+
+impl SqliteOrderRepository {
+    /// Open (or create) the database at `path`, migrating it if needed.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, StorageError> {
+        Self::from_connection(Connection::open(path)?)
+    }
+
+    /// Throwaway database, mainly for tests.
+    pub fn open_in_memory() -> Result<Self, StorageError> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(mut conn: Connection) -> Result<Self, StorageError> {
+        conn.pragma_update(None, "foreign_keys", true)?;
+        migrate(&mut conn)?;
+        let orders = load_all(&conn)?
+            .into_iter()
+            .map(|record| (record.id, order::from(record)))
+            .collect();
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+            orders: Mutex::new(orders),
+        })
+    }
+
+    /// Schema version of the open database.
+    pub fn schema_version(&self) -> Result<u32, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn.pragma_query_value(None, "user_version", |row| row.get(0))?)
+    }
+
+    /// Highest order id in the store, to resume id generation after restart.
+    pub fn max_id(&self) -> Option<u64> {
+        self.orders.lock().unwrap().keys().copied().max()
+    }
+
+    fn save(&self, record: &OrderRecord) -> Result<(), StorageError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        write_order(&tx, record)?;
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+/// Customers of a [`CustomerRegistry`](crate::customer::CustomerRegistry)
+/// in the `customers` table; may share a database file with
+/// [`SqliteOrderRepository`].
+pub struct SqliteCustomerStore {
+    conn: Connection,
+}
+
+impl SqliteCustomerStore {
+    /// Open (or create) the database at `path`, migrating it if needed.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, StorageError> {
+        let mut conn = Connection::open(path)?;
+        migrate(&mut conn)?;
+        Ok(Self { conn })
+    }
+}
+
+impl CustomerStore for SqliteCustomerStore {
+    fn load(&mut self) -> Result<Vec<Customer>, StorageError> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, name, phone, preferred_payment, addresses FROM customers ORDER BY id")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, String>(4)?,
+            ))
+        })?;
+
+        let mut customers = Vec::new();
+        for row in rows {
+            let (id, name, phone, payment, addresses) = row?;
+            let corrupt = |reason: String| StorageError::CorruptRow {
+                table: "customers".to_string(),
+                id: id as u64,
+                reason,
+            };
+            customers.push(Customer {
+                id: CustomerId(id as u64),
+                name,
+                phone,
+                addresses: serde_json::from_str(&addresses).map_err(|e| corrupt(e.to_string()))?,
+                preferred_payment: payment.map(|p| from_text(&p)).transpose().map_err(|e| corrupt(e.to_string()))?,
+            });
+        }
+        Ok(customers)
+    }
+
+    fn save(&mut self, customer: &Customer) -> Result<(), StorageError> {
+        self.conn.execute(
+            "INSERT INTO customers (id, name, phone, preferred_payment, addresses)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(id) DO UPDATE SET name = excluded.name, phone = excluded.phone,
+                 preferred_payment = excluded.preferred_payment, addresses = excluded.addresses",
+            params![
+                customer.id.0 as i64,
+                customer.name,
+                customer.phone,
+                customer.preferred_payment.as_ref().map(to_text).transpose()?,
+                serde_json::to_string(&customer.addresses).map_err(|e| StorageError::Serialize(e.to_string()))?,
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+impl OrderRepository for SqliteOrderRepository {
+    fn insert(&self, order: order) -> Result<(), OrderError> {
+        let mut orders = self.orders.lock().unwrap();
+        self.save(&OrderRecord::from(&order))?;
+        orders.insert(order.id.0, order);
+        Ok(())
+    }
+
+    fn get(&self, id: u64) -> Option<order> {
+        self.orders.lock().unwrap().get(&id).cloned()
+    }
+
+    fn list(&self) -> Vec<order> {
+        self.orders.lock().unwrap().values().cloned().collect()
+    }
+
+    fn modify(&self, id: u64, f: &mut dyn FnMut(&mut order)) -> Result<(), OrderError> {
+        let mut orders = self.orders.lock().unwrap();
+        let order = orders.get_mut(&id).ok_or(OrderError::NotFound(id))?;
+        let original = order.clone();
+        let before = OrderRecord::from(&*order);
+        f(order);
+        let after = OrderRecord::from(&*order);
+
+        if after != before
+            && let Err(e) = self.save(&after)
+        {
+            orders.insert(id, original);
+            return Err(e.into());
+        }
+        Ok(())
+    }
+}
+
+fn migrate(conn: &mut Connection) -> Result<(), StorageError> {
+    let version: usize = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    for (n, sql) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(sql)?;
+        tx.pragma_update(None, "user_version", n + 1)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+/// Replace every row belonging to `record.id`.
+fn write_order(tx: &Transaction<'_>, r: &OrderRecord) -> Result<(), StorageError> {
+    let id = r.id as i64;
+    // Child rows go with the parent via ON DELETE CASCADE
+    tx.execute("DELETE FROM orders WHERE id = ?1", params![id])?;
+    tx.execute(
+        "INSERT INTO orders (id, status, priority, created_at_ms, deliver_by_ms, pizzas_ready,
+                             coupon_code, coupon_customer, customer_id, redeem_points)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            id,
+            to_text(&r.status)?,
+            to_text(&r.priority)?,
+            to_ms(r.timestamp),
+            r.deliver_by.map(to_ms),
+            r.pizzas_ready as i64,
+            r.coupon.as_ref().map(|c| c.code.as_str()),
+            r.coupon.as_ref().map(|c| c.customer.as_str()),
+            r.customer_id.map(|c| c as i64),
+            r.redeem_points,
+        ],
+    )?;
+
+    for (position, pizza) in r.pizzas.iter().enumerate() {
+        let price = pizza.cost().map_err(|e| StorageError::Serialize(e.to_string()))?;
+        tx.execute(
+            "INSERT INTO order_lines (order_id, position, description, base, base_price_minor, crust,
+                                      crust_price_minor, size, notes, price_minor, currency, tax_category,
+                                      base_nutrition, crust_nutrition)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            params![
+                id,
+                position as i64,
+                pizza.description(),
+                pizza.base.name,
+                pizza.base.price.minor_units(),
+                pizza.crust.as_ref().map(|c| c.name.as_str()),
+                pizza.crust.as_ref().map(|c| c.price.minor_units()),
+                to_text(&pizza.size)?,
+                pizza.notes,
+                price.minor_units(),
+                price.currency().code(),
+                to_text(&pizza.category)?,
+                to_json(pizza.base.nutrition.as_ref())?,
+                to_json(pizza.crust.as_ref().and_then(|c| c.nutrition.as_ref()))?,
+            ],
+        )?;
+        for (seq, topping) in pizza.toppings.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO order_line_toppings (order_id, position, seq, name, price_minor, currency,
+                                                  placement, nutrition)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    id,
+                    position as i64,
+                    seq as i64,
+                    topping.name,
+                    topping.price.minor_units(),
+                    topping.price.currency().code(),
+                    to_text(&topping.placement)?,
+                    to_json(topping.nutrition.as_ref())?,
+                ],
+            )?;
+        }
+    }
+
+    for (seq, change) in r.history.iter().enumerate() {
+        tx.execute(
+            "INSERT INTO status_history (order_id, seq, from_status, to_status, at_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![id, seq as i64, to_text(&change.from)?, to_text(&change.to)?, to_ms(change.at)],
+        )?;
+    }
+
+    if let Some(receipt) = &r.receipt {
+        let unsaveable = |e: MoneyError| StorageError::Serialize(e.to_string());
+        tx.execute(
+            "INSERT INTO payment_receipts (order_id, subtotal_minor, discount_minor, tax_minor,
+                                           total_minor, currency, paid_at_ms, coupon_code, detail)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                id,
+                receipt.subtotal.minor_units(),
+                receipt.total_discount().map_err(unsaveable)?.minor_units(),
+                receipt.total_tax().map_err(unsaveable)?.minor_units(),
+                receipt.total_amount.minor_units(),
+                receipt.total_amount.currency().code(),
+                receipt.timestamp as i64,
+                receipt.coupon.as_ref().map(|c| c.code.as_str()),
+                serde_json::to_string(receipt).map_err(|e| StorageError::Serialize(e.to_string()))?,
+            ],
+        )?;
+        for (seq, refund) in receipt.refunds.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO refunds (order_id, seq, amount_minor, currency, refunded_at_ms, lines)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    id,
+                    seq as i64,
+                    refund.amount.minor_units(),
+                    refund.amount.currency().code(),
+                    refund.timestamp as i64,
+                    serde_json::to_string(&refund.lines).map_err(|e| StorageError::Serialize(e.to_string()))?,
+                ],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn load_all(conn: &Connection) -> Result<Vec<OrderRecord>, StorageError> {
+    let mut stmt = conn.prepare(
+        "SELECT id, status, priority, created_at_ms, deliver_by_ms, pizzas_ready,
+                coupon_code, coupon_customer, customer_id, redeem_points
+         FROM orders ORDER BY id",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, i64>(3)?,
+            row.get::<_, Option<i64>>(4)?,
+            row.get::<_, i64>(5)?,
+            row.get::<_, Option<String>>(6)?,
+            row.get::<_, Option<String>>(7)?,
+            row.get::<_, Option<i64>>(8)?,
+            row.get::<_, u32>(9)?,
+        ))
+    })?;
+
+    let mut records = Vec::new();
+    for row in rows {
+        let (id, status, priority, created, deliver_by, ready, code, customer, customer_id, redeem_points) = row?;
+        records.push(OrderRecord {
+            id: id as u64,
+            pizzas: load_lines(conn, id)?,
+            status: from_text(&status)?,
+            timestamp: from_ms(created),
+            receipt: load_receipt(conn, id)?,
+            history: load_history(conn, id)?,
+            coupon: code.zip(customer).map(|(code, customer)| CouponClaim { code, customer }),
+            pizzas_ready: ready as usize,
+            priority: from_text(&priority)?,
+            deliver_by: deliver_by.map(from_ms),
+            customer_id: customer_id.map(|c| c as u64),
+            redeem_points,
+        });
+    }
+    Ok(records)
+}
+
+/// Raw `order_lines` row; `base_price` is NULL for rows written before
+/// migration 2.
+struct LineRow {
+    position: i64,
+    description: String,
+    base: Option<String>,
+    base_price: Option<i64>,
+    crust: Option<String>,
+    crust_price: Option<i64>,
+    size: String,
+    notes: Option<String>,
+    price: i64,
+    currency: String,
+    category: String,
+    base_nutrition: Option<String>,
+    crust_nutrition: Option<String>,
+}
+
+fn load_lines(conn: &Connection, id: i64) -> Result<Vec<PizzaSpec>, StorageError> {
+    let mut lines = conn.prepare_cached(
+        "SELECT position, description, base, base_price_minor, crust, crust_price_minor, size, notes,
+                price_minor, currency, tax_category, base_nutrition, crust_nutrition
+         FROM order_lines WHERE order_id = ?1 ORDER BY position",
+    )?;
+    let rows = lines.query_map(params![id], |row| {
+        Ok(LineRow {
+            position: row.get(0)?,
+            description: row.get(1)?,
+            base: row.get(2)?,
+            base_price: row.get(3)?,
+            crust: row.get(4)?,
+            crust_price: row.get(5)?,
+            size: row.get(6)?,
+            notes: row.get(7)?,
+            price: row.get(8)?,
+            currency: row.get(9)?,
+            category: row.get(10)?,
+            base_nutrition: row.get(11)?,
+            crust_nutrition: row.get(12)?,
+        })
+    })?;
+
+    let mut pizzas = Vec::new();
+    for row in rows {
+        let row = row?;
+        let currency = currency_from(&row.currency)?;
+        let mut spec = match (row.base, row.base_price) {
+            (Some(base), Some(price)) => PizzaSpec::new(base, Money::from_minor(price, currency)),
+            // Older rows only kept the finished description and total
+            _ => PizzaSpec::new(row.description, Money::from_minor(row.price, currency)),
+        };
+        spec.base.nutrition = from_json(row.base_nutrition)?;
+        if let (Some(name), Some(price)) = (row.crust, row.crust_price) {
+            let mut crust = PizzaPart::new(name, Money::from_minor(price, currency));
+            crust.nutrition = from_json(row.crust_nutrition)?;
+            spec = spec.with_crust_part(crust);
+        }
+        if row.base_price.is_some() {
+            spec.toppings = load_toppings(conn, id, row.position)?;
+        }
+        spec.size = from_text(&row.size)?;
+        spec.notes = row.notes;
+        spec.category = from_text(&row.category)?;
+        pizzas.push(spec);
+    }
+    Ok(pizzas)
+}
+
+fn load_toppings(conn: &Connection, id: i64, position: i64) -> Result<Vec<PizzaPart>, StorageError> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT name, price_minor, currency, placement, nutrition FROM order_line_toppings
+         WHERE order_id = ?1 AND position = ?2 ORDER BY seq",
+    )?;
+    let rows = stmt.query_map(params![id, position], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, i64>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, String>(3)?,
+            row.get::<_, Option<String>>(4)?,
+        ))
+    })?;
+
+    let mut toppings = Vec::new();
+    for row in rows {
+        let (name, price, currency, placement, nutrition) = row?;
+        let price = Money::from_minor(price, currency_from(&currency)?);
+        let mut part = PizzaPart::new(name, price).with_placement(from_text(&placement)?);
+        part.nutrition = from_json(nutrition)?;
+        toppings.push(part);
+    }
+    Ok(toppings)
+}
+
+fn load_history(conn: &Connection, id: i64) -> Result<Vec<StatusChange>, StorageError> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT from_status, to_status, at_ms FROM status_history
+         WHERE order_id = ?1 ORDER BY seq",
+    )?;
+    let rows = stmt.query_map(params![id], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, i64>(2)?))
+    })?;
+
+    let mut history = Vec::new();
+    for row in rows {
+        let (from, to, at) = row?;
+        history.push(StatusChange {
+            from: from_text(&from)?,
+            to: from_text(&to)?,
+            at: from_ms(at),
+        });
+    }
+    Ok(history)
+}
+
+fn load_receipt(conn: &Connection, id: i64) -> Result<Option<PaymentReceipt>, StorageError> {
+    let detail: Option<String> = conn
+        .prepare_cached("SELECT detail FROM payment_receipts WHERE order_id = ?1")?
+        .query_row(params![id], |row| row.get(0))
+        .optional()?;
+    detail
+        .map(|json| {
+            serde_json::from_str(&json).map_err(|e| StorageError::CorruptRow {
+                table: "payment_receipts".to_string(),
+                id: id as u64,
+                reason: e.to_string(),
+            })
+        })
+        .transpose()
+}
+
+/// Unit enum as its serde name, e.g. `OrderStatus::Paid` → `"Paid"`.
+fn to_text<T: Serialize>(value: &T) -> Result<String, StorageError> {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(s)) => Ok(s),
+        Ok(other) => Err(StorageError::Serialize(format!("expected a name, got {}", other))),
+        Err(e) => Err(StorageError::Serialize(e.to_string())),
+    }
+}
+
+fn from_text<T: DeserializeOwned>(text: &str) -> Result<T, StorageError> {
+    serde_json::from_value(serde_json::Value::String(text.to_string()))
+        .map_err(|e| StorageError::Decode(e.to_string()))
+}
+
+fn to_json<T: Serialize>(value: Option<&T>) -> Result<Option<String>, StorageError> {
+    value
+        .map(|v| serde_json::to_string(v).map_err(|e| StorageError::Serialize(e.to_string())))
+        .transpose()
+}
+
+fn from_json<T: DeserializeOwned>(text: Option<String>) -> Result<Option<T>, StorageError> {
+    text.map(|t| serde_json::from_str(&t).map_err(|e| StorageError::Decode(e.to_string())))
+        .transpose()
+}
+
+fn currency_from(code: &str) -> Result<Currency, StorageError> {
+    Currency::from_code(code).ok_or_else(|| StorageError::Decode(format!("unknown currency {}", code)))
+}
+
+fn to_ms(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as i64)
+}
+
+fn from_ms(ms: i64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_millis(ms.max(0) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::billing::BillingEngine;
+    use crate::customer::{Customer, CustomerId};
+    use crate::order::{OrderPriority, OrderStatus};
+    use crate::pizza::{Cheese, Margherita, Olives, Placement, Size, ThinCrust};
+
+    fn paid_order() -> order {
+        let pizza = Olives::new(Box::new(Cheese::new(Box::new(Margherita::new()))));
+        let special = ThinCrust::new(Margherita::new())
+            .spec()
+            .with_size(Size::Large)
+            .with_topping_on("Jalapenos", Money::inr(9), Placement::Left)
+            .with_notes("no basil");
+        let mut order = order::new(vec![Box::new(pizza) as Box<dyn Pizza>, Box::new(special)])
+            .with_priority(OrderPriority::Vip)
+            .for_customer(&Customer::new(CustomerId(7), "Asha", "9876543210").unwrap())
+            .unwrap();
+        let receipt = BillingEngine::new().generate_receipt(&order).unwrap();
+        order.record_payment(receipt).unwrap();
+        order
+    }
+
+    #[test]
+    fn migrations_run_once() {
+        let path = std::env::temp_dir().join(format!("ferrispizza-migrate-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let repo = SqliteOrderRepository::open(&path).unwrap();
+        assert_eq!(repo.schema_version().unwrap(), MIGRATIONS.len() as u32);
+        drop(repo);
+
+        // Reopening must not re-run CREATE TABLE
+        let repo = SqliteOrderRepository::open(&path).unwrap();
+        assert_eq!(repo.schema_version().unwrap(), MIGRATIONS.len() as u32);
+        drop(repo);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn orders_round_trip_through_the_database() {
+        let path = std::env::temp_dir().join(format!("ferrispizza-roundtrip-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut order = paid_order();
+        order.pay_with_points(25);
+        let id = order.id.0;
+        {
+            let repo = SqliteOrderRepository::open(&path).unwrap();
+            repo.insert(order.clone()).unwrap();
+            repo.modify(id, &mut |o| o.mark_preparing().unwrap()).unwrap();
+        }
+
+        let repo = SqliteOrderRepository::open(&path).unwrap();
+        let stored = repo.get(id).unwrap();
+        assert_eq!(stored.status, OrderStatus::Preparing);
+        assert_eq!(stored.priority, OrderPriority::Vip);
+        assert_eq!(stored.customer, Some(CustomerId(7)));
+        assert_eq!(stored.redeem_points, 25);
+        assert_eq!(stored.history.len(), 2);
+        assert_eq!(stored.receipt, order.receipt);
+        assert_eq!(stored.pizzas[0].description(), "Medium Margherita + Cheese + Olives");
+        assert_eq!(stored.pizzas[0].topping_charges(), order.pizzas[0].topping_charges());
+        assert_eq!(stored.pizzas[1].spec(), order.pizzas[1].spec());
+        assert_eq!(repo.max_id(), Some(id));
+        drop(repo);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn customers_share_the_database() {
+        use crate::customer::{Address, CustomerRegistry, PaymentMethod};
+
+        let path = std::env::temp_dir().join(format!("ferrispizza-customers-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        {
+            let registry = CustomerRegistry::with_store(SqliteCustomerStore::open(&path).unwrap()).unwrap();
+            let asha = registry.register("Asha", "9876543210").unwrap();
+            registry
+                .save(asha.with_address(Address::new("home", "12 MG Road")).with_preferred_payment(PaymentMethod::Upi))
+                .unwrap();
+            registry.register("Ravi", "9123456780").unwrap();
+        }
+
+        let repo = SqliteOrderRepository::open(&path).unwrap();
+        let named: i64 = repo
+            .conn
+            .lock()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM customers WHERE name IN ('Asha', 'Ravi')", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(named, 2);
+        drop(repo);
+
+        let registry = CustomerRegistry::with_store(SqliteCustomerStore::open(&path).unwrap()).unwrap();
+        let asha = registry.lookup("9876543210").unwrap();
+        assert_eq!(asha.addresses[0].text, "12 MG Road");
+        assert_eq!(asha.preferred_payment, Some(PaymentMethod::Upi));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn tables_are_queryable_with_sql() {
+        let repo = SqliteOrderRepository::open_in_memory().unwrap();
+        let order = paid_order();
+        let total = order.receipt.as_ref().unwrap().total_amount.minor_units();
+        let refund = BillingEngine::new()
+            .quote_refund(order.receipt.as_ref().unwrap(), Money::inr(40))
+            .unwrap();
+        let id = order.id.0;
+        repo.insert(order).unwrap();
+        repo.modify(id, &mut |o| {
+            o.mark_preparing().unwrap();
+            o.mark_ready().unwrap();
+            o.mark_completed().unwrap();
+            o.record_refund(refund.clone()).unwrap();
+        })
+        .unwrap();
+        repo.insert(paid_order()).unwrap();
+
+        let conn = repo.conn.lock().unwrap();
+        let (toppings, revenue): (i64, i64) = conn
+            .query_row(
+                "SELECT (SELECT COUNT(*) FROM order_line_toppings),
+                        (SELECT SUM(total_minor) FROM payment_receipts)",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(toppings, 6);
+        assert_eq!(revenue, 2 * total);
+
+        let paid: i64 = conn
+            .query_row("SELECT COUNT(*) FROM status_history WHERE to_status = 'Paid'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(paid, 2);
+
+        let crusts: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM order_lines WHERE base = 'Margherita' AND crust = 'Thin Crust'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(crusts, 2);
+
+        let refunded: i64 = conn
+            .query_row("SELECT SUM(amount_minor) FROM refunds", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(refunded, 4_000);
+    }
+}
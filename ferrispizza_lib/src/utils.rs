@@ -2,4 +2,4 @@ mod file_logger;
 mod id_generator;
 
 pub use file_logger::FileLogger;
-pub use id_generator::IdGenerator;
+pub use id_generator::{IdGenerator, IdSource, UlidGenerator};
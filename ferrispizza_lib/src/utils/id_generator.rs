@@ -1,6 +1,22 @@
 //! Generates unique incremental IDs for orders, invoices, etc.
 
+use std::io;
+use std::sync::Mutex;
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rand::Rng;
+
+use crate::errors::StorageError;
+
+/// Source of unique numeric IDs.
+///
+/// Implementors must be safe to share between threads and must never hand
+/// out the same value twice.
+pub trait IdSource: Send + Sync {
+    /// Return the next unused ID, or fail if none can be issued safely.
+    fn next_value(&self) -> Result<u64, StorageError>;
+}
 
 /// A thread-safe ID generator.
 ///
@@ -18,13 +34,20 @@ pub struct IdGenerator {
 impl IdGenerator {
     /// Creates a new generator starting at 1 with no prefix.
     pub const fn new() -> Self {
+        Self::starting_at(1)
+    }
+
+    /// Creates a new generator whose first ID is `start`.
+    ///
+    /// Useful when resuming after the highest previously issued ID is known.
+    pub const fn starting_at(start: u64) -> Self {
         Self {
-            counter: AtomicU64::new(1),
+            counter: AtomicU64::new(start),
             prefix: None,
         }
     }
 
-    /// Creates a new generator with a string prefix.  
+    /// Creates a new generator with a string prefix.
     /// Example: `IdGenerator::with_prefix("PZ")` → `PZ-1`, `PZ-2`
     pub fn with_prefix(prefix: &str) -> Self {
         Self {
@@ -33,9 +56,14 @@ impl IdGenerator {
         }
     }
 
+    /// Generate the next incremental ID.
+    pub fn next_number(&self) -> u64 {
+        self.counter.fetch_add(1, Ordering::SeqCst)
+    }
+
     /// Generate the next incremental ID as a string.
     pub fn next_id(&self) -> String {
-        let value = self.counter.fetch_add(1, Ordering::SeqCst);
+        let value = self.next_number();
 
         match &self.prefix {
             Some(p) => format!("{}-{}", p, value),
@@ -48,3 +76,118 @@ impl IdGenerator {
         self.counter.store(1, Ordering::SeqCst);
     }
 }
+
+impl Default for IdGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IdSource for IdGenerator {
+    fn next_value(&self) -> Result<u64, StorageError> {
+        Ok(self.next_number())
+    }
+}
+
+/// Crockford's base 32 alphabet, as used by ULIDs.
+const CROCKFORD: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// Time-ordered IDs that need no shared counter or file, for tills that
+/// cannot coordinate.
+///
+/// Numeric IDs hold the milliseconds since the Unix epoch in their high
+/// 48 bits and a per-millisecond random start in the low 16, bumped as
+/// needed so one generator never repeats or goes backwards. [`next_ulid`]
+/// extends such an ID with 64 random bits into a 26-character ULID.
+///
+/// [`next_ulid`]: UlidGenerator::next_ulid
+///
+/// Usage:
+/// ```
+/// use ferrispizza_lib::utils::{IdSource, UlidGenerator};
+/// let ids = UlidGenerator::new();
+/// let first = ids.next_value().unwrap();
+/// assert!(ids.next_value().unwrap() > first);
+/// assert_eq!(ids.next_ulid().unwrap().len(), 26);
+/// ```
+#[derive(Default)]
+pub struct UlidGenerator {
+    last: Mutex<u64>,
+}
+
+impl UlidGenerator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The next ID as a ULID string, e.g. `01J9Z3K4QW0000000000000000`.
+    pub fn next_ulid(&self) -> Result<String, StorageError> {
+        let ulid = (u128::from(self.next_value()?) << 64) | u128::from(rand::rng().random::<u64>());
+        Ok((0..26)
+            .rev()
+            .map(|digit| CROCKFORD[((ulid >> (digit * 5)) & 0x1f) as usize] as char)
+            .collect())
+    }
+}
+
+impl IdSource for UlidGenerator {
+    /// Fails if the system clock is set before 1970.
+    fn next_value(&self) -> Result<u64, StorageError> {
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| StorageError::Io(io::Error::other(e)))?
+            .as_millis() as u64;
+        let candidate = (millis << 16) | u64::from(rand::rng().random::<u16>() >> 1);
+
+        let mut last = self.last.lock().unwrap();
+        *last = candidate.max(*last + 1);
+        Ok(*last)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn ids_are_unique_across_threads() {
+        let ids = Arc::new(IdGenerator::new());
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let ids = Arc::clone(&ids);
+                thread::spawn(move || (0..100).map(|_| ids.next_number()).collect::<Vec<_>>())
+            })
+            .collect();
+
+        let mut seen = HashSet::new();
+        for h in handles {
+            for id in h.join().unwrap() {
+                assert!(seen.insert(id), "duplicate id {}", id);
+            }
+        }
+        assert_eq!(seen.len(), 400);
+    }
+
+    #[test]
+    fn starting_at_resumes_counter() {
+        let ids = IdGenerator::starting_at(42);
+        assert_eq!(ids.next_number(), 42);
+        assert_eq!(ids.next_id(), "43");
+    }
+
+    #[test]
+    fn ulids_are_ordered_and_unique() {
+        let ids = UlidGenerator::new();
+        let values: Vec<u64> = (0..1000).map(|_| ids.next_value().unwrap()).collect();
+        assert!(values.windows(2).all(|w| w[0] < w[1]));
+
+        let a = ids.next_ulid().unwrap();
+        let b = ids.next_ulid().unwrap();
+        assert!(a < b);
+        assert!(a.chars().all(|c| CROCKFORD.contains(&(c as u8))));
+    }
+}
@@ -0,0 +1,65 @@
+use ferrispizza_lib::{
+    order::order,
+    pizza::Margherita,
+    concurrency::{OrderChannel, SharedOrderState}
+};
+
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn test_order_channel_send_receive() {
+    let channel = OrderChannel::new(10);
+
+    let sender = channel.sender();
+
+    let order = order::new(vec![Box::new(Margherita::new())]);
+
+    sender.send(order.clone()).expect("send ok");
+    let received = channel.receive().expect("recv ok");
+
+    assert_eq!(order.id, received.id);
+}
+
+#[test]
+fn test_shared_state_updates() {
+    let state = SharedOrderState::new();
+
+    let order1 = order::new(vec![Box::new(Margherita::new())]);
+    let order2 = order::new(vec![Box::new(Margherita::new())]);
+
+    state.add_order(order1).unwrap();
+    state.add_order(order2).unwrap();
+
+    let orders = state.list_orders();
+
+    assert_eq!(orders.len(), 2);
+}
+
+#[test]
+fn test_concurrent_workers_processing_orders() {
+    let channel = OrderChannel::new(10);
+    let state = SharedOrderState::new();
+
+    let sender = channel.sender();
+    let receiver = channel.clone();
+    let state_clone = state.clone();
+
+    // Worker thread
+    let worker = thread::spawn(move || {
+        for _ in 0..5 {
+            let order = receiver.receive_timeout(Duration::from_secs(2)).expect("order arrives");
+            state_clone.add_order(order).unwrap();
+        }
+    });
+
+    // Send 5 orders
+    for _ in 0..5 {
+        sender.send(order::new(vec![Box::new(Margherita::new())])).unwrap();
+    }
+
+    worker.join().unwrap();
+
+    assert_eq!(state.list_orders().len(), 5);
+    assert_eq!(channel.metrics().depth, 0);
+}